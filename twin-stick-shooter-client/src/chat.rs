@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use twin_stick_shooter_core::net::{ChatMessage, ChatSender, MAX_CHAT_MESSAGE_CHARS};
+use twin_stick_shooter_core::resource::{Input, Time};
+use twin_stick_shooter_core::util::Timer;
+use wasm_bindgen::JsCast;
+use web_sys::{Document, HtmlElement, HtmlInputElement};
+
+/// Canned messages offered to gamepad players, who have no keyboard to type with.
+const QUICK_CHAT_PHRASES: &[&str] = &[
+    "Hello!",
+    "Help!",
+    "Follow me!",
+    "Nice shot!",
+    "Thanks!",
+    "Good game!",
+];
+
+/// Most lines kept in the log.
+const MAX_LINES: usize = 8;
+
+/// How long a line stays visible while the chat is closed.
+const LINE_VISIBLE_SECONDS: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ChatMode {
+    Closed,
+    /// Typing into the text input. Keyboard events go to the input rather than the game.
+    Typing,
+    /// Picking a canned phrase with the gamepad.
+    QuickChat {
+        selection: usize,
+    },
+}
+
+/// Gamepad buttons used only by the chat.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChatButtons {
+    /// Opens and closes quick chat.
+    pub toggle: bool,
+    pub cancel: bool,
+}
+
+struct ChatLine {
+    message: ChatMessage,
+    visible: Timer,
+}
+
+/// The chat overlay: a fading log of recent messages plus the typing and quick chat input modes.
+pub struct Chat {
+    mode: ChatMode,
+    lines: VecDeque<ChatLine>,
+    needs_render: bool,
+
+    prev_buttons: ChatButtons,
+    prev_confirm: bool,
+    prev_dpad_up: bool,
+    prev_dpad_down: bool,
+
+    root: HtmlElement,
+    log: HtmlElement,
+    quick_chat: HtmlElement,
+    input: HtmlInputElement,
+}
+
+impl Chat {
+    pub fn new(document: &Document) -> Chat {
+        let get = |id: &str| -> HtmlElement {
+            document.get_element_by_id(id).unwrap().dyn_into().unwrap()
+        };
+        let input: HtmlInputElement = get("chat-input").dyn_into().unwrap();
+        input.set_max_length(MAX_CHAT_MESSAGE_CHARS as i32);
+
+        Chat {
+            mode: ChatMode::Closed,
+            lines: VecDeque::new(),
+            needs_render: true,
+
+            // Assume buttons were held so a held button doesn't act on the first step.
+            prev_buttons: ChatButtons {
+                toggle: true,
+                cancel: true,
+            },
+            prev_confirm: true,
+            prev_dpad_up: true,
+            prev_dpad_down: true,
+
+            root: get("chat"),
+            log: get("chat-log"),
+            quick_chat: get("chat-quick"),
+            input,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.mode != ChatMode::Closed
+    }
+
+    /// Whether keyboard input currently belongs to the chat rather than the game.
+    pub fn is_typing(&self) -> bool {
+        self.mode == ChatMode::Typing
+    }
+
+    pub fn open_typing(&mut self) {
+        self.mode = ChatMode::Typing;
+        self.input.set_value("");
+        self.needs_render = true;
+        self.render();
+        self.input.focus().unwrap();
+    }
+
+    pub fn close(&mut self) {
+        if self.mode == ChatMode::Typing {
+            self.input.blur().unwrap();
+        }
+        self.mode = ChatMode::Closed;
+        self.needs_render = true;
+    }
+
+    /// Closes the typing mode and returns whatever was typed.
+    pub fn take_typed_text(&mut self) -> String {
+        let text = self.input.value();
+        self.close();
+        text
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.lines.push_back(ChatLine {
+            message,
+            visible: Timer::with_remaining(LINE_VISIBLE_SECONDS),
+        });
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.needs_render = true;
+    }
+
+    /// Ages the log and drives gamepad quick chat. Returns a phrase to send if one was picked.
+    pub fn step(&mut self, time: &Time, input: &Input, buttons: ChatButtons) -> Option<String> {
+        for line in &mut self.lines {
+            if !line.visible.is_elapsed() && line.visible.step_and_is_elapsed(time) {
+                self.needs_render = true;
+            }
+        }
+
+        let toggle_pressed = buttons.toggle && !self.prev_buttons.toggle;
+        let cancel_pressed = buttons.cancel && !self.prev_buttons.cancel;
+        let confirm_pressed = input.confirm && !self.prev_confirm;
+        let up_pressed = input.dpad_up && !self.prev_dpad_up;
+        let down_pressed = input.dpad_down && !self.prev_dpad_down;
+        self.prev_buttons = buttons;
+        self.prev_confirm = input.confirm;
+        self.prev_dpad_up = input.dpad_up;
+        self.prev_dpad_down = input.dpad_down;
+
+        let mut picked = None;
+        match self.mode {
+            ChatMode::Closed => {
+                if toggle_pressed {
+                    self.mode = ChatMode::QuickChat { selection: 0 };
+                    self.needs_render = true;
+                }
+            }
+            ChatMode::Typing => (),
+            ChatMode::QuickChat { selection } => {
+                let n = QUICK_CHAT_PHRASES.len();
+                if toggle_pressed || cancel_pressed {
+                    self.close();
+                } else if confirm_pressed {
+                    picked = Some(QUICK_CHAT_PHRASES[selection].to_string());
+                    self.close();
+                } else if up_pressed || down_pressed {
+                    let selection = if up_pressed {
+                        (selection + n - 1) % n
+                    } else {
+                        (selection + 1) % n
+                    };
+                    self.mode = ChatMode::QuickChat { selection };
+                    self.needs_render = true;
+                }
+            }
+        }
+
+        self.render();
+        picked
+    }
+
+    fn render(&mut self) {
+        if !self.needs_render {
+            return;
+        }
+        self.needs_render = false;
+
+        let document = web_sys::window().unwrap().document().unwrap();
+        self.root
+            .set_class_name(if self.is_open() { "open" } else { "" });
+
+        while let Some(child) = self.log.last_child() {
+            self.log.remove_child(&child).unwrap();
+        }
+        for line in &self.lines {
+            let element: HtmlElement = document.create_element("div").unwrap().dyn_into().unwrap();
+            let class_name = match line.message.sender {
+                ChatSender::System => "line system",
+                ChatSender::Player { .. } => "line",
+            };
+            element.set_class_name(&if line.visible.is_elapsed() {
+                format!("{} stale", class_name)
+            } else {
+                class_name.to_string()
+            });
+            element.set_text_content(Some(&match &line.message.sender {
+                ChatSender::System => line.message.text.clone(),
+                ChatSender::Player { name } => format!("{}: {}", name, line.message.text),
+            }));
+            self.log.append_child(&element).unwrap();
+        }
+
+        while let Some(child) = self.quick_chat.last_child() {
+            self.quick_chat.remove_child(&child).unwrap();
+        }
+        if let ChatMode::QuickChat { selection } = self.mode {
+            for (index, phrase) in QUICK_CHAT_PHRASES.iter().enumerate() {
+                let element: HtmlElement =
+                    document.create_element("div").unwrap().dyn_into().unwrap();
+                element.set_class_name(if index == selection {
+                    "phrase selected"
+                } else {
+                    "phrase"
+                });
+                element.set_text_content(Some(phrase));
+                self.quick_chat.append_child(&element).unwrap();
+            }
+        }
+
+        self.input
+            .style()
+            .set_property(
+                "display",
+                if self.mode == ChatMode::Typing {
+                    "block"
+                } else {
+                    "none"
+                },
+            )
+            .unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use cgmath::num_traits::clamp;
 use cgmath::{EuclideanSpace, InnerSpace, Transform};
 use js_sys::Array;
-use legion::IntoQuery;
+use legion::{EntityStore, IntoQuery};
 use twin_stick_shooter_core::collision::Shape;
 use twin_stick_shooter_core::game::Game;
 use twin_stick_shooter_core::health::HealthComponent;
@@ -12,10 +12,11 @@ use twin_stick_shooter_core::physics::VelocityComponent;
 use twin_stick_shooter_core::player::PlayerComponent;
 use twin_stick_shooter_core::resource::Input;
 use twin_stick_shooter_core::util::clamp_magnitude;
-use twin_stick_shooter_core::{translation, Mat3, Pt2};
+use twin_stick_shooter_core::{rotation, translation, Mat3, Pt2};
 use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
+use crate::action;
 use crate::model::ModelManager;
 use crate::DebugState;
 
@@ -30,7 +31,7 @@ pub fn draw(
     let w = canvas.width() as f64;
     let h = canvas.height() as f64;
     ctx.reset_transform().unwrap();
-    ctx.set_fill_style(&JsValue::from_str("#000"));
+    ctx.set_fill_style(&JsValue::from_str(&game.ambient().background));
     ctx.fill_rect(0.0, 0.0, w, h);
     ctx.translate(0.5 * w, 0.5 * h).unwrap();
 
@@ -44,6 +45,27 @@ pub fn draw(
         draw_models(ctx, model_manager, game);
         draw_players(ctx, game, input);
     }
+
+    draw_jump_flash(ctx, w, h, game);
+}
+
+/// Washes the screen out to white as the local player's jump drive spools up.
+fn draw_jump_flash(ctx: &CanvasRenderingContext2d, w: f64, h: f64, game: &Game) {
+    let progress = action::local_player(game)
+        .and_then(|player| game.world().entry_ref(player).ok())
+        .and_then(|entry| {
+            entry
+                .get_component::<PlayerComponent>()
+                .ok()
+                .and_then(|player| player.jumping.as_ref().map(|jump| jump.progress()))
+        });
+    if let Some(progress) = progress {
+        ctx.reset_transform().unwrap();
+        ctx.set_global_alpha(progress as f64);
+        ctx.set_fill_style(&JsValue::from_str("#fff"));
+        ctx.fill_rect(0.0, 0.0, w, h);
+        ctx.set_global_alpha(1.0);
+    }
 }
 
 fn draw_players(ctx: &CanvasRenderingContext2d, game: &Game, input: &Input) {
@@ -58,7 +80,7 @@ fn draw_players(ctx: &CanvasRenderingContext2d, game: &Game, input: &Input) {
     ) in
         <(&InterpolateComponent, &VelocityComponent, &PlayerComponent)>::query().iter(game.world())
     {
-        if player.docked_to.is_some() {
+        if player.docked_to.is_some() || !player.is_alive() {
             continue;
         }
 
@@ -135,6 +157,7 @@ fn draw_models(ctx: &CanvasRenderingContext2d, model_manager: &ModelManager, gam
     for (
         &InterpolateComponent {
             interpolated_pos: pos,
+            interpolated_rot: rot,
             ..
         },
         model,
@@ -146,8 +169,19 @@ fn draw_models(ctx: &CanvasRenderingContext2d, model_manager: &ModelManager, gam
     )>::query()
     .iter(game.world())
     {
+        // Blink while invulnerable.
+        if let Some(health) = health {
+            if health.is_invulnerable() && (health.invulnerable.remaining() * 10.0) as u32 % 2 == 1
+            {
+                continue;
+            }
+        }
+
         ctx.save();
-        let xform = <Mat3 as Transform<Pt2>>::concat(&translation(pos.to_vec()), &model.transform);
+        let xform = <Mat3 as Transform<Pt2>>::concat(
+            &(translation(pos.to_vec()) * rotation(rot)),
+            &model.transform,
+        );
         ctx.transform(
             xform.x.x as f64,
             xform.x.y as f64,
@@ -171,29 +205,14 @@ fn debug_draw_hurtboxes(ctx: &CanvasRenderingContext2d, game: &Game) {
     for (
         &InterpolateComponent {
             interpolated_pos: pos,
+            interpolated_rot: rot,
             ..
         },
         hurtbox,
     ) in <(&InterpolateComponent, &HurtboxComponent)>::query().iter(game.world())
     {
-        let fill_style = JsValue::from_str(&"#44f8");
-        match &hurtbox.shape {
-            Shape::Circle(circle) => {
-                ctx.begin_path();
-                ctx.arc(
-                    pos.x as f64,
-                    pos.y as f64,
-                    circle.radius as f64,
-                    0.0,
-                    std::f64::consts::TAU,
-                )
-                .unwrap();
-                ctx.close_path();
-                ctx.set_fill_style(&fill_style);
-                ctx.fill();
-            }
-            _ => (),
-        }
+        let style = JsValue::from_str(&"#44f8");
+        debug_draw_shape(ctx, &hurtbox.shape, pos, rot, &style);
     }
 }
 
@@ -201,33 +220,77 @@ fn debug_draw_hitboxes(ctx: &CanvasRenderingContext2d, game: &Game) {
     for (
         &InterpolateComponent {
             interpolated_pos: pos,
+            interpolated_rot: rot,
             ..
         },
         hitbox,
     ) in <(&InterpolateComponent, &HitboxComponent)>::query().iter(game.world())
     {
-        let fill_style = JsValue::from_str(match hitbox.effect {
+        let style = JsValue::from_str(match hitbox.effect {
             HitboxEffect::None => "#8888",
             HitboxEffect::Damage(_) => "#f448",
-            HitboxEffect::StationDock => "#f4f8",
+            HitboxEffect::Interact(_) => "#f4f8",
+            HitboxEffect::Pickup(_) => "#4f48",
         });
-        match &hitbox.shape {
-            Shape::Circle(circle) => {
-                ctx.begin_path();
-                ctx.arc(
-                    pos.x as f64,
-                    pos.y as f64,
-                    circle.radius as f64,
-                    0.0,
-                    std::f64::consts::TAU,
-                )
-                .unwrap();
-                ctx.close_path();
+        debug_draw_shape(ctx, &hitbox.shape, pos, rot, &style);
+    }
+}
 
-                ctx.set_fill_style(&fill_style);
-                ctx.fill();
+/// Fills `shape` at `pos`, turned by `rot`. Lines and particles, which have no area, are stroked
+/// instead.
+fn debug_draw_shape(
+    ctx: &CanvasRenderingContext2d,
+    shape: &Shape,
+    pos: Pt2,
+    rot: f32,
+    style: &JsValue,
+) {
+    ctx.save();
+    ctx.translate(pos.x as f64, pos.y as f64).unwrap();
+    ctx.rotate(rot as f64).unwrap();
+    ctx.begin_path();
+    match shape {
+        Shape::Particle(_) => {
+            ctx.arc(0.0, 0.0, 1.0, 0.0, std::f64::consts::TAU).unwrap();
+        }
+        Shape::Line(line) => {
+            ctx.move_to(line.origin.x as f64, line.origin.y as f64);
+            ctx.line_to(line.dest.x as f64, line.dest.y as f64);
+        }
+        Shape::Circle(circle) => {
+            ctx.arc(0.0, 0.0, circle.radius as f64, 0.0, std::f64::consts::TAU)
+                .unwrap();
+        }
+        Shape::Rectangle(rectangle) => {
+            let half_dim = rectangle.half_dim();
+            ctx.rect(
+                -half_dim.x as f64,
+                -half_dim.y as f64,
+                2.0 * half_dim.x as f64,
+                2.0 * half_dim.y as f64,
+            );
+        }
+        Shape::Square(square) => {
+            let half_dim = square.half_dim() as f64;
+            ctx.rect(-half_dim, -half_dim, 2.0 * half_dim, 2.0 * half_dim);
+        }
+        Shape::ConvexPolygon(polygon) => {
+            for vertex in &polygon.vertices {
+                ctx.line_to(vertex.x as f64, vertex.y as f64);
             }
-            _ => (),
         }
     }
+    ctx.close_path();
+    match shape {
+        Shape::Particle(_) | Shape::Line(_) => {
+            ctx.set_stroke_style(style);
+            ctx.set_line_width(2.0);
+            ctx.stroke();
+        }
+        _ => {
+            ctx.set_fill_style(style);
+            ctx.fill();
+        }
+    }
+    ctx.restore();
 }
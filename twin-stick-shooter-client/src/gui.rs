@@ -10,6 +10,7 @@ use web_sys::HtmlElement;
 
 pub mod in_game;
 pub mod station;
+pub mod summary;
 pub mod title;
 
 const BUTTON_COOLDOWN: f32 = 0.25;
@@ -252,7 +253,26 @@ impl InnerGuiState {
         )
         .unwrap();
 
-        for (index, item) in self.menu.items().iter().copied().enumerate() {
+        let details = self.menu.details();
+        if !details.is_empty() {
+            gui.append_child(
+                {
+                    let element: HtmlElement =
+                        document.create_element("div").unwrap().dyn_into().unwrap();
+                    element.set_class_name("details");
+                    for line in details {
+                        let line_element = document.create_element("div").unwrap();
+                        line_element.set_text_content(Some(line));
+                        element.append_child(&line_element).unwrap();
+                    }
+                    element
+                }
+                .as_ref(),
+            )
+            .unwrap();
+        }
+
+        for (index, item) in self.menu.items().into_iter().enumerate() {
             gui.append_child(
                 {
                     let element: HtmlElement =
@@ -382,6 +402,12 @@ impl InnerGuiState {
                 self.replace_with(inner, menu);
                 true
             }
+            GuiResult::Refresh => {
+                let selection = self.selection;
+                self.actuate(inner);
+                self.set_selection(selection.filter(|&index| index < self.items.len()));
+                true
+            }
         }
     }
 }
@@ -464,7 +490,11 @@ pub enum HeadingStyle {
 
 pub trait Menu: Debug {
     fn heading(&self) -> Option<Heading<'_>>;
-    fn items(&self) -> &[&str];
+    fn items(&self) -> Vec<&str>;
+    /// Lines of information shown between the heading and the items.
+    fn details(&self) -> Vec<&str> {
+        vec![]
+    }
     fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult;
     fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult;
 }
@@ -474,5 +504,7 @@ pub trait Menu: Debug {
 pub enum GuiResult {
     Ok,
     ReplaceMenu(Box<dyn Menu>),
+    /// The menu's contents changed. Redraw it, keeping the selection.
+    Refresh,
     // TODO: deny option with animation and sound
 }
@@ -2,6 +2,7 @@ use twin_stick_shooter_core::game::Game;
 
 use crate::gui::title::TitleMenu;
 use crate::gui::{GuiResult, Heading, HeadingStyle, Menu};
+use crate::profile;
 
 #[derive(Debug)]
 pub struct RunningInGameMenu;
@@ -11,8 +12,8 @@ impl Menu for RunningInGameMenu {
         None
     }
 
-    fn items(&self) -> &[&str] {
-        &[]
+    fn items(&self) -> Vec<&str> {
+        vec![]
     }
 
     fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
@@ -36,8 +37,8 @@ impl Menu for PausedInGameMenu {
         })
     }
 
-    fn items(&self) -> &[&str] {
-        &["Continue", "Quit"]
+    fn items(&self) -> Vec<&str> {
+        vec!["Continue", "Quit"]
     }
 
     fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
@@ -64,8 +65,8 @@ impl Menu for QuitGameMenu {
         })
     }
 
-    fn items(&self) -> &[&str] {
-        &["No", "Yes"]
+    fn items(&self) -> Vec<&str> {
+        vec!["No", "Yes"]
     }
 
     fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
@@ -76,6 +77,7 @@ impl Menu for QuitGameMenu {
         match index {
             0 => GuiResult::ReplaceMenu(Box::new(PausedInGameMenu)),
             1 => {
+                profile::save(game);
                 game.reset();
                 GuiResult::ReplaceMenu(Box::new(TitleMenu))
             }
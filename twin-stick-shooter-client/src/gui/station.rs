@@ -1,31 +1,446 @@
+use legion::{Entity, EntityStore};
 use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::module::{LoadoutComponent, ModuleDefs};
+use twin_stick_shooter_core::player::PlayerComponent;
+use twin_stick_shooter_core::ship::ShipSystemsComponent;
+use twin_stick_shooter_core::station::{self, Catalog};
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
 
 use crate::action;
 use crate::gui::{GuiResult, Heading, HeadingStyle, Menu};
 
 use super::in_game::RunningInGameMenu;
 
+/// What every station screen shows: the ship's condition, what it carries, and how the last
+/// transaction went.
 #[derive(Debug)]
-pub struct StationDockedMenu;
+struct Status {
+    player: Entity,
+    catalog: Catalog,
+    message: Option<String>,
+    lines: Vec<String>,
+}
+
+impl Status {
+    /// The local player's status, or `None` if the player is gone. A player can die or be replaced
+    /// by a snapshot in the same frame it docks.
+    fn new(game: &Game) -> Option<Status> {
+        let mut status = Status {
+            player: action::local_player(game)?,
+            catalog: Catalog::builtin(),
+            message: None,
+            lines: vec![],
+        };
+        status.refresh(game);
+        Some(status)
+    }
+
+    /// The name the shop uses for an item, or else its ID.
+    fn item_name<'a>(&'a self, item: &'a str) -> &'a str {
+        self.catalog
+            .get(item)
+            .map(|offer| offer.name.as_str())
+            .unwrap_or(item)
+    }
+
+    fn refresh(&mut self, game: &Game) {
+        let entry = match game.world().entry_ref(self.player) {
+            Ok(entry) => entry,
+            Err(_) => return,
+        };
+        let mut lines = vec![];
+
+        let (hull, max_hull) = entry
+            .get_component::<HealthComponent>()
+            .map(|health| (health.health, health.max_health))
+            .unwrap_or((0.0, 0.0));
+        let shield = match entry.get_component::<ShipSystemsComponent>() {
+            Ok(ship) => format!(
+                "   Shield {:.0}/{:.0}   Reactor {:.0}/s",
+                ship.shield, ship.shield_capacity, ship.reactor_output
+            ),
+            Err(_) => String::new(),
+        };
+        lines.push(format!("Hull {:.0}/{:.0}{}", hull, max_hull, shield));
+        if let Ok(weapon) = entry.get_component::<WeaponComponent>() {
+            lines.push(format!("Weapon: {}", self.item_name(&weapon.def.name)));
+        }
+        if let Ok(loadout) = entry.get_component::<LoadoutComponent>() {
+            let modules: Vec<String> = loadout
+                .slots()
+                .iter()
+                .map(|(slot, installed)| match installed {
+                    Some(module) => format!("{:?}: {}", slot, self.item_name(module)),
+                    None => format!("{:?}: —", slot),
+                })
+                .collect();
+            lines.push(modules.join("   "));
+            let stats = loadout.stats();
+            lines.push(format!(
+                "Speed {:.0}   Boost {:.0}   Fire rate ×{:.2}",
+                stats.max_speed,
+                stats.boost_speed,
+                1.0 / stats.weapon_cooldown
+            ));
+        }
+
+        if let Ok(player) = entry.get_component::<PlayerComponent>() {
+            let inventory = &player.inventory;
+            lines.push(format!(
+                "Cores {}   Scrap {}",
+                inventory.count(Resource::Cores),
+                inventory.count(Resource::Scrap)
+            ));
+            let cargo: Vec<String> = inventory
+                .items()
+                .iter()
+                .map(|stack| format!("{} ×{}", self.item_name(&stack.item), stack.count))
+                .collect();
+            lines.push(format!(
+                "Cargo ({}/{}): {}",
+                inventory.items().len(),
+                inventory.item_slots,
+                if cargo.is_empty() {
+                    "empty".to_string()
+                } else {
+                    cargo.join(", ")
+                }
+            ));
+        }
+
+        if let Some(message) = &self.message {
+            lines.push(message.clone());
+        }
+        self.lines = lines;
+    }
+
+    /// Each kind of item in the player's inventory, once.
+    fn cargo(&self, game: &Game) -> Vec<String> {
+        let mut cargo: Vec<String> = vec![];
+        if let Ok(entry) = game.world().entry_ref(self.player) {
+            if let Ok(player) = entry.get_component::<PlayerComponent>() {
+                for stack in player.inventory.items() {
+                    if !cargo.contains(&stack.item) {
+                        cargo.push(stack.item.clone());
+                    }
+                }
+            }
+        }
+        cargo
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.lines.iter().map(String::as_str).collect()
+    }
+
+    /// Notes how a transaction went and redraws.
+    fn report(&mut self, game: &Game, message: String) -> GuiResult {
+        self.message = Some(message);
+        self.refresh(game);
+        GuiResult::Refresh
+    }
+}
+
+fn station_heading(text: &str) -> Option<Heading<'_>> {
+    Some(Heading {
+        style: HeadingStyle::Regular,
+        text,
+    })
+}
+
+#[derive(Debug)]
+pub struct StationDockedMenu {
+    status: Status,
+    items: Vec<String>,
+}
+
+impl StationDockedMenu {
+    pub fn new(game: &Game) -> Option<StationDockedMenu> {
+        let mut menu = StationDockedMenu {
+            status: Status::new(game)?,
+            items: vec![],
+        };
+        menu.refresh_items(game);
+        Some(menu)
+    }
+
+    fn refresh_items(&mut self, game: &Game) {
+        let repair = station::repair_price(game.world(), self.status.player);
+        self.items = vec![
+            format!("Repair ({})", repair),
+            "Shop".to_string(),
+            "Sell".to_string(),
+            "Equip".to_string(),
+            "Launch".to_string(),
+        ];
+    }
+}
 
 impl Menu for StationDockedMenu {
     fn heading(&self) -> Option<Heading<'_>> {
-        Some(Heading {
-            style: HeadingStyle::Regular,
-            text: "Docked at Station",
-        })
+        station_heading("Docked at Station")
     }
 
-    fn items(&self) -> &[&str] {
-        &["Launch"]
+    fn items(&self) -> Vec<&str> {
+        self.items.iter().map(String::as_str).collect()
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.status.details()
     }
 
     fn on_start_pressed(&mut self, _game: &mut Game) -> GuiResult {
         GuiResult::Ok
     }
 
-    fn invoke_item(&mut self, _index: usize, game: &mut Game) -> GuiResult {
-        action::launch_from_station(game);
-        GuiResult::ReplaceMenu(Box::new(RunningInGameMenu))
+    fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult {
+        match index {
+            0 => {
+                let message = match station::repair(game.world_mut(), self.status.player) {
+                    Ok(price) => format!("Repaired for {}.", price),
+                    Err(err) => format!("Can't repair: {}.", err),
+                };
+                self.refresh_items(game);
+                self.status.report(game, message)
+            }
+            1 => GuiResult::ReplaceMenu(or_in_game(ShopMenu::new(game))),
+            2 => GuiResult::ReplaceMenu(or_in_game(SellMenu::new(game))),
+            3 => GuiResult::ReplaceMenu(or_in_game(EquipMenu::new(game))),
+            4 => {
+                action::launch_from_station(game);
+                GuiResult::ReplaceMenu(Box::new(RunningInGameMenu))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The station's main menu, or the in-game menu if there's no player left to dock.
+pub fn docked_menu(game: &Game) -> Box<dyn Menu> {
+    or_in_game(StationDockedMenu::new(game))
+}
+
+fn or_in_game<M: Menu + 'static>(menu: Option<M>) -> Box<dyn Menu> {
+    match menu {
+        Some(menu) => Box::new(menu),
+        None => Box::new(RunningInGameMenu),
+    }
+}
+
+fn back_to_station(game: &Game) -> GuiResult {
+    GuiResult::ReplaceMenu(docked_menu(game))
+}
+
+#[derive(Debug)]
+pub struct ShopMenu {
+    status: Status,
+    items: Vec<String>,
+}
+
+impl ShopMenu {
+    pub fn new(game: &Game) -> Option<ShopMenu> {
+        let status = Status::new(game)?;
+        let items = status
+            .catalog
+            .offers()
+            .iter()
+            .map(|offer| format!("{} ({})", offer.name, offer.price))
+            .chain(Some("Back".to_string()))
+            .collect();
+        Some(ShopMenu { status, items })
+    }
+}
+
+impl Menu for ShopMenu {
+    fn heading(&self) -> Option<Heading<'_>> {
+        station_heading("Shop")
+    }
+
+    fn items(&self) -> Vec<&str> {
+        self.items.iter().map(String::as_str).collect()
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.status.details()
+    }
+
+    fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
+        back_to_station(game)
+    }
+
+    fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult {
+        let offer = match self.status.catalog.offers().get(index) {
+            Some(offer) => offer.clone(),
+            None => return back_to_station(game),
+        };
+        let message = match station::buy(
+            game.world_mut(),
+            self.status.player,
+            &self.status.catalog,
+            &offer.item,
+        ) {
+            Ok(()) => format!("Bought {} for {}.", offer.name, offer.price),
+            Err(err) => format!("Can't buy {}: {}.", offer.name, err),
+        };
+        self.status.report(game, message)
+    }
+}
+
+#[derive(Debug)]
+pub struct SellMenu {
+    status: Status,
+    /// The sellable items in the player's inventory, in menu order.
+    sellable: Vec<String>,
+    items: Vec<String>,
+}
+
+impl SellMenu {
+    pub fn new(game: &Game) -> Option<SellMenu> {
+        let mut menu = SellMenu {
+            status: Status::new(game)?,
+            sellable: vec![],
+            items: vec![],
+        };
+        menu.refresh_items(game);
+        Some(menu)
+    }
+
+    fn refresh_items(&mut self, game: &Game) {
+        let mut sellable = self.status.cargo(game);
+        sellable.retain(|item| self.status.catalog.get(item).is_some());
+
+        self.items = sellable
+            .iter()
+            .map(|item| {
+                let offer = self.status.catalog.get(item).unwrap();
+                format!("{} ({})", offer.name, offer.sell_price())
+            })
+            .chain(Some("Back".to_string()))
+            .collect();
+        self.sellable = sellable;
+    }
+}
+
+impl Menu for SellMenu {
+    fn heading(&self) -> Option<Heading<'_>> {
+        station_heading("Sell")
+    }
+
+    fn items(&self) -> Vec<&str> {
+        self.items.iter().map(String::as_str).collect()
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.status.details()
+    }
+
+    fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
+        back_to_station(game)
+    }
+
+    fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult {
+        let item = match self.sellable.get(index) {
+            Some(item) => item.clone(),
+            None => return back_to_station(game),
+        };
+        let name = self.status.item_name(&item).to_string();
+        let message = match station::sell(
+            game.world_mut(),
+            self.status.player,
+            &self.status.catalog,
+            &item,
+        ) {
+            Ok(price) => format!("Sold {} for {}.", name, price),
+            Err(err) => format!("Can't sell {}: {}.", name, err),
+        };
+        self.refresh_items(game);
+        self.status.report(game, message)
+    }
+}
+
+#[derive(Debug)]
+pub struct EquipMenu {
+    status: Status,
+    weapon_defs: WeaponDefs,
+    module_defs: ModuleDefs,
+    /// The weapons and modules in the player's inventory, in menu order.
+    equippable: Vec<String>,
+    items: Vec<String>,
+}
+
+impl EquipMenu {
+    pub fn new(game: &Game) -> Option<EquipMenu> {
+        let mut menu = EquipMenu {
+            status: Status::new(game)?,
+            weapon_defs: WeaponDefs::builtin(),
+            module_defs: ModuleDefs::builtin(),
+            equippable: vec![],
+            items: vec![],
+        };
+        menu.refresh_items(game);
+        Some(menu)
+    }
+
+    fn refresh_items(&mut self, game: &Game) {
+        let mut equippable = self.status.cargo(game);
+        equippable.retain(|item| {
+            self.weapon_defs.get(item).is_some() || self.module_defs.get(item).is_some()
+        });
+
+        self.items = equippable
+            .iter()
+            .map(|item| self.status.item_name(item).to_string())
+            .chain(Some("Back".to_string()))
+            .collect();
+        self.equippable = equippable;
+    }
+}
+
+impl Menu for EquipMenu {
+    fn heading(&self) -> Option<Heading<'_>> {
+        station_heading("Equip")
+    }
+
+    fn items(&self) -> Vec<&str> {
+        self.items.iter().map(String::as_str).collect()
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.status.details()
+    }
+
+    fn on_start_pressed(&mut self, game: &mut Game) -> GuiResult {
+        back_to_station(game)
+    }
+
+    fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult {
+        let item = match self.equippable.get(index) {
+            Some(item) => item.clone(),
+            None => return back_to_station(game),
+        };
+        let name = self.status.item_name(&item).to_string();
+        let result = if self.module_defs.get(&item).is_some() {
+            station::install(
+                game.world_mut(),
+                self.status.player,
+                &self.module_defs,
+                &item,
+            )
+        } else {
+            station::equip(
+                game.world_mut(),
+                self.status.player,
+                &self.weapon_defs,
+                &item,
+            )
+        };
+        let message = match result {
+            Ok(()) => format!("Equipped {}.", name),
+            Err(err) => format!("Can't equip {}: {}.", name, err),
+        };
+        self.refresh_items(game);
+        self.status.report(game, message)
     }
 }
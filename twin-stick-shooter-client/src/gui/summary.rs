@@ -0,0 +1,94 @@
+use legion::EntityStore;
+use twin_stick_shooter_core::enemy::EnemyKind;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::score::ScoreComponent;
+
+use crate::action;
+use crate::gui::in_game::RunningInGameMenu;
+use crate::gui::title::TitleMenu;
+use crate::gui::{GuiResult, Heading, HeadingStyle, Menu};
+
+/// Shown when a run ends, win or lose: the final score and how the run went.
+#[derive(Debug)]
+pub struct RunSummaryMenu {
+    title: &'static str,
+    lines: Vec<String>,
+}
+
+impl RunSummaryMenu {
+    pub fn new(game: &Game, title: &'static str) -> RunSummaryMenu {
+        let mut lines = vec![];
+        let score = action::local_player(game)
+            .and_then(|player| game.world().entry_ref(player).ok())
+            .and_then(|entry| entry.get_component::<ScoreComponent>().ok().cloned());
+        if let Some(ScoreComponent { score, stats, .. }) = score {
+            let seconds = stats.time_survived as u32;
+            lines.push(format!("Score {}", score));
+            lines.push(format!(
+                "Reached wave {}   Survived {}:{:02}",
+                game.wave_director().wave(),
+                seconds / 60,
+                seconds % 60
+            ));
+            let kills: Vec<String> = [
+                EnemyKind::Sniper,
+                EnemyKind::Charger,
+                EnemyKind::Dreadnought,
+            ]
+            .iter()
+            .map(|kind| format!("{:?} {}", kind, stats.kills.get(kind).copied().unwrap_or(0)))
+            .collect();
+            lines.push(format!(
+                "Kills {} ({})",
+                stats.total_kills(),
+                kills.join(", ")
+            ));
+            lines.push(format!(
+                "Shots {}   Hits {}   Accuracy {:.0}%",
+                stats.shots_fired,
+                stats.hits,
+                stats.accuracy() * 100.0
+            ));
+            lines.push(format!(
+                "Damage dealt {:.0}   Damage taken {:.0}",
+                stats.damage_dealt, stats.damage_taken
+            ));
+        }
+        RunSummaryMenu { title, lines }
+    }
+}
+
+impl Menu for RunSummaryMenu {
+    fn heading(&self) -> Option<Heading<'_>> {
+        Some(Heading {
+            style: HeadingStyle::Regular,
+            text: self.title,
+        })
+    }
+
+    fn items(&self) -> Vec<&str> {
+        vec!["Play Again", "Title Screen"]
+    }
+
+    fn details(&self) -> Vec<&str> {
+        self.lines.iter().map(String::as_str).collect()
+    }
+
+    fn on_start_pressed(&mut self, _game: &mut Game) -> GuiResult {
+        GuiResult::Ok
+    }
+
+    fn invoke_item(&mut self, index: usize, game: &mut Game) -> GuiResult {
+        match index {
+            0 => {
+                action::create_game(game);
+                GuiResult::ReplaceMenu(Box::new(RunningInGameMenu))
+            }
+            1 => {
+                game.reset();
+                GuiResult::ReplaceMenu(Box::new(TitleMenu))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -17,8 +17,8 @@ impl Menu for TitleMenu {
         })
     }
 
-    fn items(&self) -> &[&str] {
-        &["New Game"]
+    fn items(&self) -> Vec<&str> {
+        vec!["New Game"]
     }
 
     fn on_start_pressed(&mut self, _game: &mut Game) -> GuiResult {
@@ -0,0 +1,134 @@
+use cgmath::num_traits::clamp;
+use legion::{Entity, EntityStore, IntoQuery};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::player::{PlayerComponent, PLAYER_LIVES};
+use twin_stick_shooter_core::resource::Time;
+use twin_stick_shooter_core::score::ScoreComponent;
+use twin_stick_shooter_core::ship::ShipSystemsComponent;
+use twin_stick_shooter_core::util::Timer;
+use twin_stick_shooter_core::weapon::AltWeaponComponent;
+use web_sys::Document;
+
+const BAR_CELLS: usize = 10;
+
+/// How long an announcement stays up before fading.
+const ANNOUNCEMENT_SECONDS: f32 = 3.0;
+
+/// Renders a fraction from zero to one as a text gauge like `[██████────]`.
+fn bar(fraction: f32) -> String {
+    let filled = (clamp(fraction, 0.0, 1.0) * BAR_CELLS as f32).round() as usize;
+    format!("[{}{}]", "█".repeat(filled), "─".repeat(BAR_CELLS - filled))
+}
+
+fn set_text(document: &Document, id: &str, text: &str) {
+    if let Some(element) = document.get_element_by_id(id) {
+        if element.text_content().as_deref() != Some(text) {
+            element.set_text_content(Some(text));
+        }
+    }
+}
+
+/// A short message shown large in the middle of the screen, like "Wave 3".
+#[derive(Debug)]
+pub struct Announcement {
+    text: String,
+    visible: Timer,
+}
+
+impl Default for Announcement {
+    fn default() -> Self {
+        Announcement {
+            text: String::new(),
+            visible: Timer::elapsed(),
+        }
+    }
+}
+
+impl Announcement {
+    pub fn show(&mut self, text: String) {
+        self.text = text;
+        self.visible.reset(ANNOUNCEMENT_SECONDS);
+    }
+
+    pub fn step(&mut self, time: &Time) {
+        self.visible.step(time);
+    }
+
+    pub fn update(&self, document: &Document) {
+        set_text(document, "hud-announcement", &self.text);
+        if let Some(element) = document.get_element_by_id("hud-announcement") {
+            element.set_class_name(if self.visible.is_elapsed() {
+                "stale"
+            } else {
+                ""
+            });
+        }
+    }
+}
+
+/// Updates the resource counters from a player's inventory.
+pub fn update_inventory(document: &Document, game: &Game, player: Entity) {
+    let entry = match game.world().entry_ref(player) {
+        Ok(entry) => entry,
+        Err(_) => return,
+    };
+    if let Ok(player) = entry.get_component::<PlayerComponent>() {
+        let inventory = &player.inventory;
+        set_text(
+            document,
+            "hud-resources-cores",
+            &inventory.count(Resource::Cores).to_string(),
+        );
+        set_text(
+            document,
+            "hud-resources-scrap",
+            &inventory.count(Resource::Scrap).to_string(),
+        );
+    }
+}
+
+/// Updates the HUD from the local player's state.
+pub fn update(document: &Document, game: &Game) {
+    let charge = <(&PlayerComponent, &AltWeaponComponent)>::query()
+        .iter(game.world())
+        .next()
+        .map(|(_, AltWeaponComponent(weapon))| weapon.charge_fraction())
+        .unwrap_or(0.0);
+    set_text(document, "hud-alt-weapon-charge", &bar(charge));
+
+    if let Some((player, ship, health)) =
+        <(&PlayerComponent, &ShipSystemsComponent, &HealthComponent)>::query()
+            .iter(game.world())
+            .next()
+    {
+        set_text(document, "hud-systems-power", &bar(ship.load));
+        set_text(
+            document,
+            "hud-systems-battery",
+            &bar(ship.battery_fraction()),
+        );
+        set_text(document, "hud-systems-shield", &bar(ship.shield_fraction()));
+        let lives: Vec<&str> = (0..PLAYER_LIVES)
+            .map(|i| if i < player.lives { "●" } else { "◌" })
+            .collect();
+        set_text(
+            document,
+            "hud-systems-hull",
+            &format!(
+                "{} {}",
+                bar(health.health / health.max_health),
+                lives.join(" ")
+            ),
+        );
+    }
+
+    if let Some((_, score)) = <(&PlayerComponent, &ScoreComponent)>::query()
+        .iter(game.world())
+        .next()
+    {
+        set_text(document, "hud-score-score", &score.score.to_string());
+        set_text(document, "hud-score-combo", &format!("×{}", score.combo));
+    }
+}
@@ -1,11 +1,16 @@
 use cgmath::vec2;
 use gui::in_game::RunningInGameMenu;
-use gui::station::StationDockedMenu;
+use gui::summary::RunSummaryMenu;
 use model::ModelManager;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use twin_stick_shooter_core::game::Game;
-use twin_stick_shooter_core::resource::{GuiOverride, Input, Subframe, Time};
+use twin_stick_shooter_core::net::{
+    validate_chat_text, ChatError, ChatMessage, ClientMessage, ClockSync, ServerMessage,
+    RESYNC_THRESHOLD_TICKS, TICKS_PER_SECOND,
+};
+use twin_stick_shooter_core::resource::{GameEvent, GuiOverride, Input, Subframe, Time};
+use twin_stick_shooter_core::util::Timer;
 use wasm_bindgen::prelude::{wasm_bindgen, Closure};
 use wasm_bindgen::JsCast;
 use web_sys::{
@@ -14,13 +19,20 @@ use web_sys::{
 };
 
 mod action;
+mod chat;
 mod draw;
 mod gui;
+mod hud;
 mod model;
+mod net;
+mod profile;
 mod time_accumulator;
 
+use chat::{Chat, ChatButtons};
 use gui::title::TitleMenu;
 use gui::GuiState;
+use hud::Announcement;
+use net::Link;
 use time_accumulator::{Seconds, TimeAccumulator};
 
 use crate::time_accumulator::Milliseconds;
@@ -30,6 +42,15 @@ pub struct App {
     last_dimensions: Option<(u32, u32)>,
     time_accumulator: TimeAccumulator,
 
+    link: Link,
+    clock_sync: ClockSync,
+    ping_timer: Timer,
+    /// The controls the server was last told about, so unchanged ones aren't resent.
+    sent_input: Option<Input>,
+
+    chat: Chat,
+    announcement: Announcement,
+
     keys: HashMap<String, bool>,
     key_callback: Option<Closure<dyn FnMut(KeyboardEvent)>>,
     touch_start_input: bool,
@@ -77,11 +98,19 @@ pub fn launch() {
         last_dimensions: None,
         time_accumulator: TimeAccumulator::default(),
 
-        keys: "wasdijkl "
+        link: Link::open(&window),
+        clock_sync: ClockSync::new(),
+        ping_timer: Timer::elapsed(),
+        sent_input: None,
+
+        chat: Chat::new(&document),
+        announcement: Announcement::default(),
+
+        keys: "wasdeijkl "
             .chars()
             .map(|c| (c.to_string(), false))
             .chain(
-                (&["Enter", "Escape"])
+                (&["Enter", "Escape", "Shift"])
                     .iter()
                     .copied()
                     .map(|s| (s.to_string(), false)),
@@ -100,6 +129,7 @@ pub fn launch() {
         model_manager: ModelManager::default(),
     }));
     let mut app_mut = app.lock().unwrap();
+    profile::apply_settings(&profile::load());
 
     if quickstart {
         app_mut.gui = GuiState::new(Box::new(RunningInGameMenu));
@@ -112,6 +142,33 @@ pub fn launch() {
         move |e: KeyboardEvent| {
             let mut app_mut = app.lock().unwrap();
             let key = e.key();
+
+            // While typing a chat message, keys go to the text input. Enter sends and Escape
+            // cancels.
+            if app_mut.chat.is_typing() {
+                if e.type_() == "keydown" {
+                    match key.as_str() {
+                        "Enter" => {
+                            let text = app_mut.chat.take_typed_text();
+                            app_mut.send_chat(&text);
+                        }
+                        "Escape" => app_mut.chat.close(),
+                        _ => (),
+                    }
+                }
+                return;
+            }
+            if key == "t" && e.type_() == "keydown" && !e.repeat() {
+                // Release every held key so the ship doesn't keep moving while the player types.
+                for pressed in app_mut.keys.values_mut() {
+                    *pressed = false;
+                }
+                app_mut.chat.open_typing();
+                // Keep the "t" out of the freshly focused input.
+                e.prevent_default();
+                return;
+            }
+
             if app_mut.keys.contains_key(&key) {
                 *app_mut.keys.get_mut(&key).unwrap() = match e.type_().as_str() {
                     "keydown" => true,
@@ -227,7 +284,8 @@ pub fn launch() {
 }
 
 impl App {
-    const FIXED_TIMESTEP: Seconds = Seconds(1.0 / 100.0);
+    const FIXED_TIMESTEP: Seconds = Seconds(1.0 / TICKS_PER_SECOND as f32);
+    const PING_INTERVAL_SECONDS: f32 = 0.5;
 
     fn get_key(&self, key: &str) -> bool {
         self.keys.get(key).copied().unwrap_or_default()
@@ -243,7 +301,23 @@ impl App {
         let elapsed_seconds = self
             .time_accumulator
             .update_for_timestamp(Milliseconds(timestamp));
-        let input = self.sample_input(&window);
+        self.update_network(&window, elapsed_seconds);
+        let mut input = self.sample_input(&window);
+        if let Some(Seconds(elapsed_seconds)) = elapsed_seconds {
+            self.announcement.step(&Time { elapsed_seconds });
+            let chat_buttons = App::sample_chat_buttons(&window);
+            if let Some(text) = self
+                .chat
+                .step(&Time { elapsed_seconds }, &input, chat_buttons)
+            {
+                self.send_chat(&text);
+            }
+        }
+        if self.chat.is_open() {
+            // The chat has the controls. The ship coasts and menus ignore input until it closes.
+            input = Input::default();
+        }
+        self.send_input(&input);
         self.step(elapsed_seconds, &input);
 
         self.interpolate();
@@ -255,6 +329,8 @@ impl App {
             &input,
             &debug,
         );
+        hud::update(&document, &self.game);
+        self.announcement.update(&document);
         self.update_debug_ui(&document);
 
         window
@@ -268,6 +344,95 @@ impl App {
             .unwrap();
     }
 
+    fn update_network(&mut self, window: &Window, elapsed_seconds: Option<Seconds>) {
+        let time = Time {
+            elapsed_seconds: elapsed_seconds.unwrap_or_default().seconds(),
+        };
+
+        for received in self.link.step(window, &time) {
+            match received.message {
+                // A new connection may take a different network path, so start the estimate over.
+                // The server has forgotten our controls too.
+                ServerMessage::Welcome { .. } => {
+                    self.clock_sync.reset();
+                    self.sent_input = None;
+                }
+                ServerMessage::Pong {
+                    client_time,
+                    server_time,
+                } => self
+                    .clock_sync
+                    .record_pong(client_time, server_time, received.received_at),
+                // A fresh session has nothing to catch up on. Only a resumed one takes the server's
+                // word for where everything is.
+                ServerMessage::Snapshot(snapshot) if self.link.resumed() => {
+                    // The restored ship is undocked and back in play, wherever the menus were.
+                    if snapshot.restore(&mut self.game).is_some() {
+                        self.gui.replace_with(Box::new(RunningInGameMenu));
+                    }
+                }
+                ServerMessage::Snapshot(_) => (),
+                ServerMessage::Chat(message) => self.chat.push(message),
+            }
+        }
+
+        if self.ping_timer.step_and_is_elapsed(&time) {
+            self.link.send(&ClientMessage::Ping {
+                client_time: net::now_seconds(),
+            });
+            self.ping_timer.reset(App::PING_INTERVAL_SECONDS);
+        }
+
+        // Steer the simulation toward the tick the server expects from this client. Small errors are
+        // corrected gradually by dilating time; large ones (like the first estimate) are jumped.
+        if let Some(estimate) = self.clock_sync.estimate() {
+            let now = net::now_seconds();
+            let client_tick = self.client_tick();
+            let target_tick = estimate.target_tick(now);
+            if (target_tick - client_tick).abs() > RESYNC_THRESHOLD_TICKS {
+                self.game.set_tick(target_tick.max(0.0) as u64);
+                self.time_accumulator.set_time_scale(1.0);
+            } else {
+                self.time_accumulator
+                    .set_time_scale(estimate.time_dilation(client_tick, now));
+            }
+        }
+    }
+
+    /// Tells the server about the player's controls when they change.
+    fn send_input(&mut self, input: &Input) {
+        if !self.link.is_open() || self.sent_input.as_ref() == Some(input) {
+            return;
+        }
+        self.link.send(&ClientMessage::Input(input.clone()));
+        self.sent_input = Some(input.clone());
+    }
+
+    /// Sends a chat message to the room. Problems are reported as local system lines.
+    fn send_chat(&mut self, text: &str) {
+        let text = match validate_chat_text(text) {
+            Ok(text) => text,
+            // Closing the input without typing anything isn't worth a complaint.
+            Err(ChatError::Empty) => return,
+            Err(e) => {
+                self.chat
+                    .push(ChatMessage::system(format!("Message not sent: {}", e)));
+                return;
+            }
+        };
+        if self.link.is_open() {
+            self.link.send(&ClientMessage::Chat { text });
+        } else {
+            self.chat
+                .push(ChatMessage::system("Message not sent: not connected"));
+        }
+    }
+
+    /// The client's current fractional tick, including time accumulated toward the next step.
+    fn client_tick(&self) -> f64 {
+        self.game.tick() as f64 + (self.time_accumulator.accumulator() / App::FIXED_TIMESTEP) as f64
+    }
+
     fn update_dimensions(&mut self, window: &Window) {
         let device_pixel_ratio = window.device_pixel_ratio();
         let document = window.document().unwrap();
@@ -316,6 +481,8 @@ impl App {
                         axes.get(3).as_f64().unwrap() as f32,
                     ),
                     fire: buttons.get(7).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
+                    alt_fire: buttons.get(6).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
+                    boost: buttons.get(4).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
                     dpad_up: buttons.get(12).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
                     dpad_down: buttons.get(13).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
                     confirm: buttons.get(0).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
@@ -338,6 +505,8 @@ impl App {
                         + if self.get_key("i") { -1.0 } else { 0.0 },
                 ),
                 fire: self.get_key(" "),
+                alt_fire: self.get_key("Shift"),
+                boost: self.get_key("e"),
                 dpad_up: false,
                 dpad_down: false,
                 confirm: self.get_key("Enter"),
@@ -345,6 +514,30 @@ impl App {
             })
     }
 
+    /// Samples the chat buttons from the first standard gamepad: Back toggles quick chat and B
+    /// cancels it.
+    fn sample_chat_buttons(window: &Window) -> ChatButtons {
+        window
+            .navigator()
+            .get_gamepads()
+            .unwrap()
+            .iter()
+            .flat_map(|gamepad| {
+                let gamepad = gamepad.dyn_into::<Gamepad>().ok()?;
+                if gamepad.mapping() != GamepadMappingType::Standard {
+                    return None;
+                }
+
+                let buttons = gamepad.buttons();
+                Some(ChatButtons {
+                    toggle: buttons.get(8).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
+                    cancel: buttons.get(1).dyn_into::<GamepadButton>().unwrap().value() > 0.5,
+                })
+            })
+            .next()
+            .unwrap_or_default()
+    }
+
     fn step(&mut self, elapsed_seconds: Option<Seconds>, input: &Input) {
         // Step the GUI.
         if let Some(Seconds(elapsed_seconds)) = elapsed_seconds {
@@ -366,11 +559,46 @@ impl App {
 
         // Apply queued GUI overrides from the step.
         for gui_override in self.game.gui_override_queue().drain() {
+            match gui_override {
+                GuiOverride::StationDocked => profile::save(&self.game),
+                GuiOverride::GameOver | GuiOverride::Victory => profile::record_run(&self.game),
+            }
             // TODO: Is this silly? Why isn't this just a coalescing Option<GuiOverride>?
             self.gui.replace_with(match gui_override {
-                GuiOverride::StationDocked => Box::new(StationDockedMenu),
+                GuiOverride::StationDocked => gui::station::docked_menu(&self.game),
+                GuiOverride::GameOver => Box::new(RunSummaryMenu::new(&self.game, "Game Over")),
+                GuiOverride::Victory => Box::new(RunSummaryMenu::new(&self.game, "Victory")),
             });
         }
+
+        for event in self.game.game_event_queue().drain() {
+            match event {
+                GameEvent::PlayerDied { lives, .. } if lives > 0 => {
+                    self.chat.push(ChatMessage::system(match lives {
+                        1 => "Ship destroyed. Last ship!".to_string(),
+                        _ => format!("Ship destroyed. {} ships left.", lives),
+                    }))
+                }
+                GameEvent::PlayerDied { .. } => (),
+                GameEvent::InventoryChanged { player } => {
+                    let document = web_sys::window().unwrap().document().unwrap();
+                    hud::update_inventory(&document, &self.game, player);
+                }
+                GameEvent::WaveStarted { wave } => self.announcement.show(format!("Wave {}", wave)),
+                GameEvent::WaveCleared { wave } => {
+                    self.announcement.show(format!("Wave {} cleared", wave))
+                }
+                GameEvent::WavesWon | GameEvent::WavesLost { .. } => (),
+                GameEvent::JumpStarted { .. } => (),
+                GameEvent::SectorEntered { player, sector } => {
+                    if action::local_player(&self.game) == Some(player) {
+                        if let Ok(sector) = self.game.sector_defs().get(&sector) {
+                            self.announcement.show(sector.name.clone());
+                        }
+                    }
+                }
+            }
+        }
     }
 
     fn interpolate(&mut self) {
@@ -401,6 +629,53 @@ impl App {
         {
             element.set_inner_text(&format!("{:#?}", &*counters));
         }
+
+        if let Some(element) = document
+            .get_element_by_id("debug-clock-sync")
+            .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+        {
+            element.set_inner_text(&match self.clock_sync.estimate() {
+                Some(estimate) => {
+                    let now = net::now_seconds();
+                    format!(
+                        "rtt: {:.1} ms\njitter: {:.1} ms\noffset: {:.3} s\nserver tick: {:.1}\n\
+                         target tick: {:.1}\nclient tick: {:.1}\ntime scale: {:.3}",
+                        estimate.rtt * 1000.0,
+                        estimate.jitter * 1000.0,
+                        estimate.offset,
+                        estimate.server_tick(now),
+                        estimate.target_tick(now),
+                        self.client_tick(),
+                        self.time_accumulator.time_scale(),
+                    )
+                }
+                None if self.link.is_open() => "waiting for first pong".to_string(),
+                None => "not connected".to_string(),
+            });
+        }
+
+        if let Some(element) = document
+            .get_element_by_id("debug-session")
+            .and_then(|element| element.dyn_into::<HtmlElement>().ok())
+        {
+            element.set_inner_text(&format!(
+                "connected: {}\nreconnect attempts: {}\nsession: {}\nresumed: {}\n\
+                 snapshot: {}",
+                self.link.is_open(),
+                self.link.reconnect_attempts(),
+                self.link.session_token().unwrap_or("none"),
+                self.link.resumed(),
+                match self.link.snapshot() {
+                    Some(snapshot) => format!(
+                        "tick {}, {} entities, you at {:?}",
+                        snapshot.tick,
+                        snapshot.entities.len(),
+                        snapshot.you().map(|you| (you.pos.x, you.pos.y)),
+                    ),
+                    None => "none".to_string(),
+                },
+            ));
+        }
     }
 }
 
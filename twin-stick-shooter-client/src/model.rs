@@ -40,8 +40,17 @@ impl Default for ModelManager {
         };
         model_manager.insert("ships/player".to_string(), player_ship());
         model_manager.insert("ships/station".to_string(), station());
+        model_manager.insert("gates/jump".to_string(), jump_gate());
         model_manager.insert("test/target".to_string(), target());
+        model_manager.insert("ships/dreadnought".to_string(), hull(60.0, "#a33", "#622"));
+        model_manager.insert("ships/turret".to_string(), hull(15.0, "#c84", "#742"));
+        model_manager.insert("ships/engine".to_string(), hull(15.0, "#fd4", "#f80"));
         model_manager.insert("shots/lemon".to_string(), lemon());
+        model_manager.insert("shots/enemy".to_string(), enemy_shot());
+        model_manager.insert("shots/cannon".to_string(), cannon_shot());
+        model_manager.insert("pickups/scrap".to_string(), pickup("#aaa", "#777"));
+        model_manager.insert("pickups/cores".to_string(), pickup("#c6f", "#94c"));
+        model_manager.insert("pickups/repair".to_string(), pickup("#4f4", "#2c2"));
         model_manager
     }
 }
@@ -95,6 +104,7 @@ fn player_ship() -> Model {
         }));
         ctx.set_line_width(2.0);
         ctx.stroke();
+        draw_nose(ctx, 20.0);
     }))
 }
 
@@ -121,6 +131,25 @@ fn station() -> Model {
     }))
 }
 
+fn jump_gate() -> Model {
+    Model::new(Box::new(|ctx, _is_hit_flashing| {
+        ctx.begin_path();
+        ctx.arc(0.0, 0.0, 40.0, 0.0, std::f64::consts::TAU).unwrap();
+        ctx.close_path();
+        ctx.set_fill_style(&JsValue::from_str("#48f4"));
+        ctx.fill();
+
+        ctx.set_stroke_style(&JsValue::from_str("#8cf"));
+        ctx.set_line_width(6.0);
+        ctx.stroke();
+
+        ctx.begin_path();
+        ctx.arc(0.0, 0.0, 28.0, 0.0, std::f64::consts::TAU).unwrap();
+        ctx.set_line_width(2.0);
+        ctx.stroke();
+    }))
+}
+
 fn target() -> Model {
     Model::new(Box::new(|ctx, is_hit_flashing| {
         ctx.begin_path();
@@ -141,6 +170,41 @@ fn target() -> Model {
         }));
         ctx.set_line_width(2.0);
         ctx.stroke();
+        draw_nose(ctx, 20.0);
+    }))
+}
+
+/// Marks which way a ship of `radius` faces, in the current stroke style.
+fn draw_nose(ctx: &CanvasRenderingContext2d, radius: f64) {
+    ctx.begin_path();
+    ctx.move_to(0.4 * radius, 0.0);
+    ctx.line_to(radius, 0.0);
+    ctx.stroke();
+}
+
+/// A round hull in the usual style, for enemy ships and their parts.
+fn hull(radius: f64, fill: &'static str, stroke: &'static str) -> Model {
+    Model::new(Box::new(move |ctx, is_hit_flashing| {
+        ctx.begin_path();
+        ctx.arc(0.0, 0.0, radius, 0.0, std::f64::consts::TAU)
+            .unwrap();
+        ctx.close_path();
+
+        ctx.set_fill_style(&JsValue::from_str(if is_hit_flashing {
+            "#fff"
+        } else {
+            fill
+        }));
+        ctx.fill();
+
+        ctx.set_stroke_style(&JsValue::from_str(if is_hit_flashing {
+            "#fff"
+        } else {
+            stroke
+        }));
+        ctx.set_line_width(2.0);
+        ctx.stroke();
+        draw_nose(ctx, radius);
     }))
 }
 
@@ -157,3 +221,50 @@ fn lemon() -> Model {
         ctx.stroke();
     }))
 }
+
+fn enemy_shot() -> Model {
+    Model::new(Box::new(|ctx, _is_hit_flashing| {
+        ctx.begin_path();
+        ctx.arc(0.0, 0.0, 6.0, 0.0, std::f64::consts::TAU).unwrap();
+
+        ctx.set_fill_style(&JsValue::from_str("#f44"));
+        ctx.fill();
+
+        ctx.set_stroke_style(&JsValue::from_str("#c22"));
+        ctx.set_line_width(2.0);
+        ctx.stroke();
+    }))
+}
+
+fn cannon_shot() -> Model {
+    Model::new(Box::new(|ctx, _is_hit_flashing| {
+        ctx.begin_path();
+        ctx.arc(0.0, 0.0, 8.0, 0.0, std::f64::consts::TAU).unwrap();
+
+        ctx.set_fill_style(&JsValue::from_str("#4ff"));
+        ctx.fill();
+
+        ctx.set_stroke_style(&JsValue::from_str("#2cc"));
+        ctx.set_line_width(2.0);
+        ctx.stroke();
+    }))
+}
+
+/// A small diamond, in the given fill and outline colors.
+fn pickup(fill: &'static str, stroke: &'static str) -> Model {
+    Model::new(Box::new(move |ctx, _is_hit_flashing| {
+        ctx.begin_path();
+        ctx.move_to(0.0, -8.0);
+        ctx.line_to(8.0, 0.0);
+        ctx.line_to(0.0, 8.0);
+        ctx.line_to(-8.0, 0.0);
+        ctx.close_path();
+
+        ctx.set_fill_style(&JsValue::from_str(fill));
+        ctx.fill();
+
+        ctx.set_stroke_style(&JsValue::from_str(stroke));
+        ctx.set_line_width(2.0);
+        ctx.stroke();
+    }))
+}
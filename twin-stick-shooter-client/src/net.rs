@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use twin_stick_shooter_core::net::{ClientMessage, ServerMessage, Snapshot};
+use twin_stick_shooter_core::resource::Time;
+use twin_stick_shooter_core::util::Timer;
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{MessageEvent, WebSocket, Window};
+
+/// Returns the current wall clock time in seconds.
+pub fn now_seconds() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+/// A message from the server along with the client time at which it arrived.
+#[derive(Clone, Debug)]
+pub struct Received {
+    pub received_at: f64,
+    pub message: ServerMessage,
+}
+
+/// A websocket connection to the game server.
+///
+/// Incoming messages are timestamped and queued by a JS callback, then drained once per frame.
+pub struct Connection {
+    websocket: WebSocket,
+    inbox: Arc<Mutex<VecDeque<Received>>>,
+    _message_callback: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl Connection {
+    pub fn open(window: &Window) -> Connection {
+        let websocket = WebSocket::new(&websocket_url(window)).unwrap();
+        let inbox = Arc::new(Mutex::new(VecDeque::new()));
+
+        let message_callback = Closure::wrap(Box::new({
+            let inbox = Arc::clone(&inbox);
+            move |e: MessageEvent| {
+                let received_at = now_seconds();
+                match e
+                    .data()
+                    .as_string()
+                    .map(|text| ServerMessage::from_json(&text))
+                {
+                    Some(Ok(message)) => inbox.lock().unwrap().push_back(Received {
+                        received_at,
+                        message,
+                    }),
+                    Some(Err(e)) => {
+                        web_sys::console::warn_1(&format!("malformed server message: {}", e).into())
+                    }
+                    None => web_sys::console::warn_1(&"non-text server message".into()),
+                }
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        websocket.set_onmessage(Some(message_callback.as_ref().unchecked_ref()));
+
+        Connection {
+            websocket,
+            inbox,
+            _message_callback: message_callback,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.websocket.ready_state() == WebSocket::OPEN
+    }
+
+    /// True once the socket has closed or failed to open. A closed connection never reopens.
+    pub fn is_closed(&self) -> bool {
+        self.websocket.ready_state() == WebSocket::CLOSED
+    }
+
+    /// Sends a message if the connection is open. Messages sent at other times are dropped.
+    pub fn send(&self, message: &ClientMessage) {
+        if self.is_open() {
+            self.websocket.send_with_str(&message.to_json()).unwrap();
+        }
+    }
+
+    pub fn drain(&self) -> Vec<Received> {
+        self.inbox.lock().unwrap().drain(..).collect()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Detach the callback before it's freed, in case the socket is still delivering messages.
+        self.websocket.set_onmessage(None);
+        self.websocket.close().unwrap();
+    }
+}
+
+/// Keeps a connection to the server alive across drops.
+///
+/// Closed connections are reopened with exponential backoff. Each new connection greets the server
+/// with the session token from the last `Welcome`, so the server can hand back the same player
+/// entity if its grace period hasn't run out.
+pub struct Link {
+    connection: Connection,
+    greeted: bool,
+    reconnect_attempts: u32,
+    reconnect_timer: Timer,
+
+    session_token: Option<String>,
+    resumed: bool,
+    snapshot: Option<Snapshot>,
+}
+
+impl Link {
+    const RECONNECT_BASE_DELAY_SECONDS: f32 = 0.5;
+    const RECONNECT_MAX_DELAY_SECONDS: f32 = 8.0;
+
+    pub fn open(window: &Window) -> Link {
+        Link {
+            connection: Connection::open(window),
+            greeted: false,
+            reconnect_attempts: 0,
+            reconnect_timer: Timer::elapsed(),
+
+            session_token: None,
+            resumed: false,
+            snapshot: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.connection.is_open()
+    }
+
+    pub fn send(&self, message: &ClientMessage) {
+        self.connection.send(message);
+    }
+
+    pub fn session_token(&self) -> Option<&str> {
+        self.session_token.as_deref()
+    }
+
+    /// Whether the current session was resumed rather than created fresh.
+    pub fn resumed(&self) -> bool {
+        self.resumed
+    }
+
+    pub fn reconnect_attempts(&self) -> u32 {
+        self.reconnect_attempts
+    }
+
+    /// The most recent full snapshot from the server.
+    pub fn snapshot(&self) -> Option<&Snapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Greets newly opened connections, reopens closed ones when their backoff elapses, and returns
+    /// everything received since the last step.
+    ///
+    /// Session messages are also recorded by the link before being returned.
+    pub fn step(&mut self, window: &Window, time: &Time) -> Vec<Received> {
+        if self.connection.is_open() && !self.greeted {
+            self.connection.send(&ClientMessage::Hello {
+                resume_token: self.session_token.clone(),
+            });
+            self.greeted = true;
+        } else if self.connection.is_closed() && self.reconnect_timer.step_and_is_elapsed(time) {
+            self.connection = Connection::open(window);
+            self.greeted = false;
+            self.reconnect_timer.reset(
+                (Link::RECONNECT_BASE_DELAY_SECONDS * 2.0f32.powi(self.reconnect_attempts as i32))
+                    .min(Link::RECONNECT_MAX_DELAY_SECONDS),
+            );
+            self.reconnect_attempts += 1;
+        }
+
+        let received = self.connection.drain();
+        for Received { message, .. } in &received {
+            match message {
+                ServerMessage::Welcome {
+                    session_token,
+                    resumed,
+                } => {
+                    self.session_token = Some(session_token.clone());
+                    self.resumed = *resumed;
+                    self.reconnect_attempts = 0;
+                    self.reconnect_timer.elapse_now();
+                }
+                ServerMessage::Snapshot(snapshot) => self.snapshot = Some(snapshot.clone()),
+                _ => (),
+            }
+        }
+        received
+    }
+}
+
+/// Builds the websocket URL relative to the page, mirroring how the page itself was loaded.
+fn websocket_url(window: &Window) -> String {
+    let location = window.location();
+    let scheme = if location.protocol().unwrap() == "https:" {
+        "wss:"
+    } else {
+        "ws:"
+    };
+    let mut path = location.pathname().unwrap();
+    if !path.ends_with('/') {
+        path.push('/');
+    }
+    format!("{}//{}{}websocket", scheme, location.host().unwrap(), path)
+}
@@ -0,0 +1,131 @@
+use legion::EntityStore;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::module::ModuleDefs;
+use twin_stick_shooter_core::profile::{Profile, Storage, StorageError};
+use twin_stick_shooter_core::score::ScoreComponent;
+use twin_stick_shooter_core::weapon::WeaponDefs;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+
+use crate::action;
+
+/// Keys are prefixed so the game's entries are recognizable among others on the same origin.
+const KEY_PREFIX: &str = "twin-stick-shooter.";
+
+/// Saves into the browser's `localStorage`.
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    pub fn open() -> Result<LocalStorage, StorageError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .map(|storage| LocalStorage { storage })
+            .ok_or(StorageError::Unavailable)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.storage
+            .get_item(&format!("{}{}", KEY_PREFIX, key))
+            .map_err(|e| StorageError::Io(format!("{:?}", e)))
+    }
+
+    fn write(&mut self, key: &str, text: &str) -> Result<(), StorageError> {
+        self.storage
+            .set_item(&format!("{}{}", KEY_PREFIX, key), text)
+            .map_err(|e| StorageError::Io(format!("{:?}", e)))
+    }
+}
+
+fn warn(message: String) {
+    web_sys::console::warn_1(&message.into());
+}
+
+/// The saved profile. A missing or unreadable one gives a fresh profile, with a warning for the
+/// latter.
+pub fn load() -> Profile {
+    let loaded = LocalStorage::open()
+        .map_err(Into::into)
+        .and_then(|storage| Profile::load(&storage));
+    loaded.unwrap_or_else(|e| {
+        warn(format!("couldn't load profile: {}", e));
+        Profile::default()
+    })
+}
+
+/// Makes changes to the saved profile.
+fn update(f: impl FnOnce(&mut Profile)) {
+    let mut storage = match LocalStorage::open() {
+        Ok(storage) => storage,
+        Err(e) => return warn(format!("couldn't save profile: {}", e)),
+    };
+    // Don't overwrite a profile that failed to load; it may still be recoverable.
+    let mut profile = match Profile::load(&storage) {
+        Ok(profile) => profile,
+        Err(e) => return warn(format!("couldn't save profile: {}", e)),
+    };
+    f(&mut profile);
+    if let Err(e) = profile.save(&mut storage) {
+        warn(format!("couldn't save profile: {}", e));
+    }
+}
+
+/// Saves the local player's inventory and ship, along with the settings.
+pub fn save(game: &Game) {
+    update(|profile| {
+        if let Some(player) = action::local_player(game) {
+            profile.capture(game.world(), player, &ModuleDefs::builtin());
+        }
+        profile.settings.draw_hitboxes = draw_hitboxes_checkbox()
+            .map(|input| input.checked())
+            .unwrap_or(profile.settings.draw_hitboxes);
+    });
+}
+
+/// Adds the local player's finished run to the best scores.
+pub fn record_run(game: &Game) {
+    let score = action::local_player(game)
+        .and_then(|player| game.world().entry_ref(player).ok())
+        .and_then(|entry| {
+            entry
+                .get_component::<ScoreComponent>()
+                .ok()
+                .map(|score| score.score)
+        });
+    if let Some(score) = score {
+        let wave = game.wave_director().wave();
+        update(|profile| {
+            profile.record_score(score, wave);
+        });
+    }
+}
+
+/// Gives the local player what the profile says they own.
+pub fn restore(game: &mut Game, profile: &Profile) {
+    if let Some(player) = action::local_player(game) {
+        profile.restore(
+            game.world_mut(),
+            player,
+            &WeaponDefs::builtin(),
+            &ModuleDefs::builtin(),
+        );
+    }
+}
+
+/// Puts the saved settings into effect.
+pub fn apply_settings(profile: &Profile) {
+    if let Some(input) = draw_hitboxes_checkbox() {
+        input.set_checked(profile.settings.draw_hitboxes);
+    }
+}
+
+fn draw_hitboxes_checkbox() -> Option<HtmlInputElement> {
+    web_sys::window()?
+        .document()?
+        .get_element_by_id("debug-draw-hitboxes")?
+        .dyn_into::<HtmlInputElement>()
+        .ok()
+}
@@ -31,10 +31,21 @@ impl Milliseconds {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct TimeAccumulator {
     last_timestamp: Option<Milliseconds>,
     accumulator: Seconds,
+    time_scale: f32,
+}
+
+impl Default for TimeAccumulator {
+    fn default() -> Self {
+        TimeAccumulator {
+            last_timestamp: None,
+            accumulator: Seconds::default(),
+            time_scale: 1.0,
+        }
+    }
 }
 
 impl TimeAccumulator {
@@ -42,6 +53,16 @@ impl TimeAccumulator {
         self.accumulator
     }
 
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Sets a factor applied to observed time before it fills the accumulator. Values slightly off
+    /// 1.0 let the simulation drift toward a target tick without visible jumps.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+    }
+
     /// Updates the time accumulator with an observed timestamp.
     ///
     /// Returns the time in seconds between the previous observed timestamp and this one. Note that
@@ -53,7 +74,7 @@ impl TimeAccumulator {
             .map(|last_timestamp| (timestamp - last_timestamp).to_seconds());
 
         if let Some(last_timestamp) = self.last_timestamp {
-            let dt = (timestamp - last_timestamp).to_seconds();
+            let dt = Seconds((timestamp - last_timestamp).to_seconds().seconds() * self.time_scale);
             // Allow simulation to slow down if running below 10 fps.
             self.accumulator = (self.accumulator + dt).at_most(Seconds(0.1));
         }
@@ -5,34 +5,6 @@ import init, { launch } from './twin_stick_shooter_client.js';
     launch();
 })();
 
-let websocket = new Promise((resolve, reject) => {
-    let url = new URL(window.location.href);
-    if (url.protocol === 'https:') {
-        url.protocol = 'wss:';
-    } else {
-        url.protocol = 'ws:';
-    }
-    if (!url.pathname.endsWith('/')) {
-        url.pathname += '/';
-    }
-    url.pathname += 'websocket';
-    url.hash = '';
-
-    let ws = new WebSocket(url);
-    ws.addEventListener('open', () => {
-        resolve(ws);
-        ws.send('hello from javascript');
-    });
-    ws.addEventListener('message', e => {
-        console.log('received websocket message:', e.data);
-    });
-    ws.addEventListener('error', e => {
-        reject(new Error('websocket error: ' + e));
-        console.log('websocket error:', e);
-    });
-    return ws;
-});
-
 (async function () {
     let conn = new RTCPeerConnection();
 
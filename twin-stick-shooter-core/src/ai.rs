@@ -0,0 +1,291 @@
+use cgmath::num_traits::{clamp, zero};
+use cgmath::{vec2, InnerSpace};
+use legion::world::SubWorld;
+use legion::{Entity, IntoQuery};
+use rand::Rng;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::{ForceComponent, MassComponent, VelocityComponent};
+use crate::player::PlayerComponent;
+use crate::position::PositionComponent;
+use crate::resource::Time;
+use crate::util::{clamp_magnitude, Timer};
+use crate::{Pt2, Vec2};
+
+/// How quickly an agent closes the gap between its velocity and the one its behaviors want. Lower
+/// is snappier.
+const STEERING_RESPONSE_SECONDS: f32 = 0.25;
+
+/// Steers an entity by applying forces computed from a weighted blend of behaviors.
+#[derive(Clone, Debug)]
+pub struct AiComponent {
+    pub max_speed: f32,
+    /// Caps the steering force, expressed per unit mass so it doesn't depend on the ship's mass.
+    pub max_accel: f32,
+    pub steering: Vec<Steering>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Steering {
+    pub behavior: Behavior,
+    pub weight: f32,
+}
+
+impl Steering {
+    pub fn new(behavior: Behavior, weight: f32) -> Steering {
+        Steering { behavior, weight }
+    }
+}
+
+/// A steering behavior. Each produces a desired velocity; behaviors that need a target aim at the
+/// nearest player and contribute nothing when there isn't one.
+#[derive(Clone, Debug)]
+pub enum Behavior {
+    /// Head straight for the target.
+    Seek,
+    /// Head away from the target while it's closer than `panic_distance`.
+    Flee { panic_distance: f32 },
+    /// Circle the target at `radius`.
+    Orbit { radius: f32, clockwise: bool },
+    /// Hold `distance` from the target while sliding sideways, switching direction now and then.
+    Strafe {
+        distance: f32,
+        clockwise: bool,
+        switch_timer: Timer,
+    },
+    /// Drift in a slowly and randomly changing direction. `turn_rate` is the most the heading can
+    /// change per second, in radians.
+    Wander { heading: f32, turn_rate: f32 },
+    /// Keep away from other agents closer than `radius`.
+    Separation { radius: f32 },
+}
+
+impl Behavior {
+    pub fn strafe(distance: f32) -> Behavior {
+        Behavior::Strafe {
+            distance,
+            clockwise: false,
+            switch_timer: Timer::elapsed(),
+        }
+    }
+
+    /// Wander starting off in a random direction.
+    pub fn wander<R: Rng + ?Sized>(rng: &mut R, turn_rate: f32) -> Behavior {
+        Behavior::Wander {
+            heading: rng.gen_range(0.0..std::f32::consts::TAU),
+            turn_rate,
+        }
+    }
+
+    fn desired_velocity(
+        &mut self,
+        agent: &Agent,
+        max_speed: f32,
+        rng: &mut Pcg32,
+        time: &Time,
+    ) -> Vec2 {
+        let to_target = agent.target.map(|target| target - agent.pos);
+        match *self {
+            Behavior::Seek => to_target
+                .map(|to_target| with_magnitude(to_target, max_speed))
+                .unwrap_or_else(zero),
+            Behavior::Flee { panic_distance } => match to_target {
+                Some(to_target) if to_target.magnitude() < panic_distance => {
+                    with_magnitude(-to_target, max_speed)
+                }
+                _ => zero(),
+            },
+            Behavior::Orbit { radius, clockwise } => to_target
+                .map(|to_target| circle(to_target, radius, clockwise, max_speed))
+                .unwrap_or_else(zero),
+            Behavior::Strafe {
+                distance,
+                ref mut clockwise,
+                ref mut switch_timer,
+            } => {
+                if switch_timer.step_and_is_elapsed(time) {
+                    *clockwise = rng.gen();
+                    switch_timer.reset(rng.gen_range(0.5..1.5));
+                }
+                to_target
+                    .map(|to_target| circle(to_target, distance, *clockwise, max_speed))
+                    .unwrap_or_else(zero)
+            }
+            Behavior::Wander {
+                ref mut heading,
+                turn_rate,
+            } => {
+                *heading += rng.gen_range(-1.0..1.0) * turn_rate * time.elapsed_seconds;
+                vec2(heading.cos(), heading.sin()) * max_speed
+            }
+            Behavior::Separation { radius } => {
+                let push = agent
+                    .neighbors
+                    .iter()
+                    .map(|&other| agent.pos - other)
+                    .filter(|away| away.magnitude() < radius)
+                    .fold(zero(), |push: Vec2, away| {
+                        push + with_magnitude(away, 1.0 - away.magnitude() / radius)
+                    });
+                clamp_magnitude(push * max_speed, 0.0, max_speed)
+            }
+        }
+    }
+}
+
+/// An `AiComponent` as prefabs describe it, without the state its behaviors build up.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AiDef {
+    pub max_speed: f32,
+    pub max_accel: f32,
+    pub steering: Vec<SteeringDef>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SteeringDef {
+    pub behavior: BehaviorDef,
+    pub weight: f32,
+}
+
+/// The settings of a `Behavior`. Whatever a behavior randomizes is rolled when it's created.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BehaviorDef {
+    Seek,
+    Flee { panic_distance: f32 },
+    Orbit { radius: f32, clockwise: bool },
+    Strafe { distance: f32 },
+    Wander { turn_rate: f32 },
+    Separation { radius: f32 },
+}
+
+impl AiDef {
+    pub fn to_component<R: Rng + ?Sized>(&self, rng: &mut R) -> AiComponent {
+        AiComponent {
+            max_speed: self.max_speed,
+            max_accel: self.max_accel,
+            steering: self
+                .steering
+                .iter()
+                .map(|def| Steering::new(def.behavior.to_behavior(rng), def.weight))
+                .collect(),
+        }
+    }
+}
+
+impl BehaviorDef {
+    pub fn to_behavior<R: Rng + ?Sized>(&self, rng: &mut R) -> Behavior {
+        match *self {
+            BehaviorDef::Seek => Behavior::Seek,
+            BehaviorDef::Flee { panic_distance } => Behavior::Flee { panic_distance },
+            BehaviorDef::Orbit { radius, clockwise } => Behavior::Orbit { radius, clockwise },
+            BehaviorDef::Strafe { distance } => Behavior::strafe(distance),
+            BehaviorDef::Wander { turn_rate } => Behavior::wander(rng, turn_rate),
+            BehaviorDef::Separation { radius } => Behavior::Separation { radius },
+        }
+    }
+}
+
+/// What a behavior knows about its agent's surroundings.
+struct Agent<'a> {
+    pos: Pt2,
+    target: Option<Pt2>,
+    neighbors: &'a [Pt2],
+}
+
+/// Rescales `v` to `magnitude`, or gives zero if `v` has no meaningful direction.
+fn with_magnitude(v: Vec2, magnitude: f32) -> Vec2 {
+    if v.magnitude2() > 1e-6 {
+        v.normalize_to(magnitude)
+    } else {
+        zero()
+    }
+}
+
+/// Desired velocity for circling at `radius` around a target `to_target` away: tangential, plus a
+/// radial correction that grows with the distance from the circle.
+fn circle(to_target: Vec2, radius: f32, clockwise: bool, max_speed: f32) -> Vec2 {
+    let dist = to_target.magnitude();
+    if dist < 1e-3 {
+        return zero();
+    }
+    let inward = to_target / dist;
+    let tangent = if clockwise {
+        vec2(inward.y, -inward.x)
+    } else {
+        vec2(-inward.y, inward.x)
+    };
+    let correction = clamp((dist - radius) / radius, -1.0, 1.0);
+    with_magnitude(tangent + inward * correction, max_speed)
+}
+
+#[legion::system]
+#[read_component(PositionComponent)]
+#[read_component(VelocityComponent)]
+#[read_component(MassComponent)]
+#[read_component(PlayerComponent)]
+#[write_component(ForceComponent)]
+#[write_component(AiComponent)]
+pub fn ai(world: &mut SubWorld, #[resource] time: &Time, #[resource] rng: &mut Pcg32) {
+    let players: Vec<Pt2> = <(&PositionComponent, &PlayerComponent)>::query()
+        .iter(world)
+        .filter(|(_, player)| player.is_alive())
+        .map(|(&PositionComponent(pos), _)| pos)
+        .collect();
+    let agents: Vec<(Entity, Pt2)> = <(Entity, &PositionComponent, &AiComponent)>::query()
+        .iter(world)
+        .map(|(&entity, &PositionComponent(pos), _)| (entity, pos))
+        .collect();
+
+    let mut neighbors = vec![];
+    for (
+        &entity,
+        &PositionComponent(pos),
+        &VelocityComponent(vel),
+        mass,
+        ForceComponent(force),
+        ai,
+    ) in <(
+        Entity,
+        &PositionComponent,
+        &VelocityComponent,
+        &MassComponent,
+        &mut ForceComponent,
+        &mut AiComponent,
+    )>::query()
+    .iter_mut(world)
+    {
+        let target = players.iter().copied().min_by(|a, b| {
+            (a - pos)
+                .magnitude2()
+                .partial_cmp(&(b - pos).magnitude2())
+                .unwrap()
+        });
+        neighbors.clear();
+        neighbors.extend(
+            agents
+                .iter()
+                .filter(|&&(other, _)| other != entity)
+                .map(|&(_, other_pos)| other_pos),
+        );
+        let agent = Agent {
+            pos,
+            target,
+            neighbors: &neighbors,
+        };
+
+        let max_speed = ai.max_speed;
+        let desired = ai.steering.iter_mut().fold(zero(), |sum: Vec2, steering| {
+            sum + steering
+                .behavior
+                .desired_velocity(&agent, max_speed, rng, time)
+                * steering.weight
+        });
+        let desired = clamp_magnitude(desired, 0.0, max_speed);
+
+        let accel = (desired - vel) / STEERING_RESPONSE_SECONDS;
+        *force += clamp_magnitude(accel, 0.0, ai.max_accel) * mass.mass();
+    }
+}
@@ -0,0 +1,230 @@
+use cgmath::num_traits::zero;
+use cgmath::{vec2, InnerSpace};
+use collision::Discrete;
+use legion::world::SubWorld;
+use legion::{Entity, EntityStore, IntoQuery};
+use serde::{Deserialize, Serialize};
+
+use crate::collision::{compute_bound, Aabb, Shape, ShapeDef};
+use crate::hitbox::pose;
+use crate::physics::{AngularVelocityComponent, MassComponent, VelocityComponent};
+use crate::player::PlayerComponent;
+use crate::position::{PositionComponent, RotationComponent};
+use crate::{Mat3, Pt2, Vec2};
+
+/// How far bodies may sink into each other before they're pushed apart, to keep resting contacts
+/// from jittering.
+const PENETRATION_SLOP: f32 = 0.5;
+/// The fraction of the remaining overlap pushed out each step.
+const PENETRATION_CORRECTION: f32 = 0.8;
+
+/// A solid collider. Bodies bounce off and push each other apart, in proportion to their
+/// `MassComponent`s. A body without a mass or velocity is immovable.
+#[derive(Clone, Debug)]
+pub struct BodyComponent {
+    pub shape: Shape,
+    /// The fraction of the speed they close at that two bodies part at. The bouncier of the two
+    /// wins.
+    pub restitution: f32,
+    /// How much sliding contact slows the bodies, as a fraction of how hard they press together.
+    /// Two bodies use the geometric mean of theirs.
+    pub friction: f32,
+}
+
+/// A body as written in data files.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BodyDef {
+    pub shape: ShapeDef,
+    #[serde(default = "BodyDef::default_restitution")]
+    pub restitution: f32,
+    #[serde(default = "BodyDef::default_friction")]
+    pub friction: f32,
+}
+
+impl BodyDef {
+    fn default_restitution() -> f32 {
+        0.4
+    }
+
+    fn default_friction() -> f32 {
+        0.3
+    }
+
+    pub fn to_component(&self) -> BodyComponent {
+        BodyComponent {
+            shape: self.shape.to_shape(),
+            restitution: self.restitution,
+            friction: self.friction,
+        }
+    }
+}
+
+/// A body's state while contacts are resolved.
+struct Body {
+    entity: Entity,
+    shape: Shape,
+    restitution: f32,
+    friction: f32,
+    rot: Option<RotationComponent>,
+    inv_mass: f32,
+    inv_inertia: f32,
+    /// Held inside this entity, so it doesn't collide with it.
+    docked_to: Option<Entity>,
+
+    pos: Pt2,
+    vel: Vec2,
+    ang_vel: f32,
+}
+
+impl Body {
+    fn pose(&self) -> Mat3 {
+        pose(self.pos, self.rot.as_ref())
+    }
+
+    fn bound(&self) -> Aabb {
+        compute_bound(&self.shape, &self.pose())
+    }
+
+    /// The velocity of the body's point at `r` from its center.
+    fn point_vel(&self, r: Vec2) -> Vec2 {
+        self.vel + self.ang_vel * vec2(-r.y, r.x)
+    }
+
+    fn apply_impulse(&mut self, r: Vec2, impulse: Vec2) {
+        self.vel += impulse * self.inv_mass;
+        self.ang_vel += r.perp_dot(impulse) * self.inv_inertia;
+    }
+}
+
+/// Separates overlapping bodies and exchanges impulses between them. Runs after everything has
+/// moved for the step.
+#[legion::system]
+#[read_component(BodyComponent)]
+#[read_component(RotationComponent)]
+#[read_component(MassComponent)]
+#[read_component(PlayerComponent)]
+#[write_component(PositionComponent)]
+#[write_component(VelocityComponent)]
+#[write_component(AngularVelocityComponent)]
+pub fn contact(world: &mut SubWorld) {
+    let mut bodies: Vec<Body> = <(
+        Entity,
+        &BodyComponent,
+        &PositionComponent,
+        Option<&RotationComponent>,
+        Option<&MassComponent>,
+        Option<&VelocityComponent>,
+        Option<&AngularVelocityComponent>,
+        Option<&PlayerComponent>,
+    )>::query()
+    .iter(world)
+    .map(
+        |(&entity, body, &PositionComponent(pos), rot, mass, vel, ang_vel, player)| {
+            let (inv_mass, inv_inertia) = match (mass, vel) {
+                (Some(mass), Some(_)) => (
+                    mass.inv_mass(),
+                    if ang_vel.is_some() {
+                        mass.inv_inertia()
+                    } else {
+                        0.0
+                    },
+                ),
+                _ => (0.0, 0.0),
+            };
+            Body {
+                entity,
+                shape: body.shape.clone(),
+                restitution: body.restitution,
+                friction: body.friction,
+                rot: rot.cloned(),
+                inv_mass,
+                inv_inertia,
+                docked_to: player.and_then(|player| player.docked_to),
+                pos,
+                vel: vel.map(|vel| vel.0).unwrap_or_else(zero),
+                ang_vel: ang_vel.map(|ang_vel| ang_vel.0).unwrap_or(0.0),
+            }
+        },
+    )
+    .collect();
+
+    // There are few enough bodies to check every pair.
+    for i in 0..bodies.len() {
+        let (done, rest) = bodies.split_at_mut(i + 1);
+        let a = &mut done[i];
+        for b in rest {
+            resolve(a, b);
+        }
+    }
+
+    for body in bodies {
+        if body.inv_mass == 0.0 {
+            continue;
+        }
+        let mut entry = world.entry_mut(body.entity).unwrap();
+        entry.get_component_mut::<PositionComponent>().unwrap().0 = body.pos;
+        entry.get_component_mut::<VelocityComponent>().unwrap().0 = body.vel;
+        if let Ok(AngularVelocityComponent(ang_vel)) = entry.get_component_mut() {
+            *ang_vel = body.ang_vel;
+        }
+    }
+}
+
+fn resolve(a: &mut Body, b: &mut Body) {
+    let inv_mass = a.inv_mass + b.inv_mass;
+    if inv_mass == 0.0 || a.docked_to == Some(b.entity) || b.docked_to == Some(a.entity) {
+        return;
+    }
+    if !a.bound().intersects(&b.bound()) {
+        return;
+    }
+    let contact = match crate::collision::contact(&a.shape, &a.pose(), &b.shape, &b.pose()) {
+        Some(contact) => contact,
+        None => return,
+    };
+    let normal = contact.normal;
+    if !normal.magnitude2().is_normal() {
+        return;
+    }
+    let r_a = contact.contact_point - a.pos;
+    let r_b = contact.contact_point - b.pos;
+
+    // Push them apart, the lighter one further.
+    let depth = contact.penetration_depth - PENETRATION_SLOP;
+    if depth > 0.0 {
+        let correction = normal * (depth * PENETRATION_CORRECTION / inv_mass);
+        a.pos -= correction * a.inv_mass;
+        b.pos += correction * b.inv_mass;
+    }
+
+    let rel_vel = b.point_vel(r_b) - a.point_vel(r_a);
+    let closing = rel_vel.dot(normal);
+    // Already parting.
+    if closing >= 0.0 {
+        return;
+    }
+    let (inv_inertia_a, inv_inertia_b) = (a.inv_inertia, b.inv_inertia);
+    let effective_inv_mass = |dir: Vec2| {
+        inv_mass
+            + r_a.perp_dot(dir).powi(2) * inv_inertia_a
+            + r_b.perp_dot(dir).powi(2) * inv_inertia_b
+    };
+
+    let restitution = a.restitution.max(b.restitution);
+    let normal_impulse = -(1.0 + restitution) * closing / effective_inv_mass(normal);
+    a.apply_impulse(r_a, -normal * normal_impulse);
+    b.apply_impulse(r_b, normal * normal_impulse);
+
+    // Friction opposes sliding, up to a limit set by how hard they hit.
+    let rel_vel = b.point_vel(r_b) - a.point_vel(r_a);
+    let sliding = rel_vel - normal * rel_vel.dot(normal);
+    if sliding.magnitude2() < 1e-6 {
+        return;
+    }
+    let tangent = sliding.normalize();
+    let max_friction = (a.friction * b.friction).sqrt() * normal_impulse;
+    let friction_impulse = (rel_vel.dot(tangent) / effective_inv_mass(tangent)).min(max_friction);
+    a.apply_impulse(r_a, tangent * friction_impulse);
+    b.apply_impulse(r_b, -tangent * friction_impulse);
+}
@@ -0,0 +1,135 @@
+use legion::query::component;
+use legion::world::SubWorld;
+use legion::{Entity, EntityStore, IntoQuery};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiComponent;
+use crate::health::{damaging_hits, HealthComponent, Hit};
+use crate::hitbox::{HitboxComponent, HurtboxComponent};
+use crate::score::ShotComponent;
+use crate::transform::{Orphan, ParentComponent};
+use crate::weapon::{WeaponComponent, WeaponDef};
+use crate::Point;
+
+/// A piece of a larger entity, its body, which is its parent (see `ParentComponent`). A part with
+/// its own `HealthComponent` is destroyed independently. Hits on a part without one pass to the
+/// body instead.
+#[derive(Clone, Debug)]
+pub struct PartComponent {
+    /// Scales the damage passed to the body: above one for weak points, below for armor.
+    pub damage_multiplier: f32,
+}
+
+/// A part attached to a prefab.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartDef {
+    /// The prefab the part is made from. It can't have parts of its own.
+    pub prefab: String,
+    pub offset: Point,
+    #[serde(default = "PartDef::default_damage_multiplier")]
+    pub damage_multiplier: f32,
+    /// Whether the part outlives the body.
+    #[serde(default)]
+    pub orphan: Orphan,
+    /// Radians per second to circle the body at, counterclockwise. Zero keeps the part in place.
+    #[serde(default)]
+    pub orbit: f32,
+}
+
+impl PartDef {
+    fn default_damage_multiplier() -> f32 {
+        1.0
+    }
+}
+
+/// A stage of a boss fight, entered once the boss is down to `below` of its maximum health.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    pub below: f32,
+    /// Swaps the main weapon, by name in `WeaponDefs`.
+    #[serde(default)]
+    pub weapon: Option<String>,
+    #[serde(default)]
+    pub max_speed: Option<f32>,
+    /// Seconds the boss can't be hurt for on entering the phase.
+    #[serde(default)]
+    pub invulnerable: f32,
+
+    /// The weapon named above, looked up when loaded.
+    #[serde(skip)]
+    pub(crate) weapon_def: Option<WeaponDef>,
+}
+
+/// Steps an entity through phases as its health drops.
+#[derive(Clone, Debug)]
+pub struct BossComponent {
+    /// In the order they're entered, from the highest threshold down.
+    pub phases: Vec<Phase>,
+    /// How many phases have been entered.
+    pub phase: usize,
+}
+
+/// Passes damage dealt to parts without health of their own on to their bodies.
+#[legion::system]
+#[read_component(PartComponent)]
+#[read_component(ParentComponent)]
+#[read_component(HurtboxComponent)]
+#[read_component(HitboxComponent)]
+#[read_component(ShotComponent)]
+#[write_component(HealthComponent)]
+pub fn part_damage(world: &mut SubWorld) {
+    let mut passed = vec![];
+    for (&entity, part, child, hurtbox) in
+        <(Entity, &PartComponent, &ParentComponent, &HurtboxComponent)>::query()
+            .filter(!component::<HealthComponent>())
+            .iter(world)
+    {
+        for hit in damaging_hits(world, entity, hurtbox) {
+            passed.push((
+                child.parent,
+                Hit {
+                    damage: hit.damage * part.damage_multiplier,
+                    ..hit
+                },
+            ));
+        }
+    }
+
+    for (body, hit) in passed {
+        if let Ok(mut entry) = world.entry_mut(body) {
+            if let Ok(health) = entry.get_component_mut::<HealthComponent>() {
+                health.part_hits.push(hit);
+            }
+        }
+    }
+}
+
+#[legion::system(for_each)]
+pub fn boss_phase(
+    boss: &mut BossComponent,
+    health: &mut HealthComponent,
+    mut weapon: Option<&mut WeaponComponent>,
+    mut ai: Option<&mut AiComponent>,
+) {
+    if health.health == 0.0 {
+        return;
+    }
+    let fraction = health.health / health.max_health;
+    while let Some(phase) = boss.phases.get(boss.phase) {
+        if fraction > phase.below {
+            break;
+        }
+        if let (Some(weapon), Some(def)) = (weapon.as_mut(), &phase.weapon_def) {
+            weapon.def = def.clone();
+        }
+        if let (Some(ai), Some(max_speed)) = (ai.as_mut(), phase.max_speed) {
+            ai.max_speed = max_speed;
+        }
+        if phase.invulnerable > 0.0 {
+            health.invulnerable.reset(phase.invulnerable);
+        }
+        boss.phase += 1;
+    }
+}
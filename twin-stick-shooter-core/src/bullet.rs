@@ -1,7 +1,7 @@
 use legion::systems::CommandBuffer;
 use legion::Entity;
 
-use crate::hitbox::HitboxComponent;
+use crate::hitbox::{HitboxComponent, SweptComponent};
 use crate::resource::Time;
 use crate::util::Timer;
 
@@ -11,6 +11,12 @@ pub struct LifespanComponent(pub Timer);
 #[derive(Clone, Debug)]
 pub struct RemoveOnHitComponent;
 
+/// Lets a projectile pass through what it hits. Each thing is only hit once.
+#[derive(Clone, Debug, Default)]
+pub struct PierceComponent {
+    pub already_hit: Vec<Entity>,
+}
+
 #[legion::system(for_each)]
 pub fn lifespan(
     cmd: &mut CommandBuffer,
@@ -34,3 +40,25 @@ pub fn remove_on_hit(
         cmd.remove(*entity);
     }
 }
+
+/// Forgets hits on things a piercing projectile already went through, so they're only damaged once.
+/// Runs between collision detection and damage.
+#[legion::system(for_each)]
+pub fn pierce(hitbox: &mut HitboxComponent, pierce: &mut PierceComponent) {
+    let already_hit = &mut pierce.already_hit;
+    hitbox.hit_entities.retain(|entity| {
+        if already_hit.contains(entity) {
+            false
+        } else {
+            already_hit.push(*entity);
+            true
+        }
+    });
+}
+
+/// Stops a projectile at the first thing in its way rather than everything it touched this step.
+/// Only swept hitboxes know which came first. Runs between collision detection and damage.
+#[legion::system(for_each)]
+pub fn first_hit(hitbox: &mut HitboxComponent, _: &SweptComponent, _: &RemoveOnHitComponent) {
+    hitbox.hit_entities.truncate(1);
+}
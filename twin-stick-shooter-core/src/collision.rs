@@ -1,10 +1,223 @@
-use collision::CollisionStrategy;
+use cgmath::{vec2, Basis2, Decomposed, InnerSpace, Rad, Rotation2};
+use collision::{CollisionStrategy, Primitive};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::fmt::{self, Display, Formatter};
 
-use crate::Mat3;
+use crate::{Mat3, Point, Pt2, Vec2};
 
 pub type Aabb = collision::Aabb2<f32>;
 pub type Circle = collision::primitive::Circle<f32>;
+pub type Contact = collision::Contact<Pt2>;
+pub type ConvexPolygon = collision::primitive::ConvexPolygon<f32>;
+pub type Line = collision::Line2<f32>;
+pub type Particle = collision::primitive::Particle2<f32>;
+pub type Rectangle = collision::primitive::Rectangle<f32>;
 pub type Shape = collision::primitive::Primitive2<f32>;
+pub type Square = collision::primitive::Square<f32>;
+
+/// A collision shape as written in data files. There's one for each kind of `Shape`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ShapeDef {
+    /// A single point at the origin.
+    Particle,
+    /// A segment between two points.
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Circle {
+        radius: f32,
+    },
+    /// Centered on the origin.
+    Rectangle {
+        width: f32,
+        height: f32,
+    },
+    /// Centered on the origin.
+    Square {
+        size: f32,
+    },
+    /// Convex, with its vertices in counterclockwise order.
+    Polygon {
+        vertices: Vec<Point>,
+    },
+}
+
+/// Why a `ShapeDef` can't be used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShapeError {
+    /// A polygon has fewer than three vertices.
+    TooFewVertices(usize),
+    /// A polygon's vertices run clockwise, or enclose no area.
+    Clockwise,
+    /// A polygon bends inward somewhere, or crosses itself.
+    NotConvex,
+    /// A line starts and ends at the same point.
+    ZeroLength,
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::TooFewVertices(count) => {
+                write!(f, "a polygon needs at least 3 vertices, not {}", count)
+            }
+            ShapeError::Clockwise => f.write_str("polygon vertices must run counterclockwise"),
+            ShapeError::NotConvex => f.write_str("polygon must be convex"),
+            ShapeError::ZeroLength => f.write_str("a line's ends must be different points"),
+        }
+    }
+}
+
+impl ShapeDef {
+    /// Checks what the format can't express: that lines have length and that polygons are convex
+    /// and counterclockwise, as collision detection assumes.
+    pub fn validate(&self) -> Result<(), ShapeError> {
+        let vertices = match self {
+            ShapeDef::Line { from, to } if from == to => return Err(ShapeError::ZeroLength),
+            ShapeDef::Polygon { vertices } => vertices,
+            _ => return Ok(()),
+        };
+        if vertices.len() < 3 {
+            return Err(ShapeError::TooFewVertices(vertices.len()));
+        }
+        let vertex = |i: usize| {
+            let [x, y] = vertices[i % vertices.len()];
+            vec2(x, y)
+        };
+        let mut area = 0.0;
+        let mut turning = 0.0;
+        let mut bends_inward = false;
+        for i in 0..vertices.len() {
+            let (a, b, c) = (vertex(i), vertex(i + 1), vertex(i + 2));
+            area += a.perp_dot(b);
+            let (ab, bc) = (b - a, c - b);
+            let turn = ab.perp_dot(bc);
+            bends_inward |= turn < 0.0;
+            turning += turn.atan2(ab.dot(bc));
+        }
+        if area <= 0.0 {
+            Err(ShapeError::Clockwise)
+        } else if bends_inward || (turning - 2.0 * PI).abs() > 1e-3 {
+            // Only going around exactly once rules out stars, which turn left at every vertex.
+            Err(ShapeError::NotConvex)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn to_shape(&self) -> Shape {
+        match *self {
+            ShapeDef::Particle => Particle::new().into(),
+            ShapeDef::Line {
+                from: [ax, ay],
+                to: [bx, by],
+            } => Line::new(Pt2::new(ax, ay), Pt2::new(bx, by)).into(),
+            ShapeDef::Circle { radius } => Circle { radius }.into(),
+            ShapeDef::Rectangle { width, height } => Rectangle::new(width, height).into(),
+            ShapeDef::Square { size } => Square::new(size).into(),
+            ShapeDef::Polygon { ref vertices } => {
+                ConvexPolygon::new(vertices.iter().map(|&[x, y]| Pt2::new(x, y)).collect()).into()
+            }
+        }
+    }
+
+    /// The moment of inertia of a solid body of this shape and `mass`, about its origin.
+    pub fn inertia(&self, mass: f32) -> f32 {
+        match *self {
+            ShapeDef::Particle => 0.0,
+            ShapeDef::Line {
+                from: [ax, ay],
+                to: [bx, by],
+            } => {
+                // A thin rod, which needn't pass through the origin.
+                let (a, b) = (vec2(ax, ay), vec2(bx, by));
+                mass * (a.dot(a) + a.dot(b) + b.dot(b)) / 3.0
+            }
+            ShapeDef::Circle { radius } => 0.5 * mass * radius * radius,
+            ShapeDef::Rectangle { width, height } => {
+                mass * (width * width + height * height) / 12.0
+            }
+            ShapeDef::Square { size } => mass * size * size / 6.0,
+            ShapeDef::Polygon { ref vertices } => {
+                // Sums the triangles fanning out from the origin, weighted by their areas.
+                let mut area = 0.0;
+                let mut moment = 0.0;
+                for (i, &[ax, ay]) in vertices.iter().enumerate() {
+                    let [bx, by] = vertices[(i + 1) % vertices.len()];
+                    let (a, b) = (vec2(ax, ay), vec2(bx, by));
+                    let cross = a.perp_dot(b);
+                    area += cross;
+                    moment += cross * (a.dot(a) + a.dot(b) + b.dot(b));
+                }
+                if area == 0.0 {
+                    0.0
+                } else {
+                    mass * moment / (6.0 * area)
+                }
+            }
+        }
+    }
+
+    /// How far the shape reaches from its origin.
+    pub fn radius(&self) -> f32 {
+        match *self {
+            ShapeDef::Particle => 0.0,
+            ShapeDef::Line {
+                from: [ax, ay],
+                to: [bx, by],
+            } => vec2(ax, ay).magnitude().max(vec2(bx, by).magnitude()),
+            ShapeDef::Circle { radius } => radius,
+            ShapeDef::Rectangle { width, height } => vec2(width, height).magnitude() / 2.0,
+            ShapeDef::Square { size } => size * std::f32::consts::SQRT_2 / 2.0,
+            ShapeDef::Polygon { ref vertices } => vertices
+                .iter()
+                .map(|&[x, y]| vec2(x, y).magnitude())
+                .fold(0.0, f32::max),
+        }
+    }
+
+    pub fn scaled(&self, factor: f32) -> ShapeDef {
+        match *self {
+            ShapeDef::Particle => ShapeDef::Particle,
+            ShapeDef::Line {
+                from: [ax, ay],
+                to: [bx, by],
+            } => ShapeDef::Line {
+                from: [ax * factor, ay * factor],
+                to: [bx * factor, by * factor],
+            },
+            ShapeDef::Circle { radius } => ShapeDef::Circle {
+                radius: radius * factor,
+            },
+            ShapeDef::Rectangle { width, height } => ShapeDef::Rectangle {
+                width: width * factor,
+                height: height * factor,
+            },
+            ShapeDef::Square { size } => ShapeDef::Square {
+                size: size * factor,
+            },
+            ShapeDef::Polygon { ref vertices } => ShapeDef::Polygon {
+                vertices: vertices
+                    .iter()
+                    .map(|&[x, y]| [x * factor, y * factor])
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// The world-space bounds of `shape` placed by `xform`, found from its extremes along each axis
+/// so that rotated shapes stay tightly bound.
+pub fn compute_bound(shape: &Shape, xform: &Mat3) -> Aabb {
+    let support = |x, y| shape.support_point(&vec2(x, y), xform);
+    Aabb {
+        min: Pt2::new(support(-1.0, 0.0).x, support(0.0, -1.0).y),
+        max: Pt2::new(support(1.0, 0.0).x, support(0.0, 1.0).y),
+    }
+}
 
 type GJK = collision::algorithm::minkowski::GJK2<f32>;
 
@@ -19,3 +232,50 @@ pub fn test(shape_a: &Shape, xform_a: &Mat3, shape_b: &Shape, xform_b: &Mat3) ->
     )
     .is_some()
 }
+
+/// How deep `shape_b` sinks into `shape_a`, if they overlap. The contact's normal points from A
+/// toward B, the way to push B out along.
+pub fn contact(
+    shape_a: &Shape,
+    xform_a: &Mat3,
+    shape_b: &Shape,
+    xform_b: &Mat3,
+) -> Option<Contact> {
+    let gjk = GJK::new();
+    gjk.intersection(
+        &CollisionStrategy::FullResolution,
+        shape_a,
+        xform_a,
+        shape_b,
+        xform_b,
+    )
+}
+
+/// The fraction of the way from `from_b` to `to_b` that `shape_b` first touches `shape_a`, if it
+/// does on the way. Both transforms must only rotate and translate, by the same rotation.
+pub fn time_of_impact(
+    shape_a: &Shape,
+    xform_a: &Mat3,
+    shape_b: &Shape,
+    from_b: &Mat3,
+    to_b: &Mat3,
+) -> Option<f32> {
+    let gjk = GJK::new();
+    let xform_a = decompose(xform_a);
+    gjk.intersection_time_of_impact(
+        shape_a,
+        &xform_a..&xform_a,
+        shape_b,
+        &decompose(from_b)..&decompose(to_b),
+    )
+    .map(|contact| contact.time_of_impact)
+}
+
+/// Splits a transform that only rotates and translates into the parts that sweeping needs.
+fn decompose(xform: &Mat3) -> Decomposed<Vec2, Basis2<f32>> {
+    Decomposed {
+        scale: 1.0,
+        rot: Basis2::from_angle(Rad(xform.x.y.atan2(xform.x.x))),
+        disp: xform.z.truncate(),
+    }
+}
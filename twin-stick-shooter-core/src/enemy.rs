@@ -0,0 +1,59 @@
+use legion::systems::CommandBuffer;
+use legion::Entity;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::prefab::{Instance, Prefabs};
+use crate::test::ReflectWithin;
+use crate::{Pt2, Vec2};
+
+/// The usual distance from the arena's center past which enemies are turned back.
+pub const ARENA_RADIUS: f32 = 400.0;
+
+/// The kinds of enemy ship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    /// Hangs back, circling the player and sniping with leading shots.
+    Sniper,
+    /// Charges in with a short-range shotgun.
+    Charger,
+    /// A slow capital ship with turrets and an exposed engine, growing more dangerous as it's worn
+    /// down.
+    Dreadnought,
+}
+
+/// Marks an entity as an enemy ship, which waves count toward being cleared.
+#[derive(Clone, Debug)]
+pub struct EnemyComponent {
+    pub kind: EnemyKind,
+}
+
+impl EnemyKind {
+    /// The prefab this kind of enemy is built from.
+    pub fn prefab(self) -> &'static str {
+        match self {
+            EnemyKind::Sniper => "sniper",
+            EnemyKind::Charger => "charger",
+            EnemyKind::Dreadnought => "dreadnought",
+        }
+    }
+}
+
+pub fn spawn_enemy<R: Rng + ?Sized>(
+    cmd: &mut CommandBuffer,
+    rng: &mut R,
+    prefabs: &Prefabs,
+    kind: EnemyKind,
+    pos: Pt2,
+    vel: Vec2,
+    arena_radius: f32,
+) -> Entity {
+    let instance = Instance::at(pos)
+        .with_vel(vel)
+        .with_weapon_cooldown(rng.gen())
+        .with_seed(rng.gen());
+    let entity = prefabs.spawn(kind.prefab(), cmd, &instance).unwrap();
+    cmd.add_component(entity, ReflectWithin(arena_radius));
+    cmd.add_component(entity, EnemyComponent { kind });
+    entity
+}
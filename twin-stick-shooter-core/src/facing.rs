@@ -0,0 +1,53 @@
+use cgmath::num_traits::clamp;
+use cgmath::InnerSpace;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::{AngularVelocityComponent, VelocityComponent};
+use crate::position::RotationComponent;
+use crate::resource::Time;
+use crate::util::wrap_angle;
+use crate::weapon::WeaponComponent;
+
+/// Below this speed, an entity that isn't aiming keeps facing the way it was.
+const MIN_TRAVEL_SPEED: f32 = 10.0;
+
+/// Turns an entity toward where its main weapon is aimed, or else the way it's moving.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FacingComponent {
+    /// Radians per second.
+    pub turn_rate: f32,
+}
+
+/// Runs between aiming and firing, while triggers are still held.
+#[legion::system(for_each)]
+pub fn facing(
+    facing: &FacingComponent,
+    &RotationComponent(rot): &RotationComponent,
+    AngularVelocityComponent(ang_vel): &mut AngularVelocityComponent,
+    &VelocityComponent(vel): &VelocityComponent,
+    weapon: Option<&WeaponComponent>,
+    #[resource] time: &Time,
+) {
+    if time.elapsed_seconds <= 0.0 {
+        return;
+    }
+    let aim = weapon
+        .and_then(|weapon| weapon.trigger)
+        .filter(|dir| dir.magnitude2() > 1e-6);
+    let dir = match aim {
+        Some(dir) => dir,
+        None if vel.magnitude() > MIN_TRAVEL_SPEED => vel,
+        None => {
+            *ang_vel = 0.0;
+            return;
+        }
+    };
+    // Close the gap this step if the turn rate allows.
+    let gap = wrap_angle(dir.y.atan2(dir.x) - rot);
+    *ang_vel = clamp(
+        gap / time.elapsed_seconds,
+        -facing.turn_rate,
+        facing.turn_rate,
+    );
+}
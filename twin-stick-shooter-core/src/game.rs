@@ -1,26 +1,128 @@
 use ::collision::dbvt::DynamicBoundingVolumeTree;
-use legion::{Resources, Schedule, World};
+use cgmath::num_traits::zero;
+use legion::query::component;
+use legion::world::Entry;
+use legion::{Entity, IntoQuery, Resources, Schedule, World};
 use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg32;
-use std::ops::Deref;
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
 
-use crate::bullet::{lifespan_system, remove_on_hit_system};
+use crate::ai::ai_system;
+use crate::body::contact_system;
+use crate::boss::{boss_phase_system, part_damage_system};
+use crate::bullet::{first_hit_system, lifespan_system, pierce_system, remove_on_hit_system};
+use crate::facing::facing_system;
+use crate::gunner::gunner_plan_system;
 use crate::health::damage_system;
-use crate::hitbox::hitbox_system;
-use crate::interpolate::interpolate_system;
-use crate::physics::physics_system;
-use crate::player::{player_act_system, player_plan_system, player_react_system};
-use crate::resource::{CollideCounters, GuiOverrideQueue, Input, Subframe, Time};
+use crate::hitbox::{hitbox_system, HitboxComponent, HurtboxComponent};
+use crate::interpolate::{interpolate_system, InterpolateComponent};
+use crate::inventory::inventory_events_system;
+use crate::loot::pickup_magnet_system;
+use crate::module::loadout_system;
+use crate::physics::{
+    angular_physics_system, physics_system, AngularVelocityComponent, VelocityComponent,
+};
+use crate::player::{player_life_system, player_plan_system, player_react_system, PlayerComponent};
+use crate::position::{PositionComponent, RotationComponent};
+use crate::prefab::Prefabs;
+use crate::resource::{
+    CollideCounters, GameEvent, GameEventQueue, GuiOverrideQueue, Input, Subframe, Time,
+};
+use crate::score::{score_system, shot_hits_system};
+use crate::sector::{Ambient, Sector, Sectors, START_SECTOR};
+use crate::ship::ship_systems_system;
 use crate::test::reflect_within_system;
+use crate::transform::{orbit_system, propagate_transforms_system, ParentComponent};
+use crate::travel::{jump_system, Departures};
+use crate::wave::{spawner_system, wave_director_system, WaveDirector};
+use crate::weapon::{alt_weapon_system, weapon_system, WeaponDefs};
+use crate::Pt2;
+
+/// The entities in one sector, and the state the systems keep about them.
+struct SectorState {
+    world: World,
+    resources: Resources,
+    schedule: Schedule,
+}
+
+impl SectorState {
+    fn new() -> SectorState {
+        let mut resources = Resources::default();
+        resources.insert(CollideCounters::default());
+        resources.insert(WaveDirector::default());
+        resources.insert(WeaponDefs::builtin());
+        resources.insert(Prefabs::builtin());
+        resources.insert(Ambient::default());
+        resources.insert(Departures::default());
+
+        SectorState {
+            world: World::default(),
+            resources,
+            schedule: Schedule::builder()
+                .add_system(loadout_system())
+                .add_system(ship_systems_system())
+                .add_system(player_plan_system())
+                .add_system(spawner_system())
+                .add_system(ai_system())
+                .add_system(gunner_plan_system())
+                .add_system(pickup_magnet_system())
+                .add_system(facing_system())
+                .add_system(physics_system())
+                .add_system(angular_physics_system())
+                .add_system(reflect_within_system())
+                .add_system(orbit_system())
+                .add_system(propagate_transforms_system())
+                .add_system(contact_system())
+                .add_system(weapon_system())
+                .add_system(alt_weapon_system())
+                .add_system(hitbox_system(DynamicBoundingVolumeTree::new()))
+                .add_system(pierce_system())
+                .add_system(first_hit_system())
+                .add_system(shot_hits_system())
+                .add_system(player_react_system())
+                .add_system(part_damage_system())
+                .add_system(damage_system())
+                .add_system(boss_phase_system())
+                .add_system(player_life_system())
+                .add_system(jump_system())
+                .add_system(wave_director_system())
+                .add_system(score_system())
+                .add_system(lifespan_system())
+                .add_system(remove_on_hit_system())
+                .add_system(inventory_events_system())
+                .build(),
+        }
+    }
+
+    fn is_occupied(&self) -> bool {
+        <&PlayerComponent>::query()
+            .iter(&self.world)
+            .next()
+            .is_some()
+    }
+}
+
+/// Sectors nobody is in are only stepped once every this many steps, by that much time at once.
+pub const IDLE_SECTOR_STEP_INTERVAL: u64 = 10;
+
+/// Marks the entity being moved between sectors.
+struct Moving;
 
 pub struct Game {
     rng: Pcg32,
-    world: World,
     is_paused: bool,
+    tick: u64,
 
-    step_resources: Resources,
-    step_schedule: Schedule,
+    /// Every sector visited so far, by key in `sector_defs`. Sectors keep their entities and keep
+    /// running while nobody is in them, though only the current sector and those with players in
+    /// them are stepped at the full rate.
+    sectors: BTreeMap<String, SectorState>,
+    /// The sector being shown, which follows the players.
+    current: String,
+    sector_defs: Sectors,
     gui_override_queue: GuiOverrideQueue,
+    game_event_queue: GameEventQueue,
 
     interpolate_resources: Resources,
     interpolate_schedule: Schedule,
@@ -34,49 +136,101 @@ impl Game {
         });
         let rng = Pcg32::from_seed(seed);
 
-        let world = World::default();
-
-        let mut step_resources = Resources::default();
-        step_resources.insert(CollideCounters::default());
+        let mut sectors = BTreeMap::new();
+        sectors.insert(START_SECTOR.to_string(), SectorState::new());
 
         Game {
             rng,
-            world,
             is_paused: false,
+            tick: 0,
 
-            step_resources,
-            step_schedule: Schedule::builder()
-                .add_system(player_plan_system())
-                .add_system(physics_system())
-                .add_system(reflect_within_system())
-                .add_system(player_act_system())
-                .add_system(hitbox_system(DynamicBoundingVolumeTree::new()))
-                .add_system(player_react_system())
-                .add_system(damage_system())
-                .add_system(lifespan_system())
-                .add_system(remove_on_hit_system())
-                .build(),
+            sectors,
+            current: START_SECTOR.to_string(),
+            sector_defs: Sectors::builtin(),
             gui_override_queue: GuiOverrideQueue::default(),
+            game_event_queue: GameEventQueue::default(),
 
             interpolate_resources: Resources::default(),
             interpolate_schedule: Schedule::builder().add_system(interpolate_system()).build(),
         }
     }
 
+    fn current(&self) -> &SectorState {
+        &self.sectors[&self.current]
+    }
+
+    fn current_mut(&mut self) -> &mut SectorState {
+        self.sectors.get_mut(&self.current).unwrap()
+    }
+
     pub fn rng_mut(&mut self) -> &mut impl Rng {
         &mut self.rng
     }
 
+    /// The current sector's world.
     pub fn world(&self) -> &World {
-        &self.world
+        &self.current().world
     }
 
     pub fn world_mut(&mut self) -> &mut World {
-        &mut self.world
+        &mut self.current_mut().world
+    }
+
+    /// The prefabs the current sector spawns from, alongside its world to spawn them into.
+    pub fn prefabs_and_world_mut(&mut self) -> (impl Deref<Target = Prefabs> + '_, &mut World) {
+        let sector = self.current_mut();
+        (
+            sector.resources.get::<Prefabs>().unwrap(),
+            &mut sector.world,
+        )
     }
 
     pub fn rng_and_world_mut(&mut self) -> (&mut impl Rng, &mut World) {
-        (&mut self.rng, &mut self.world)
+        let world = &mut self.sectors.get_mut(&self.current).unwrap().world;
+        (&mut self.rng, world)
+    }
+
+    /// The key of the sector being shown.
+    pub fn current_sector(&self) -> &str {
+        &self.current
+    }
+
+    /// The world of any sector visited so far.
+    pub fn sector_world(&self, sector: &str) -> Option<&World> {
+        self.sectors.get(sector).map(|state| &state.world)
+    }
+
+    /// The key of the sector `entity` is in, if it's in any.
+    pub fn sector_of(&self, entity: Entity) -> Option<&str> {
+        self.sectors
+            .iter()
+            .find(|(_, state)| state.world.contains(entity))
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// The world of whichever sector `entity` is in. Players who have jumped away from the current
+    /// sector are found here.
+    pub fn world_of(&self, entity: Entity) -> Option<&World> {
+        self.sectors
+            .values()
+            .map(|state| &state.world)
+            .find(|world| world.contains(entity))
+    }
+
+    pub fn world_of_mut(&mut self, entity: Entity) -> Option<&mut World> {
+        self.sectors
+            .values_mut()
+            .map(|state| &mut state.world)
+            .find(|world| world.contains(entity))
+    }
+
+    pub fn sector_defs(&self) -> &Sectors {
+        &self.sector_defs
+    }
+
+    /// Replaces the sectors that jump gates lead to.
+    pub fn set_sector_defs(&mut self, sector_defs: Sectors) {
+        self.sector_defs = sector_defs;
     }
 
     pub fn is_paused(&self) -> bool {
@@ -87,42 +241,213 @@ impl Game {
         self.is_paused = is_paused;
     }
 
+    /// The number of steps taken so far. Clock synchronization may also move it to line up with a
+    /// server's tick.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
     pub fn gui_override_queue(&mut self) -> &GuiOverrideQueue {
         &self.gui_override_queue
     }
 
+    pub fn game_event_queue(&mut self) -> &GameEventQueue {
+        &self.game_event_queue
+    }
+
     pub fn collide_counters(&self) -> impl Deref<Target = CollideCounters> + '_ {
-        self.step_resources.get::<CollideCounters>().unwrap()
+        self.current().resources.get::<CollideCounters>().unwrap()
+    }
+
+    pub fn wave_director(&self) -> impl Deref<Target = WaveDirector> + '_ {
+        self.current().resources.get::<WaveDirector>().unwrap()
+    }
+
+    pub fn wave_director_mut(&mut self) -> impl DerefMut<Target = WaveDirector> + '_ {
+        self.current_mut()
+            .resources
+            .get_mut::<WaveDirector>()
+            .unwrap()
+    }
+
+    pub fn ambient(&self) -> impl Deref<Target = Ambient> + '_ {
+        self.current().resources.get::<Ambient>().unwrap()
+    }
+
+    pub fn set_ambient(&mut self, ambient: Ambient) {
+        self.current_mut().resources.insert(ambient);
     }
 
+    /// Forgets every sector, leaving an empty one to start over in.
     pub fn reset(&mut self) {
         self.is_paused = false;
-        self.world.clear();
+        self.sectors.clear();
+        self.current = START_SECTOR.to_string();
+        self.sectors
+            .insert(self.current.clone(), SectorState::new());
     }
 
+    /// Forgets every sector, then sets up `sector` as the current one.
+    pub fn start_in(&mut self, sector: &Sector) {
+        self.reset();
+        self.current = sector.id.clone();
+        let mut state = SectorState::new();
+        sector.build(&mut state.world, &mut state.resources, &mut self.rng);
+        self.sectors.insert(self.current.clone(), state);
+    }
+
+    /// Advances the current sector and every other sector with a player in it. Sectors nobody is
+    /// in are advanced less often, in bigger steps, so their enemies, spawners and waves carry on
+    /// cheaply until a player returns.
     pub fn step(&mut self, elapsed_seconds: f32, input: Input) {
-        self.step_resources.insert(Time { elapsed_seconds });
-        self.step_resources.insert(input);
-        self.step_resources.insert(self.rng.clone());
-        self.step_resources.insert(self.gui_override_queue.clone());
+        let idle_step = self.tick.is_multiple_of(IDLE_SECTOR_STEP_INTERVAL);
+        for (id, sector) in self.sectors.iter_mut() {
+            let elapsed_seconds = if *id == self.current || sector.is_occupied() {
+                elapsed_seconds
+            } else if idle_step {
+                elapsed_seconds * IDLE_SECTOR_STEP_INTERVAL as f32
+            } else {
+                continue;
+            };
+            let resources = &mut sector.resources;
+            resources.insert(Time { elapsed_seconds });
+            resources.insert(input.clone());
+            resources.insert(self.rng.clone());
+            resources.insert(self.gui_override_queue.clone());
+            resources.insert(self.game_event_queue.clone());
 
-        self.step_schedule
-            .execute(&mut self.world, &mut self.step_resources);
+            sector.schedule.execute(&mut sector.world, resources);
 
-        self.rng = self.step_resources.remove().unwrap();
+            self.rng = resources.remove().unwrap();
+        }
+        self.travel();
+        self.tick += 1;
+    }
+
+    /// Moves players whose jumps finished to their destinations, setting up sectors visited for
+    /// the first time. The view follows the players out of a sector they've all left.
+    fn travel(&mut self) {
+        let mut departures = vec![];
+        for (id, sector) in self.sectors.iter_mut() {
+            let mut sector_departures = sector.resources.get_mut::<Departures>().unwrap();
+            for (player, target) in sector_departures.0.drain(..) {
+                departures.push((id.clone(), player, target));
+            }
+        }
+
+        for (from, player, target) in departures {
+            let def = match self.sector_defs.get(&target.sector) {
+                Ok(def) => def,
+                // The gate leads nowhere. Stay put.
+                Err(_) => continue,
+            };
+            let arrival = match target.arrival {
+                Some([x, y]) => Pt2::new(x, y),
+                None => def.player_spawn(),
+            };
+            if !self.sectors.contains_key(&target.sector) {
+                let mut state = SectorState::new();
+                def.build(&mut state.world, &mut state.resources, &mut self.rng);
+                self.sectors.insert(target.sector.clone(), state);
+            }
+
+            if from != target.sector {
+                let mut source = self.sectors.remove(&from).unwrap();
+                let destination = self.sectors.get_mut(&target.sector).unwrap();
+                move_entity(&mut source.world, &mut destination.world, player);
+                if from == self.current && !source.is_occupied() {
+                    self.current = target.sector.clone();
+                }
+                self.sectors.insert(from, source);
+            }
+            let destination = &mut self.sectors.get_mut(&target.sector).unwrap().world;
+            arrive(destination, player, arrival);
+            self.game_event_queue.push_back(GameEvent::SectorEntered {
+                player,
+                sector: target.sector,
+            });
+        }
     }
 
     pub fn interpolate(&mut self, subframe: Subframe) {
         self.interpolate_resources.insert(subframe);
         self.interpolate_resources.insert(self.rng.clone());
 
+        let world = &mut self.sectors.get_mut(&self.current).unwrap().world;
         self.interpolate_schedule
-            .execute(&mut self.world, &mut self.interpolate_resources);
+            .execute(world, &mut self.interpolate_resources);
 
         self.rng = self.interpolate_resources.remove().unwrap();
     }
 }
 
+/// Moves an entity and all its components to another world, along with its children and theirs.
+/// Entities are unique across worlds, so they keep their IDs.
+fn move_entity(from: &mut World, to: &mut World, entity: Entity) {
+    let children: Vec<(Entity, Entity)> = <(Entity, &ParentComponent)>::query()
+        .iter(from)
+        .map(|(&child, component)| (child, component.parent))
+        .collect();
+    let mut moving = vec![entity];
+    let mut i = 0;
+    while i < moving.len() {
+        let parent = moving[i];
+        moving.extend(
+            children
+                .iter()
+                .filter(|&&(_, p)| p == parent)
+                .map(|&(child, _)| child),
+        );
+        i += 1;
+    }
+
+    for &entity in &moving {
+        from.entry(entity).unwrap().add_component(Moving);
+    }
+    to.move_from(from, &component::<Moving>());
+    for &entity in &moving {
+        let mut entry = to.entry(entity).unwrap();
+        entry.remove_component::<Moving>();
+        forget_collision_indices(&mut entry);
+    }
+}
+
+/// Places a player that just jumped in, at rest. Anything it knew about its old sector is
+/// forgotten.
+fn arrive(world: &mut World, player: Entity, pos: Pt2) {
+    let mut entry = match world.entry(player) {
+        Some(entry) => entry,
+        None => return,
+    };
+    entry.add_component(PositionComponent(pos));
+    entry.add_component(InterpolateComponent::new(pos, 0.0));
+    entry.add_component(RotationComponent(0.0));
+    entry.add_component(VelocityComponent(zero()));
+    entry.add_component(AngularVelocityComponent(0.0));
+    if let Ok(player) = entry.get_component_mut::<PlayerComponent>() {
+        player.docked_to = None;
+        player.home_station = None;
+        player.spawn_pos = pos;
+        player.jumping = None;
+    }
+    forget_collision_indices(&mut entry);
+}
+
+/// Collision indices belong to the old sector's systems.
+fn forget_collision_indices(entry: &mut Entry) {
+    if let Ok(hitbox) = entry.get_component_mut::<HitboxComponent>() {
+        hitbox.dbvt_index = None;
+    }
+    if let Ok(hurtbox) = entry.get_component_mut::<HurtboxComponent>() {
+        hurtbox.dbvt_index = None;
+        hurtbox.hit_by_entities.clear();
+    }
+}
+
 impl Default for Game {
     fn default() -> Self {
         Game::new()
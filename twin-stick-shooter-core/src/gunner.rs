@@ -0,0 +1,98 @@
+use cgmath::InnerSpace;
+use legion::world::SubWorld;
+use legion::IntoQuery;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::VelocityComponent;
+use crate::player::PlayerComponent;
+use crate::position::PositionComponent;
+use crate::weapon::WeaponComponent;
+use crate::{Pt2, Vec2};
+
+/// Aims an entity's `WeaponComponent` at the nearest player in range and pulls the trigger.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GunnerComponent {
+    pub aim: Aim,
+    /// Players farther away than this are ignored.
+    pub range: f32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Aim {
+    /// Shoot at where the target is now.
+    Direct,
+    /// Shoot at where the target will be if it keeps its velocity.
+    Leading,
+}
+
+/// Direction to fire a shot at `shot_speed` (relative to the shooter) so that it meets a target at
+/// `rel_pos` moving at `rel_vel` relative to the shooter. Returns `None` if the shot can't catch
+/// the target.
+pub fn intercept_direction(rel_pos: Vec2, rel_vel: Vec2, shot_speed: f32) -> Option<Vec2> {
+    // Solve |rel_pos + rel_vel * t| = shot_speed * t for the earliest positive t.
+    let a = rel_vel.magnitude2() - shot_speed * shot_speed;
+    let b = 2.0 * rel_pos.dot(rel_vel);
+    let c = rel_pos.magnitude2();
+    let t = if a.abs() < 1e-6 {
+        if b >= 0.0 {
+            return None;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt = discriminant.sqrt();
+        let (t1, t2) = ((-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a));
+        let (t1, t2) = (t1.min(t2), t1.max(t2));
+        if t1 > 0.0 {
+            t1
+        } else if t2 > 0.0 {
+            t2
+        } else {
+            return None;
+        }
+    };
+    Some(rel_pos + rel_vel * t)
+}
+
+#[legion::system]
+#[read_component(PositionComponent)]
+#[read_component(VelocityComponent)]
+#[read_component(PlayerComponent)]
+#[read_component(GunnerComponent)]
+#[write_component(WeaponComponent)]
+pub fn gunner_plan(world: &mut SubWorld) {
+    let players: Vec<(Pt2, Vec2)> =
+        <(&PositionComponent, &VelocityComponent, &PlayerComponent)>::query()
+            .iter(world)
+            .filter(|(_, _, player)| {
+                player.is_alive() && player.docked_to.is_none() && player.jumping.is_none()
+            })
+            .map(|(&PositionComponent(pos), &VelocityComponent(vel), _)| (pos, vel))
+            .collect();
+
+    for (&PositionComponent(pos), &VelocityComponent(vel), gunner, weapon) in <(
+        &PositionComponent,
+        &VelocityComponent,
+        &GunnerComponent,
+        &mut WeaponComponent,
+    )>::query()
+    .iter_mut(world)
+    {
+        let target = players
+            .iter()
+            .map(|&(target_pos, target_vel)| (target_pos - pos, target_vel))
+            .filter(|(rel_pos, _)| rel_pos.magnitude() <= gunner.range)
+            .min_by(|(a, _), (b, _)| a.magnitude2().partial_cmp(&b.magnitude2()).unwrap());
+        weapon.trigger = target.map(|(rel_pos, target_vel)| match gunner.aim {
+            Aim::Direct => rel_pos,
+            // Shots inherit the shooter's velocity, so lead relative to it.
+            Aim::Leading => {
+                intercept_direction(rel_pos, target_vel - vel, weapon.def.speed).unwrap_or(rel_pos)
+            }
+        });
+    }
+}
@@ -1,58 +1,167 @@
 use legion::systems::CommandBuffer;
 use legion::world::SubWorld;
 use legion::{Entity, EntityStore};
+use rand_pcg::Pcg32;
 
+use crate::enemy::EnemyComponent;
 use crate::hitbox::{HitboxComponent, HitboxEffect, HurtboxComponent};
+use crate::loot::LootComponent;
+use crate::player::PlayerComponent;
+use crate::position::PositionComponent;
+use crate::prefab::Prefabs;
 use crate::resource::Time;
+use crate::score::{RunStats, ScoreComponent, ShotComponent};
+use crate::ship::ShipSystemsComponent;
 use crate::util::Timer;
 
 #[derive(Clone, Debug)]
 pub struct HealthComponent {
+    /// For ships with `ShipSystemsComponent`, this is the hull, behind the shields.
     pub health: f32,
+    pub max_health: f32,
     pub hit_flash: Timer,
+    /// Hits are ignored until this elapses.
+    pub invulnerable: Timer,
+
+    // Intra-frame state.
+    /// Hits passed on by parts without health of their own (see `PartComponent`).
+    pub part_hits: Vec<Hit>,
+}
+
+/// Damage dealt by one hitbox, and who fired it, if anyone.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub damage: f32,
+    pub shooter: Option<Entity>,
 }
 
 impl HealthComponent {
     pub fn new(health: f32) -> HealthComponent {
         HealthComponent {
             health,
+            max_health: health,
             hit_flash: Timer::elapsed(),
+            invulnerable: Timer::elapsed(),
+            part_hits: vec![],
         }
     }
 
     pub fn is_hit_flashing(&self) -> bool {
         !self.hit_flash.is_elapsed()
     }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerable.is_elapsed()
+    }
 }
 
 const HIT_FLASH_DURATION_SECONDS: f32 = 0.05;
 
+#[allow(clippy::too_many_arguments)]
 #[legion::system(for_each)]
 #[read_component(HitboxComponent)]
+#[read_component(ShotComponent)]
+#[write_component(ScoreComponent)]
 pub fn damage(
     cmd: &mut CommandBuffer,
-    world: &SubWorld,
+    world: &mut SubWorld,
     entity: &Entity,
-    hurtbox: &HurtboxComponent,
+    hurtbox: Option<&HurtboxComponent>,
     health: &mut HealthComponent,
+    player: Option<&PlayerComponent>,
+    mut ship: Option<&mut ShipSystemsComponent>,
+    pos: Option<&PositionComponent>,
+    loot: Option<&LootComponent>,
+    enemy: Option<&EnemyComponent>,
     #[resource] time: &Time,
+    #[resource] rng: &mut Pcg32,
+    #[resource] prefabs: &Prefabs,
 ) {
     health.hit_flash.step(time);
+    health.invulnerable.step(time);
+    if health.is_invulnerable() {
+        health.part_hits.clear();
+        return;
+    }
 
     // Take damage from all colliding hitboxes.
-    for hitbox_entity in &hurtbox.hit_by_entities {
-        let hitbox: &HitboxComponent = world
-            .entry_ref(*hitbox_entity)
-            .unwrap()
-            .into_component()
-            .unwrap();
-        if let HitboxEffect::Damage(damage) = hitbox.effect {
-            health.health = (health.health - damage).max(0.0);
-            health.hit_flash.reset(HIT_FLASH_DURATION_SECONDS);
+    let mut hits = std::mem::take(&mut health.part_hits);
+    if let Some(hurtbox) = hurtbox {
+        hits.extend(damaging_hits(world, *entity, hurtbox));
+    }
+    let mut killed_by = None;
+    for Hit { damage, shooter } in hits {
+        credit(world, shooter, |stats| stats.damage_dealt += damage);
+        credit(world, Some(*entity), |stats| stats.damage_taken += damage);
+
+        // Shields go first; the hull takes whatever gets through.
+        let damage = match ship.as_mut() {
+            Some(ship) => ship.absorb(damage),
+            None => damage,
+        };
+        let was_alive = health.health > 0.0;
+        health.health = (health.health - damage).max(0.0);
+        health.hit_flash.reset(HIT_FLASH_DURATION_SECONDS);
+        if was_alive && health.health == 0.0 {
+            killed_by = shooter;
         }
     }
 
-    if health.health == 0.0 {
+    // Players aren't removed. `player_life` takes it from here.
+    if health.health == 0.0 && player.is_none() {
         cmd.remove(*entity);
+        if let (Some(&PositionComponent(pos)), Some(loot)) = (pos, loot) {
+            loot.drop_at(cmd, rng, prefabs, pos);
+        }
+        if let (Some(killer), Some(enemy)) = (killed_by, enemy) {
+            if let Some(score) = score_mut(world, killer) {
+                score.record_kill(enemy.kind);
+            }
+        }
+    }
+}
+
+/// The damaging hits `entity` took on `hurtbox` this step.
+pub(crate) fn damaging_hits(
+    world: &impl EntityStore,
+    entity: Entity,
+    hurtbox: &HurtboxComponent,
+) -> Vec<Hit> {
+    hurtbox
+        .hit_by_entities
+        .iter()
+        .filter_map(|hitbox_entity| {
+            let hitbox_entry = world.entry_ref(*hitbox_entity).unwrap();
+            let hitbox: &HitboxComponent = hitbox_entry.get_component().unwrap();
+            // A hitbox can retract a hit (see `pierce`). Both sides have to agree.
+            if !hitbox.hit_entities.contains(&entity) {
+                return None;
+            }
+            match hitbox.effect {
+                HitboxEffect::Damage(damage) => Some(Hit {
+                    damage,
+                    shooter: hitbox_entry
+                        .get_component::<ShotComponent>()
+                        .ok()
+                        .map(|shot| shot.shooter),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn score_mut<'a>(world: &'a mut SubWorld, entity: Entity) -> Option<&'a mut ScoreComponent> {
+    world
+        .entry_mut(entity)
+        .ok()?
+        .into_component_mut::<ScoreComponent>()
+        .ok()
+}
+
+/// Updates the run stats of `entity`, if it keeps score.
+fn credit(world: &mut SubWorld, entity: Option<Entity>, f: impl FnOnce(&mut RunStats)) {
+    if let Some(score) = entity.and_then(|entity| score_mut(world, entity)) {
+        f(&mut score.stats);
     }
 }
@@ -1,15 +1,18 @@
 use cgmath::{vec2, EuclideanSpace};
 use collision::dbvt::{DiscreteVisitor, DynamicBoundingVolumeTree, TreeValueWrapped};
-use collision::ComputeBound;
+use collision::Union;
 use legion::world::SubWorld;
 use legion::{Entity, EntityStore, IntoQuery};
 use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::collision::{Aabb, Shape};
-use crate::position::PositionComponent;
+use crate::collision::{compute_bound, Aabb, Shape};
+use crate::loot::Pickup;
+use crate::position::{PositionComponent, RotationComponent};
 use crate::resource::CollideCounters;
-use crate::{translation, Mat3, Vec2};
+use crate::travel::Interaction;
+use crate::{rotation, translation, Mat3, Pt2, Vec2};
 
 /// A collider that deals damage.
 #[derive(Clone, Debug)]
@@ -28,6 +31,7 @@ pub struct HitboxComponent {
 pub struct HitboxMask(u32);
 
 impl HitboxMask {
+    pub const NONE: HitboxMask = HitboxMask(0);
     pub const TARGET: HitboxMask = HitboxMask(0x00000001);
     pub const PLAYER: HitboxMask = HitboxMask(0x00000002);
 
@@ -36,11 +40,14 @@ impl HitboxMask {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum HitboxEffect {
     None,
     Damage(f32),
-    StationDock,
+    /// Lets the player that touches it interact, such as by docking.
+    Interact(Interaction),
+    /// Collected into the inventory of the player that touches it.
+    Pickup(Pickup),
 }
 
 impl Default for HitboxEffect {
@@ -61,23 +68,35 @@ pub struct HurtboxComponent {
     pub hit_by_entities: Vec<Entity>,
 }
 
-fn compute_bound(shape: &Shape, xform: &Mat3) -> Aabb {
-    match shape {
-        Shape::Circle(circle) => {
-            let local = circle.compute_bound();
-            Aabb {
-                min: local.min + xform.z.truncate(),
-                max: local.max + xform.z.truncate(),
-            }
-        }
-        _ => unimplemented!("transformed compute_bound() for `Shape`s other than `Circle`"),
+/// Tests a hitbox along the whole of its motion since the last step, so that fast ones can't pass
+/// through thin things between steps. Its hits are listed earliest first.
+#[derive(Clone, Debug)]
+pub struct SweptComponent {
+    /// Where it was when last tested.
+    pub prev_pos: Pt2,
+}
+
+/// The transform from an entity's space to the world's.
+pub(crate) fn pose(pos: Pt2, rot: Option<&RotationComponent>) -> Mat3 {
+    match rot {
+        Some(&RotationComponent(rot)) => translation(pos.to_vec()) * rotation(rot),
+        None => translation(pos.to_vec()),
     }
 }
 
+/// The pose of an entity known to have a position.
+fn pose_of(world: &impl EntityStore, entity: Entity) -> Mat3 {
+    let entry = world.entry_ref(entity).unwrap();
+    let &PositionComponent(pos) = entry.get_component().unwrap();
+    pose(pos, entry.get_component().ok())
+}
+
 #[legion::system]
 #[read_component(PositionComponent)]
+#[read_component(RotationComponent)]
 #[write_component(HitboxComponent)]
 #[write_component(HurtboxComponent)]
+#[write_component(SweptComponent)]
 pub fn hitbox(
     #[state] dbvt: &mut DynamicBoundingVolumeTree<TreeValueWrapped<Entity, Aabb>>,
     world: &mut SubWorld,
@@ -86,20 +105,30 @@ pub fn hitbox(
 ) {
     const COLLISION_MARGIN: Vec2 = vec2(25.0, 25.0);
     let mut hitbox_entities_by_dbvt_index = HashMap::new();
+    // Where each swept hitbox moved from, and what it hit along the way.
+    let mut swept_from = HashMap::new();
+    let mut swept_hits: HashMap<Entity, Vec<(f32, Entity)>> = HashMap::new();
     *counters = CollideCounters::default();
 
     // Update all hitboxes.
-    for (entity, (&PositionComponent(pos), hitbox)) in
-        <(&PositionComponent, &mut HitboxComponent)>::query()
-            .iter_chunks_mut(world)
-            .flat_map(|chunk| chunk.into_iter_entities())
+    for (entity, (&PositionComponent(pos), rot, swept, hitbox)) in <(
+        &PositionComponent,
+        Option<&RotationComponent>,
+        Option<&mut SweptComponent>,
+        &mut HitboxComponent,
+    )>::query()
+    .iter_chunks_mut(world)
+    .flat_map(|chunk| chunk.into_iter_entities())
     {
         counters.hitboxes += 1;
-        let value = TreeValueWrapped::new(
-            entity,
-            compute_bound(&hitbox.shape, &translation(pos.to_vec())),
-            COLLISION_MARGIN,
-        );
+        let mut bound = compute_bound(&hitbox.shape, &pose(pos, rot));
+        if let Some(swept) = swept {
+            let from = pose(swept.prev_pos, rot);
+            bound = bound.union(&compute_bound(&hitbox.shape, &from));
+            swept_from.insert(entity, from);
+            swept.prev_pos = pos;
+        }
+        let value = TreeValueWrapped::new(entity, bound, COLLISION_MARGIN);
         match hitbox.dbvt_index {
             Some(index) => {
                 counters.dbvt_updates += 1;
@@ -141,18 +170,13 @@ pub fn hitbox(
     // Process all collision pairs.
     let mut hurtbox_query = <&mut HurtboxComponent>::query();
     let (mut hurtbox_world, mut world) = world.split_for_query(&hurtbox_query);
-    let (position_world, mut hitbox_world) = world.split::<&PositionComponent>();
+    let (pose_world, mut hitbox_world) = world.split::<(&PositionComponent, &RotationComponent)>();
     dbvt.tick_with_rng(rng);
     for (hurtbox_entity, hurtbox) in hurtbox_query
         .iter_chunks_mut(&mut hurtbox_world)
         .flat_map(|chunk| chunk.into_iter_entities())
     {
-        let &PositionComponent(hurtbox_pos) = position_world
-            .entry_ref(hurtbox_entity)
-            .unwrap()
-            .into_component()
-            .unwrap();
-        let hurtbox_xform = translation(hurtbox_pos.to_vec());
+        let hurtbox_xform = pose_of(&pose_world, hurtbox_entity);
         let bound = compute_bound(&hurtbox.shape, &hurtbox_xform);
 
         counters.dbvt_queries += 1;
@@ -171,21 +195,40 @@ pub fn hitbox(
             if hurtbox.mask.overlaps(hitbox.mask) {
                 counters.mask_hits += 1;
 
-                let &PositionComponent(hitbox_pos) = position_world
-                    .entry_ref(hitbox_entity)
-                    .unwrap()
-                    .into_component()
-                    .unwrap();
-                if crate::collision::test(
-                    &hurtbox.shape,
-                    &hurtbox_xform,
-                    &hitbox.shape,
-                    &translation(hitbox_pos.to_vec()),
-                ) {
+                let hitbox_xform = pose_of(&pose_world, hitbox_entity);
+                let hit = match swept_from.get(&hitbox_entity) {
+                    Some(from) => match crate::collision::time_of_impact(
+                        &hurtbox.shape,
+                        &hurtbox_xform,
+                        &hitbox.shape,
+                        from,
+                        &hitbox_xform,
+                    ) {
+                        // Listed once they're all in, in order.
+                        Some(time) => {
+                            let hits = swept_hits.entry(hitbox_entity).or_default();
+                            hits.push((time, hurtbox_entity));
+                            true
+                        }
+                        None => false,
+                    },
+                    None => {
+                        let hit = crate::collision::test(
+                            &hurtbox.shape,
+                            &hurtbox_xform,
+                            &hitbox.shape,
+                            &hitbox_xform,
+                        );
+                        if hit {
+                            hitbox.hit_entities.push(hurtbox_entity);
+                        }
+                        hit
+                    }
+                };
+                if hit {
                     counters.gjk_hits += 1;
 
                     hurtbox.hit_by_entities.push(hitbox_entity);
-                    hitbox.hit_entities.push(hurtbox_entity);
                 } else {
                     counters.gjk_misses += 1;
                 }
@@ -194,4 +237,13 @@ pub fn hitbox(
             }
         }
     }
+
+    for (hitbox_entity, mut hits) in swept_hits {
+        hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut hitbox_entry = hitbox_world.entry_mut(hitbox_entity).unwrap();
+        let hitbox: &mut HitboxComponent = hitbox_entry.get_component_mut().unwrap();
+        hitbox
+            .hit_entities
+            .extend(hits.into_iter().map(|(_, hurtbox_entity)| hurtbox_entity));
+    }
 }
@@ -1,23 +1,46 @@
 use cgmath::{EuclideanSpace, VectorSpace};
 
-use crate::position::PositionComponent;
+use crate::position::{PositionComponent, RotationComponent};
 use crate::resource::Subframe;
+use crate::util::wrap_angle;
 use crate::{Pt2, Vec2};
 
 #[derive(Clone, Debug)]
 pub struct InterpolateComponent {
     pub prev_pos: Pt2,
     pub interpolated_pos: Pt2,
+    /// Zero for entities without a `RotationComponent`.
+    pub prev_rot: f32,
+    pub interpolated_rot: f32,
+}
+
+impl InterpolateComponent {
+    /// Starts out at rest at `pos`, facing `rot`.
+    pub fn new(pos: Pt2, rot: f32) -> InterpolateComponent {
+        InterpolateComponent {
+            prev_pos: pos,
+            interpolated_pos: pos,
+            prev_rot: rot,
+            interpolated_rot: rot,
+        }
+    }
 }
 
 #[legion::system(for_each)]
 pub fn interpolate(
     &PositionComponent(pos): &PositionComponent,
+    rot: Option<&RotationComponent>,
     &mut InterpolateComponent {
         prev_pos,
         ref mut interpolated_pos,
+        prev_rot,
+        ref mut interpolated_rot,
     }: &mut InterpolateComponent,
     #[resource] &Subframe(subframe): &Subframe,
 ) {
     *interpolated_pos = Pt2::from_vec(Vec2::lerp(prev_pos.to_vec(), pos.to_vec(), subframe));
+    if let Some(&RotationComponent(rot)) = rot {
+        // Turn the short way round, even across the wrap.
+        *interpolated_rot = wrap_angle(prev_rot + wrap_angle(rot - prev_rot) * subframe);
+    }
 }
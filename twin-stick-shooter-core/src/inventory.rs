@@ -0,0 +1,206 @@
+use legion::Entity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::player::PlayerComponent;
+use crate::resource::{GameEvent, GameEventQueue};
+
+/// Most items of one kind that fit in a single slot.
+pub const MAX_STACK: u32 = 10;
+
+/// Bulk materials, counted rather than stacked into slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resource {
+    Cores,
+    Scrap,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 2] = [Resource::Cores, Resource::Scrap];
+}
+
+/// Some number of one kind of item, such as a weapon or module, filling one inventory slot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InventoryError {
+    /// There isn't room for everything being added.
+    Full,
+    /// There's less on hand than is being removed.
+    NotEnough,
+}
+
+impl Display for InventoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InventoryError::Full => "not enough room",
+            InventoryError::NotEnough => "not enough on hand",
+        })
+    }
+}
+
+/// Resources and items carried by a player. Every change is all or nothing: an add or remove that
+/// can't be done in full fails without touching anything.
+#[derive(Clone, Debug)]
+pub struct Inventory {
+    // Attributes.
+    /// Most of each resource that can be carried.
+    pub resource_capacity: u32,
+    pub item_slots: usize,
+
+    // State.
+    resources: HashMap<Resource, u32>,
+    items: Vec<ItemStack>,
+    /// Whether anything changed since the last `InventoryChanged` event.
+    changed: bool,
+}
+
+impl Inventory {
+    pub fn new(resource_capacity: u32, item_slots: usize) -> Inventory {
+        Inventory {
+            resource_capacity,
+            item_slots,
+            resources: HashMap::new(),
+            items: vec![],
+            // Announce the starting contents.
+            changed: true,
+        }
+    }
+
+    pub fn count(&self, resource: Resource) -> u32 {
+        self.resources.get(&resource).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, resource: Resource, amount: u32) -> Result<(), InventoryError> {
+        let total = match self.count(resource).checked_add(amount) {
+            Some(total) if total <= self.resource_capacity => total,
+            _ => return Err(InventoryError::Full),
+        };
+        self.resources.insert(resource, total);
+        self.changed |= amount > 0;
+        Ok(())
+    }
+
+    /// Adds as much of `amount` as fits. Returns how much was added.
+    pub fn add_up_to(&mut self, resource: Resource, amount: u32) -> u32 {
+        let amount = amount.min(self.resource_capacity.saturating_sub(self.count(resource)));
+        self.add(resource, amount).unwrap();
+        amount
+    }
+
+    pub fn remove(&mut self, resource: Resource, amount: u32) -> Result<(), InventoryError> {
+        let count = self.count(resource);
+        if count < amount {
+            return Err(InventoryError::NotEnough);
+        }
+        self.resources.insert(resource, count - amount);
+        self.changed |= amount > 0;
+        Ok(())
+    }
+
+    /// Whether there's at least this much of each resource on hand.
+    pub fn can_afford(&self, costs: &[(Resource, u32)]) -> bool {
+        Resource::ALL.iter().all(|&resource| {
+            let total: u32 = costs
+                .iter()
+                .filter(|(r, _)| *r == resource)
+                .map(|(_, amount)| amount)
+                .sum();
+            self.count(resource) >= total
+        })
+    }
+
+    /// Removes several resources at once, or none of them if any are short.
+    pub fn spend(&mut self, costs: &[(Resource, u32)]) -> Result<(), InventoryError> {
+        if !self.can_afford(costs) {
+            return Err(InventoryError::NotEnough);
+        }
+        for &(resource, amount) in costs {
+            self.remove(resource, amount).unwrap();
+        }
+        Ok(())
+    }
+
+    pub fn items(&self) -> &[ItemStack] {
+        &self.items
+    }
+
+    pub fn item_count(&self, item: &str) -> u32 {
+        self.items
+            .iter()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Adds items, topping up existing stacks before starting new ones.
+    pub fn add_item(&mut self, item: &str, count: u32) -> Result<(), InventoryError> {
+        let room_in_stacks: u32 = self
+            .items
+            .iter()
+            .filter(|stack| stack.item == item)
+            .map(|stack| MAX_STACK - stack.count)
+            .sum();
+        let free_slots = self.item_slots.saturating_sub(self.items.len()) as u32;
+        if count > room_in_stacks + free_slots * MAX_STACK {
+            return Err(InventoryError::Full);
+        }
+
+        let mut left = count;
+        for stack in self.items.iter_mut().filter(|stack| stack.item == item) {
+            let moved = left.min(MAX_STACK - stack.count);
+            stack.count += moved;
+            left -= moved;
+        }
+        while left > 0 {
+            let moved = left.min(MAX_STACK);
+            self.items.push(ItemStack {
+                item: item.to_string(),
+                count: moved,
+            });
+            left -= moved;
+        }
+        self.changed |= count > 0;
+        Ok(())
+    }
+
+    /// Removes items, emptying the last stacks first.
+    pub fn remove_item(&mut self, item: &str, count: u32) -> Result<(), InventoryError> {
+        if self.item_count(item) < count {
+            return Err(InventoryError::NotEnough);
+        }
+
+        let mut left = count;
+        for stack in self
+            .items
+            .iter_mut()
+            .rev()
+            .filter(|stack| stack.item == item)
+        {
+            let moved = left.min(stack.count);
+            stack.count -= moved;
+            left -= moved;
+        }
+        self.items.retain(|stack| stack.count > 0);
+        self.changed |= count > 0;
+        Ok(())
+    }
+}
+
+/// Announces inventories that changed during the step.
+#[legion::system(for_each)]
+pub fn inventory_events(
+    entity: &Entity,
+    player: &mut PlayerComponent,
+    #[resource] game_event_queue: &GameEventQueue,
+) {
+    if player.inventory.changed {
+        player.inventory.changed = false;
+        game_event_queue.push_back(GameEvent::InventoryChanged { player: *entity });
+    }
+}
@@ -1,23 +1,55 @@
-use cgmath::vec3;
+use cgmath::{vec3, Rad};
 
+pub mod ai;
+pub mod body;
+pub mod boss;
 pub mod bullet;
 pub mod collision;
+pub mod enemy;
+pub mod facing;
 pub mod game;
+pub mod gunner;
 pub mod health;
 pub mod hitbox;
 pub mod interpolate;
+pub mod inventory;
+pub mod loot;
 pub mod model;
+pub mod module;
+pub mod net;
 pub mod physics;
 pub mod player;
 pub mod position;
+pub mod prefab;
+pub mod profile;
 pub mod resource;
+pub mod score;
+pub mod sector;
+pub mod ship;
+pub mod station;
 pub mod test;
+pub mod transform;
+pub mod travel;
 pub mod util;
+pub mod wave;
+pub mod weapon;
 
 pub type Pt2 = cgmath::Point2<f32>;
 pub type Vec2 = cgmath::Vector2<f32>;
 pub type Mat3 = cgmath::Matrix3<f32>;
 
+/// A point as written in data files, `[x, y]`.
+pub type Point = [f32; 2];
+
 pub fn translation(x: Vec2) -> Mat3 {
     Mat3::from_cols(vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), x.extend(1.0))
 }
+
+/// Counterclockwise by `angle` radians about the origin.
+pub fn rotation(angle: f32) -> Mat3 {
+    Mat3::from_angle_z(Rad(angle))
+}
+
+pub fn scale(s: f32) -> Mat3 {
+    Mat3::from_cols(vec3(s, 0.0, 0.0), vec3(0.0, s, 0.0), vec3(0.0, 0.0, 1.0))
+}
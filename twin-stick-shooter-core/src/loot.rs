@@ -0,0 +1,124 @@
+use cgmath::InnerSpace;
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::{Entity, IntoQuery};
+use rand::Rng;
+use rand_distr::Distribution;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::hitbox::HitboxEffect;
+use crate::inventory::Resource;
+use crate::physics::VelocityComponent;
+use crate::player::PlayerComponent;
+use crate::position::PositionComponent;
+use crate::prefab::{Instance, Prefabs};
+use crate::resource::Time;
+use crate::util::{clamp_magnitude, UnitDisc};
+use crate::{Pt2, Vec2};
+
+/// Fastest a freshly dropped pickup drifts away from the wreck.
+const DRIFT_SPEED: f32 = 60.0;
+
+/// Fraction of a drifting pickup's speed lost per second.
+const DRIFT_DRAG_PER_SECOND: f32 = 0.5;
+
+/// Players within this distance of a pickup pull it in.
+pub const MAGNET_RADIUS: f32 = 150.0;
+
+const MAGNET_SPEED: f32 = 400.0;
+const MAGNET_ACCEL: f32 = 1500.0;
+
+/// What a pickup gives the player who collects it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Pickup {
+    Resource(Resource, u32),
+    /// Restores this much hull.
+    Repair(f32),
+}
+
+impl Pickup {
+    /// The prefab this kind of pickup is built from. Its hitbox effect is replaced by the pickup.
+    pub fn prefab(&self) -> &'static str {
+        match self {
+            Pickup::Resource(Resource::Cores, _) => "cores_pickup",
+            Pickup::Resource(Resource::Scrap, _) => "scrap_pickup",
+            Pickup::Repair(_) => "repair_pickup",
+        }
+    }
+}
+
+/// A chance of dropping a pickup.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LootDrop {
+    pub pickup: Pickup,
+    /// Probability from zero to one.
+    pub chance: f32,
+}
+
+/// What something drops when destroyed. Each drop is rolled separately.
+#[derive(Clone, Debug)]
+pub struct LootComponent {
+    pub drops: Vec<LootDrop>,
+}
+
+impl LootComponent {
+    /// Rolls for each drop and spawns the winners around `pos`.
+    pub fn drop_at(&self, cmd: &mut CommandBuffer, rng: &mut Pcg32, prefabs: &Prefabs, pos: Pt2) {
+        for drop in &self.drops {
+            if rng.gen::<f32>() < drop.chance {
+                let vel = UnitDisc.sample(rng) * DRIFT_SPEED;
+                spawn_pickup(cmd, prefabs, drop.pickup, pos, vel);
+            }
+        }
+    }
+}
+
+/// Marks an entity as a pickup that's drawn toward nearby players.
+#[derive(Clone, Debug)]
+pub struct PickupComponent;
+
+pub fn spawn_pickup(
+    cmd: &mut CommandBuffer,
+    prefabs: &Prefabs,
+    pickup: Pickup,
+    pos: Pt2,
+    vel: Vec2,
+) -> Entity {
+    let instance = Instance::at(pos)
+        .with_vel(vel)
+        .with_effect(HitboxEffect::Pickup(pickup));
+    prefabs.spawn(pickup.prefab(), cmd, &instance).unwrap()
+}
+
+/// Pulls pickups toward the nearest player in range. Pickups out of range slow to a stop.
+#[legion::system]
+#[read_component(PositionComponent)]
+#[read_component(PlayerComponent)]
+#[read_component(PickupComponent)]
+#[write_component(VelocityComponent)]
+pub fn pickup_magnet(world: &mut SubWorld, #[resource] time: &Time) {
+    let players: Vec<Pt2> = <(&PositionComponent, &PlayerComponent)>::query()
+        .iter(world)
+        .filter(|(_, player)| player.is_alive())
+        .map(|(&PositionComponent(pos), _)| pos)
+        .collect();
+
+    let dt = time.elapsed_seconds;
+    for (&PositionComponent(pos), VelocityComponent(vel), _) in
+        <(&PositionComponent, &mut VelocityComponent, &PickupComponent)>::query().iter_mut(world)
+    {
+        let nearest = players
+            .iter()
+            .map(|&player_pos| player_pos - pos)
+            .filter(|offset| offset.magnitude2() < MAGNET_RADIUS * MAGNET_RADIUS)
+            .min_by(|a, b| a.magnitude2().partial_cmp(&b.magnitude2()).unwrap());
+        match nearest {
+            Some(offset) if offset.magnitude2() > 1e-6 => {
+                let dv = offset.normalize_to(MAGNET_SPEED) - *vel;
+                *vel += clamp_magnitude(dv, 0.0, MAGNET_ACCEL * dt);
+            }
+            _ => *vel *= (1.0 - DRIFT_DRAG_PER_SECOND * dt).max(0.0),
+        }
+    }
+}
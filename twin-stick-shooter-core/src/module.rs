@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::health::HealthComponent;
+use crate::player::PLAYER_MAX_HEALTH;
+use crate::ship::ShipSystemsComponent;
+use crate::weapon::{AltWeaponComponent, WeaponComponent};
+
+/// The modules shipped with the game, keyed by name.
+const BUILTIN_MODULES_JSON: &str = include_str!("../data/modules.json");
+
+/// The kinds of slot a module can go in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Slot {
+    Engine,
+    Reactor,
+    ShieldGenerator,
+    WeaponMount,
+    Armor,
+}
+
+/// A ship stat that modules can modify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    MaxSpeed,
+    BoostSpeed,
+    MaxForce,
+    ReactorOutput,
+    BatteryCapacity,
+    ShieldCapacity,
+    ShieldRechargeRate,
+    MaxHull,
+    /// Multiplies weapon cooldowns.
+    WeaponCooldown,
+    /// Multiplies weapon damage.
+    WeaponDamage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Op {
+    Add,
+    Multiply,
+}
+
+/// Modifiers stack in a fixed order regardless of how modules are arranged: a stat starts from its
+/// base value, then every `Add` is summed in, then the result is scaled by every `Multiply`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Modifier {
+    pub stat: Stat,
+    pub op: Op,
+    pub value: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModuleDef {
+    pub slot: Slot,
+    pub modifiers: Vec<Modifier>,
+}
+
+/// A table of module definitions by name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModuleDefs(HashMap<String, ModuleDef>);
+
+impl ModuleDefs {
+    pub fn builtin() -> ModuleDefs {
+        ModuleDefs::from_json(BUILTIN_MODULES_JSON).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<ModuleDefs> {
+        serde_json::from_str(json)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModuleDef> {
+        self.0.get(name)
+    }
+}
+
+/// A ship's stats after modules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipStats {
+    pub max_speed: f32,
+    /// Top speed while boosting.
+    pub boost_speed: f32,
+    /// Most force the engines can apply.
+    pub max_force: f32,
+    pub reactor_output: f32,
+    pub battery_capacity: f32,
+    pub shield_capacity: f32,
+    pub shield_recharge_rate: f32,
+    pub max_hull: f32,
+    pub weapon_cooldown: f32,
+    pub weapon_damage: f32,
+}
+
+impl Default for ShipStats {
+    /// A player ship with no modules.
+    fn default() -> Self {
+        ShipStats {
+            max_speed: 250.0,
+            boost_speed: 500.0,
+            max_force: 1.0e6,
+            reactor_output: 20.0,
+            battery_capacity: 100.0,
+            shield_capacity: 6.0,
+            shield_recharge_rate: 2.0,
+            max_hull: PLAYER_MAX_HEALTH,
+            weapon_cooldown: 1.0,
+            weapon_damage: 1.0,
+        }
+    }
+}
+
+impl ShipStats {
+    fn stat_mut(&mut self, stat: Stat) -> &mut f32 {
+        match stat {
+            Stat::MaxSpeed => &mut self.max_speed,
+            Stat::BoostSpeed => &mut self.boost_speed,
+            Stat::MaxForce => &mut self.max_force,
+            Stat::ReactorOutput => &mut self.reactor_output,
+            Stat::BatteryCapacity => &mut self.battery_capacity,
+            Stat::ShieldCapacity => &mut self.shield_capacity,
+            Stat::ShieldRechargeRate => &mut self.shield_recharge_rate,
+            Stat::MaxHull => &mut self.max_hull,
+            Stat::WeaponCooldown => &mut self.weapon_cooldown,
+            Stat::WeaponDamage => &mut self.weapon_damage,
+        }
+    }
+
+    /// Applies `modifiers` to these stats in the order described on `Modifier`.
+    pub fn modified<'a>(&self, modifiers: impl Iterator<Item = &'a Modifier> + Clone) -> ShipStats {
+        let mut stats = self.clone();
+        for op in &[Op::Add, Op::Multiply] {
+            for modifier in modifiers.clone().filter(|modifier| modifier.op == *op) {
+                let stat = stats.stat_mut(modifier.stat);
+                match op {
+                    Op::Add => *stat += modifier.value,
+                    Op::Multiply => *stat *= modifier.value,
+                }
+            }
+        }
+        stats
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadoutError {
+    UnknownModule,
+    /// The ship has no slot of the module's kind.
+    NoSlot,
+}
+
+impl Display for LoadoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LoadoutError::UnknownModule => "no such module",
+            LoadoutError::NoSlot => "no slot for that module",
+        })
+    }
+}
+
+/// The modules fitted to a ship and the stats they add up to.
+#[derive(Clone, Debug)]
+pub struct LoadoutComponent {
+    // Attributes.
+    pub base: ShipStats,
+
+    // State.
+    slots: Vec<(Slot, Option<String>)>,
+    stats: ShipStats,
+    /// Whether the stats changed since they were last applied to the ship.
+    changed: bool,
+}
+
+impl LoadoutComponent {
+    /// An empty loadout with one slot for each of `slots`.
+    pub fn new(base: ShipStats, slots: &[Slot]) -> LoadoutComponent {
+        LoadoutComponent {
+            stats: base.clone(),
+            base,
+            slots: slots.iter().map(|&slot| (slot, None)).collect(),
+            changed: true,
+        }
+    }
+
+    pub fn slots(&self) -> &[(Slot, Option<String>)] {
+        &self.slots
+    }
+
+    pub fn stats(&self) -> &ShipStats {
+        &self.stats
+    }
+
+    /// Where `module` would go: the first empty slot of its kind, or else the first slot of its
+    /// kind.
+    pub fn slot_for(&self, defs: &ModuleDefs, module: &str) -> Result<usize, LoadoutError> {
+        let slot = defs.get(module).ok_or(LoadoutError::UnknownModule)?.slot;
+        let mut matching = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, (s, _))| *s == slot);
+        let first = matching.clone().next().ok_or(LoadoutError::NoSlot)?.0;
+        Ok(matching
+            .find(|(_, (_, installed))| installed.is_none())
+            .map_or(first, |(index, _)| index))
+    }
+
+    /// Puts `module` in the slot at `index`, returning the module it replaces.
+    pub fn install(
+        &mut self,
+        defs: &ModuleDefs,
+        index: usize,
+        module: &str,
+    ) -> Result<Option<String>, LoadoutError> {
+        let def = defs.get(module).ok_or(LoadoutError::UnknownModule)?;
+        match self.slots.get_mut(index) {
+            Some((slot, installed)) if *slot == def.slot => {
+                let old = installed.replace(module.to_string());
+                self.recompute(defs);
+                Ok(old)
+            }
+            _ => Err(LoadoutError::NoSlot),
+        }
+    }
+
+    /// Empties the slot at `index`, returning the module that was in it.
+    pub fn uninstall(&mut self, defs: &ModuleDefs, index: usize) -> Option<String> {
+        let old = self.slots.get_mut(index)?.1.take();
+        self.recompute(defs);
+        old
+    }
+
+    fn recompute(&mut self, defs: &ModuleDefs) {
+        let modifiers = self
+            .slots
+            .iter()
+            .filter_map(|(_, installed)| installed.as_ref())
+            .filter_map(|module| defs.get(module))
+            .flat_map(|def| def.modifiers.iter());
+        self.stats = self.base.modified(modifiers);
+        self.changed = true;
+    }
+}
+
+/// Pushes changed loadout stats out to the ship's systems, hull and weapons.
+#[legion::system(for_each)]
+pub fn loadout(
+    loadout: &mut LoadoutComponent,
+    ship: Option<&mut ShipSystemsComponent>,
+    health: Option<&mut HealthComponent>,
+    weapon: Option<&mut WeaponComponent>,
+    alt_weapon: Option<&mut AltWeaponComponent>,
+) {
+    if !loadout.changed {
+        return;
+    }
+    loadout.changed = false;
+    let stats = &loadout.stats;
+
+    if let Some(ship) = ship {
+        ship.reactor_output = stats.reactor_output;
+        ship.battery_capacity = stats.battery_capacity;
+        ship.battery = ship.battery.min(ship.battery_capacity);
+        ship.shield_capacity = stats.shield_capacity;
+        ship.shield = ship.shield.min(ship.shield_capacity);
+        ship.shield_recharge_rate = stats.shield_recharge_rate;
+    }
+    if let Some(health) = health {
+        health.max_health = stats.max_hull;
+        health.health = health.health.min(health.max_health);
+    }
+    for weapon in weapon
+        .into_iter()
+        .chain(alt_weapon.map(|AltWeaponComponent(weapon)| weapon))
+    {
+        weapon.cooldown_scale = stats.weapon_cooldown;
+        weapon.damage_scale = stats.weapon_damage;
+    }
+}
@@ -0,0 +1,13 @@
+mod chat;
+mod clock;
+mod protocol;
+mod session;
+mod snapshot;
+
+pub use chat::{
+    validate_chat_text, ChatError, ChatMessage, ChatRateLimiter, ChatSender, MAX_CHAT_MESSAGE_CHARS,
+};
+pub use clock::{ClockEstimate, ClockSync, RESYNC_THRESHOLD_TICKS, TICKS_PER_SECOND};
+pub use protocol::{ClientMessage, ServerMessage};
+pub use session::{ExpiredSession, SessionTable, SESSION_GRACE_SECONDS};
+pub use snapshot::{EntitySnapshot, Snapshot};
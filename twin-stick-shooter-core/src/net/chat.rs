@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use crate::resource::Time;
+
+/// Longest chat message accepted, in characters.
+pub const MAX_CHAT_MESSAGE_CHARS: usize = 160;
+
+/// Messages a sender may fire off back to back before being throttled.
+const CHAT_BURST: f32 = 5.0;
+
+/// Rate at which a throttled sender earns back the right to send.
+const CHAT_MESSAGES_PER_SECOND: f32 = 0.5;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ChatSender {
+    /// An announcement from the server itself.
+    System,
+    Player {
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub sender: ChatSender,
+    pub text: String,
+}
+
+impl ChatMessage {
+    pub fn system(text: impl Into<String>) -> ChatMessage {
+        ChatMessage {
+            sender: ChatSender::System,
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChatError {
+    Empty,
+    TooLong,
+    RateLimited,
+}
+
+impl Display for ChatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChatError::Empty => "message is empty",
+            ChatError::TooLong => "message is too long",
+            ChatError::RateLimited => "sending too fast; wait a moment",
+        })
+    }
+}
+
+/// Normalizes chat text for display: turns control characters (including newlines) into spaces,
+/// then trims surrounding whitespace. Rejects messages that end up empty or too long.
+pub fn validate_chat_text(text: &str) -> Result<String, ChatError> {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        Err(ChatError::Empty)
+    } else if text.chars().count() > MAX_CHAT_MESSAGE_CHARS {
+        Err(ChatError::TooLong)
+    } else {
+        Ok(text.to_string())
+    }
+}
+
+/// A token bucket limiting how quickly one sender can chat.
+#[derive(Clone, Debug)]
+pub struct ChatRateLimiter {
+    tokens: f32,
+}
+
+impl ChatRateLimiter {
+    pub fn new() -> ChatRateLimiter {
+        ChatRateLimiter { tokens: CHAT_BURST }
+    }
+
+    pub fn step(&mut self, time: &Time) {
+        self.tokens =
+            (self.tokens + CHAT_MESSAGES_PER_SECOND * time.elapsed_seconds).min(CHAT_BURST);
+    }
+
+    /// Spends one message's worth of allowance, or fails if there isn't enough.
+    pub fn try_send(&mut self) -> Result<(), ChatError> {
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(ChatError::RateLimited)
+        }
+    }
+}
+
+impl Default for ChatRateLimiter {
+    fn default() -> Self {
+        ChatRateLimiter::new()
+    }
+}
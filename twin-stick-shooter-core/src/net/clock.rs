@@ -0,0 +1,128 @@
+use cgmath::num_traits::clamp;
+use std::collections::VecDeque;
+
+/// Fixed simulation rate shared by the client and server.
+pub const TICKS_PER_SECOND: f64 = 100.0;
+
+/// Distance in ticks between the client's tick and its target beyond which the client should jump
+/// straight to the target rather than dilate time to reach it.
+pub const RESYNC_THRESHOLD_TICKS: f64 = 25.0;
+
+/// Number of most recent ping/pong round trips considered by the estimate.
+const SAMPLE_WINDOW: usize = 16;
+
+/// Extra lead, in multiples of the jitter, to absorb round trips slower than the median.
+const LEAD_JITTER_FACTOR: f64 = 2.0;
+
+/// Extra lead in ticks beyond the one-way trip and jitter allowance.
+const LEAD_EXTRA_TICKS: f64 = 2.0;
+
+/// Time scale adjustment per tick of error between the client's tick and its target.
+const DILATION_PER_TICK: f32 = 0.01;
+
+/// Largest deviation of the time scale from 1.0.
+const MAX_DILATION: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    rtt: f64,
+    offset: f64,
+}
+
+/// A snapshot of the current clock synchronization estimate. All values are in seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockEstimate {
+    /// Median round-trip time.
+    pub rtt: f64,
+    /// Mean absolute deviation of recent round-trip times from the median.
+    pub jitter: f64,
+    /// Server time minus client time.
+    pub offset: f64,
+}
+
+impl ClockEstimate {
+    pub fn server_time(&self, client_time: f64) -> f64 {
+        client_time + self.offset
+    }
+
+    /// The server's current (fractional) tick as seen from the client at `client_time`.
+    pub fn server_tick(&self, client_time: f64) -> f64 {
+        self.server_time(client_time) * TICKS_PER_SECOND
+    }
+
+    /// How far ahead of the server the client should run so that its inputs for a tick reach the
+    /// server before the server simulates that tick.
+    pub fn lead(&self) -> f64 {
+        0.5 * self.rtt + LEAD_JITTER_FACTOR * self.jitter + LEAD_EXTRA_TICKS / TICKS_PER_SECOND
+    }
+
+    /// The (fractional) tick the client should be simulating at `client_time`.
+    pub fn target_tick(&self, client_time: f64) -> f64 {
+        (self.server_time(client_time) + self.lead()) * TICKS_PER_SECOND
+    }
+
+    /// Returns a time scale that gently steers `client_tick` toward the target tick: slightly above
+    /// 1.0 when the client is behind and slightly below when it's ahead.
+    pub fn time_dilation(&self, client_tick: f64, client_time: f64) -> f32 {
+        let error = (self.target_tick(client_time) - client_tick) as f32;
+        1.0 + clamp(error * DILATION_PER_TICK, -MAX_DILATION, MAX_DILATION)
+    }
+}
+
+/// Estimates round-trip time, jitter, and server clock offset from ping/pong round trips.
+#[derive(Clone, Debug, Default)]
+pub struct ClockSync {
+    samples: VecDeque<Sample>,
+    estimate: Option<ClockEstimate>,
+}
+
+impl ClockSync {
+    pub fn new() -> ClockSync {
+        ClockSync::default()
+    }
+
+    pub fn estimate(&self) -> Option<ClockEstimate> {
+        self.estimate
+    }
+
+    /// Records a completed round trip. `client_send_time` and `client_recv_time` are on the client's
+    /// clock, and `server_time` is the server's clock when it replied.
+    pub fn record_pong(&mut self, client_send_time: f64, server_time: f64, client_recv_time: f64) {
+        // Assume the reply took half the round trip to arrive.
+        let rtt = (client_recv_time - client_send_time).max(0.0);
+        let offset = server_time + 0.5 * rtt - client_recv_time;
+
+        self.samples.push_back(Sample { rtt, offset });
+        while self.samples.len() > SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.estimate = Some(self.compute_estimate());
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.estimate = None;
+    }
+
+    fn compute_estimate(&self) -> ClockEstimate {
+        let mut rtts: Vec<f64> = self.samples.iter().map(|sample| sample.rtt).collect();
+        rtts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rtt = rtts[rtts.len() / 2];
+        let jitter = rtts.iter().map(|x| (x - rtt).abs()).sum::<f64>() / rtts.len() as f64;
+
+        // The fastest round trip spent the least time queued, so its symmetric-delay assumption is
+        // the most trustworthy.
+        let offset = self
+            .samples
+            .iter()
+            .min_by(|a, b| a.rtt.partial_cmp(&b.rtt).unwrap())
+            .unwrap()
+            .offset;
+
+        ClockEstimate {
+            rtt,
+            jitter,
+            offset,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::net::{ChatMessage, Snapshot};
+use crate::resource::Input;
+
+/// A message sent from a client to the server over the reliable websocket path.
+///
+/// All times are in seconds. Client times are on the client's own clock and are echoed back
+/// verbatim, so the server never needs to interpret them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    /// Sent once when a connection opens. Carries the token from an earlier `Welcome` when the
+    /// client is reconnecting and wants its old session back.
+    Hello {
+        resume_token: Option<String>,
+    },
+    Ping {
+        client_time: f64,
+    },
+    /// A room-wide chat message. The server validates and rate limits it before relaying it.
+    Chat {
+        text: String,
+    },
+    /// The client's controls. Sent when they change, and held by the server until the next one.
+    Input(Input),
+}
+
+/// A message sent from the server to a client over the reliable websocket path.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    /// Reply to `Hello`. `resumed` is false if a fresh session had to be created, for example
+    /// because the old one's grace period ran out.
+    Welcome {
+        session_token: String,
+        resumed: bool,
+    },
+    Pong {
+        client_time: f64,
+        server_time: f64,
+    },
+    Snapshot(Snapshot),
+    Chat(ChatMessage),
+}
+
+impl ClientMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<ClientMessage> {
+        serde_json::from_str(json)
+    }
+}
+
+impl ServerMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<ServerMessage> {
+        serde_json::from_str(json)
+    }
+}
@@ -0,0 +1,121 @@
+use legion::Entity;
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::resource::Time;
+use crate::util::Timer;
+
+/// How long a disconnected session's player entity is kept alive awaiting a reconnect.
+pub const SESSION_GRACE_SECONDS: f32 = 30.0;
+
+#[derive(Clone, Debug)]
+struct Session {
+    name: String,
+    player: Entity,
+
+    /// Counts down while the session has no connection. `None` while connected.
+    grace: Option<Timer>,
+}
+
+/// A session whose grace period ran out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpiredSession {
+    pub token: String,
+    pub name: String,
+    pub player: Entity,
+}
+
+/// Tracks which player entity belongs to which session token, and how long disconnected sessions
+/// have left before they're abandoned.
+#[derive(Debug, Default)]
+pub struct SessionTable {
+    sessions: HashMap<String, Session>,
+    sessions_created: usize,
+}
+
+impl SessionTable {
+    pub fn new() -> SessionTable {
+        SessionTable::default()
+    }
+
+    /// Starts a new connected session controlling `player` and returns its token. The session is
+    /// given a display name that, unlike the token, is safe to show to other players.
+    pub fn create<R: Rng + ?Sized>(&mut self, rng: &mut R, player: Entity) -> String {
+        let token = format!("{:032x}", rng.gen::<u128>());
+        self.sessions_created += 1;
+        self.sessions.insert(
+            token.clone(),
+            Session {
+                name: format!("Pilot {}", self.sessions_created),
+                player,
+                grace: None,
+            },
+        );
+        token
+    }
+
+    /// Reattaches a connection to an existing session, returning the player entity it controls.
+    /// Returns `None` if the token is unknown or its grace period already ran out.
+    ///
+    /// A session that still looks connected can also be resumed. This happens when a client notices
+    /// a dropped connection before the server does.
+    pub fn resume(&mut self, token: &str) -> Option<Entity> {
+        let session = self.sessions.get_mut(token)?;
+        session.grace = None;
+        Some(session.player)
+    }
+
+    /// Marks a session as disconnected, starting its grace period.
+    pub fn disconnect(&mut self, token: &str) {
+        if let Some(session) = self.sessions.get_mut(token) {
+            session.grace = Some(Timer::with_remaining(SESSION_GRACE_SECONDS));
+        }
+    }
+
+    pub fn name(&self, token: &str) -> Option<&str> {
+        self.sessions
+            .get(token)
+            .map(|session| session.name.as_str())
+    }
+
+    /// The display name of the session controlling `player`, if any.
+    pub fn name_of_player(&self, player: Entity) -> Option<&str> {
+        self.sessions
+            .values()
+            .find(|session| session.player == player)
+            .map(|session| session.name.as_str())
+    }
+
+    pub fn player(&self, token: &str) -> Option<Entity> {
+        self.sessions.get(token).map(|session| session.player)
+    }
+
+    pub fn is_connected(&self, token: &str) -> bool {
+        self.sessions
+            .get(token)
+            .map(|session| session.grace.is_none())
+            .unwrap_or(false)
+    }
+
+    /// Advances grace periods, dropping expired sessions. Returns the dropped sessions so the caller
+    /// can remove their player entities from the world.
+    pub fn step(&mut self, time: &Time) -> Vec<ExpiredSession> {
+        let mut expired = vec![];
+        self.sessions.retain(|token, session| {
+            let is_expired = session
+                .grace
+                .as_mut()
+                .map(|grace| grace.step_and_is_elapsed(time))
+                .unwrap_or(false);
+            if is_expired {
+                expired.push(ExpiredSession {
+                    token: token.clone(),
+                    name: session.name.clone(),
+                    player: session.player,
+                });
+            }
+            !is_expired
+        });
+        expired
+    }
+}
@@ -0,0 +1,143 @@
+use cgmath::SquareMatrix;
+use legion::{Entity, IntoQuery};
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::health::HealthComponent;
+use crate::interpolate::InterpolateComponent;
+use crate::model::ModelComponent;
+use crate::physics::VelocityComponent;
+use crate::player::{spawn_player, PlayerComponent};
+use crate::position::{PositionComponent, RotationComponent};
+use crate::{Mat3, Pt2, Vec2};
+
+/// The full visible state of a game at one tick, sent to clients so they can resynchronize from
+/// scratch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub entities: Vec<EntitySnapshot>,
+
+    /// Index into `entities` of the receiving client's own player, if it has one.
+    pub you: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub pos: Pt2,
+    pub vel: Option<Vec2>,
+    pub model: String,
+    pub health: Option<f32>,
+}
+
+impl Snapshot {
+    /// Captures every entity with a position and a model in the sector `you` is in, or the current
+    /// sector if there's no `you`. `you` identifies the receiving client's player entity.
+    pub fn capture(game: &Game, you: Option<Entity>) -> Snapshot {
+        let world = you
+            .and_then(|you| game.world_of(you))
+            .unwrap_or_else(|| game.world());
+        let mut you_index = None;
+        let entities = <(
+            Entity,
+            &PositionComponent,
+            Option<&VelocityComponent>,
+            &ModelComponent,
+            Option<&HealthComponent>,
+        )>::query()
+        .iter(world)
+        .enumerate()
+        .map(
+            |(index, (entity, &PositionComponent(pos), vel, model, health))| {
+                if Some(*entity) == you {
+                    you_index = Some(index);
+                }
+                EntitySnapshot {
+                    pos,
+                    vel: vel.map(|&VelocityComponent(vel)| vel),
+                    model: model.name.clone(),
+                    health: health.map(|health| health.health),
+                }
+            },
+        )
+        .collect();
+
+        Snapshot {
+            tick: game.tick(),
+            entities,
+            you: you_index,
+        }
+    }
+
+    /// Replaces everything in the game's current sector with the snapshot's entities. The
+    /// receiving client's player keeps its ship, with its inventory, loadout and lives, and is
+    /// moved to where the snapshot has it. If there's no ship to keep, a fresh one is spawned. The
+    /// player's entity is returned. Everything else only drifts along at its snapshotted velocity
+    /// until the next snapshot.
+    pub fn restore(&self, game: &mut Game) -> Option<Entity> {
+        let (prefabs, world) = game.prefabs_and_world_mut();
+        let kept = match self.you {
+            Some(_) => <(Entity, &PlayerComponent)>::query()
+                .iter(world)
+                .next()
+                .map(|(&entity, _)| entity),
+            None => None,
+        };
+        let others: Vec<Entity> = <Entity>::query()
+            .iter(world)
+            .copied()
+            .filter(|&entity| Some(entity) != kept)
+            .collect();
+        for entity in others {
+            world.remove(entity);
+        }
+
+        let mut you = None;
+        for (index, snapshot) in self.entities.iter().enumerate() {
+            let entity = if Some(index) == self.you {
+                let entity = match kept {
+                    Some(entity) => {
+                        let mut entry = world.entry(entity).unwrap();
+                        entry.add_component(PositionComponent(snapshot.pos));
+                        let rot = entry
+                            .get_component::<RotationComponent>()
+                            .map_or(0.0, |&RotationComponent(rot)| rot);
+                        entry.add_component(InterpolateComponent::new(snapshot.pos, rot));
+                        // The stations it knew are gone along with everything else.
+                        let player = entry.get_component_mut::<PlayerComponent>().unwrap();
+                        player.docked_to = None;
+                        player.home_station = None;
+                        entity
+                    }
+                    None => spawn_player(world, &prefabs, snapshot.pos),
+                };
+                you = Some(entity);
+                entity
+            } else {
+                world.push((
+                    PositionComponent(snapshot.pos),
+                    InterpolateComponent::new(snapshot.pos, 0.0),
+                    ModelComponent {
+                        name: snapshot.model.clone(),
+                        transform: Mat3::identity(),
+                    },
+                ))
+            };
+            let mut entry = world.entry(entity).unwrap();
+            if let Some(vel) = snapshot.vel {
+                entry.add_component(VelocityComponent(vel));
+            }
+            if let Some(health) = snapshot.health {
+                match entry.get_component_mut::<HealthComponent>() {
+                    Ok(component) => component.health = health,
+                    Err(_) => entry.add_component(HealthComponent::new(health)),
+                }
+            }
+        }
+        you
+    }
+
+    pub fn you(&self) -> Option<&EntitySnapshot> {
+        self.you.and_then(|index| self.entities.get(index))
+    }
+}
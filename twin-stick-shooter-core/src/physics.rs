@@ -1,8 +1,9 @@
 use cgmath::num_traits::zero;
 
 use crate::interpolate::InterpolateComponent;
-use crate::position::PositionComponent;
+use crate::position::{PositionComponent, RotationComponent};
 use crate::resource::Time;
+use crate::util::wrap_angle;
 use crate::Vec2;
 
 #[derive(Clone, Debug)]
@@ -14,17 +15,35 @@ impl Default for ForceComponent {
     }
 }
 
+/// Counterclockwise, in the same units as `ForceComponent` times distance.
+#[derive(Clone, Debug, Default)]
+pub struct TorqueComponent(pub f32);
+
 #[derive(Clone, Debug)]
 pub struct MassComponent {
     mass: f32,
     inv_mass: f32,
+    /// Moment of inertia, which resists torque as mass resists force.
+    inertia: f32,
+    inv_inertia: f32,
 }
 
 impl MassComponent {
+    /// Turns like a solid disc of unit radius, until given `with_inertia`.
     pub fn new(mass: f32) -> MassComponent {
         MassComponent {
             mass,
             inv_mass: 1.0 / mass,
+            inertia: 0.5 * mass,
+            inv_inertia: 2.0 / mass,
+        }
+    }
+
+    pub fn with_inertia(self, inertia: f32) -> MassComponent {
+        MassComponent {
+            inertia,
+            inv_inertia: 1.0 / inertia,
+            ..self
         }
     }
 
@@ -35,11 +54,23 @@ impl MassComponent {
     pub fn inv_mass(&self) -> f32 {
         self.inv_mass
     }
+
+    pub fn inertia(&self) -> f32 {
+        self.inertia
+    }
+
+    pub fn inv_inertia(&self) -> f32 {
+        self.inv_inertia
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct VelocityComponent(pub Vec2);
 
+/// Radians per second, counterclockwise.
+#[derive(Clone, Debug, Default)]
+pub struct AngularVelocityComponent(pub f32);
+
 #[legion::system(for_each)]
 pub fn physics(
     #[resource] time: &Time,
@@ -58,3 +89,23 @@ pub fn physics(
     }
     *pos += *vel * time.elapsed_seconds;
 }
+
+/// Turns entities as `physics` moves them.
+#[legion::system(for_each)]
+pub fn angular_physics(
+    #[resource] time: &Time,
+    mass: Option<&MassComponent>,
+    RotationComponent(rot): &mut RotationComponent,
+    interpolate: Option<&mut InterpolateComponent>,
+    AngularVelocityComponent(ang_vel): &mut AngularVelocityComponent,
+    torque: Option<&mut TorqueComponent>,
+) {
+    if let (Some(mass), Some(TorqueComponent(torque))) = (mass, torque) {
+        *ang_vel += *torque * mass.inv_inertia() * time.elapsed_seconds;
+        *torque = 0.0;
+    }
+    if let Some(InterpolateComponent { prev_rot, .. }) = interpolate {
+        *prev_rot = *rot;
+    }
+    *rot = wrap_angle(*rot + *ang_vel * time.elapsed_seconds);
+}
@@ -1,57 +1,162 @@
-use cgmath::num_traits::{clamp, one};
-use cgmath::InnerSpace;
+use cgmath::num_traits::{clamp, zero};
+use cgmath::{vec2, InnerSpace};
 use legion::systems::CommandBuffer;
 use legion::world::SubWorld;
-use legion::{Entity, EntityStore, IntoQuery};
+use legion::{Entity, EntityStore, IntoQuery, World};
 
-use crate::bullet::{LifespanComponent, RemoveOnHitComponent};
-use crate::collision::Circle;
-use crate::hitbox::{HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent};
+use crate::body::BodyComponent;
+use crate::health::HealthComponent;
+use crate::hitbox::{HitboxComponent, HitboxEffect, HurtboxComponent};
 use crate::interpolate::InterpolateComponent;
+use crate::inventory::Inventory;
+use crate::loot::Pickup;
 use crate::model::ModelComponent;
+use crate::module::{LoadoutComponent, ShipStats, Slot};
 use crate::physics::{ForceComponent, MassComponent, VelocityComponent};
 use crate::position::PositionComponent;
-use crate::resource::{GuiOverride, GuiOverrideQueue, Input, Time};
+use crate::prefab::{Instance, Prefabs, Team};
+use crate::resource::{GameEvent, GameEventQueue, GuiOverride, GuiOverrideQueue, Input, Time};
+use crate::score::ScoreComponent;
+use crate::ship::ShipSystemsComponent;
+use crate::travel::{Interaction, Jump};
 use crate::util::{map_magnitude, Timer};
-use crate::Vec2;
+use crate::weapon::{AltWeaponComponent, WeaponComponent};
+use crate::Pt2;
+
+/// The prefab player ships are built from.
+pub const PLAYER_PREFAB: &str = "player";
+
+pub const PLAYER_MAX_HEALTH: f32 = 10.0;
+
+/// Ships a player starts with. Losing the last one ends the game.
+pub const PLAYER_LIVES: u32 = 3;
+
+/// How long a destroyed player waits before respawning.
+pub const RESPAWN_DELAY_SECONDS: f32 = 2.0;
+
+/// How long a freshly respawned player is immune to damage.
+pub const RESPAWN_INVULNERABLE_SECONDS: f32 = 2.0;
+
+/// Most of each resource a player can carry.
+pub const PLAYER_RESOURCE_CAPACITY: u32 = 9999;
+
+pub const PLAYER_ITEM_SLOTS: usize = 8;
+
+/// The slots a player ship has for modules.
+pub const PLAYER_MODULE_SLOTS: [Slot; 5] = [
+    Slot::Engine,
+    Slot::Reactor,
+    Slot::ShieldGenerator,
+    Slot::WeaponMount,
+    Slot::Armor,
+];
+
+/// Energy per second drawn while boosting.
+const BOOST_POWER: f32 = 30.0;
 
 #[derive(Clone, Debug)]
 pub struct PlayerComponent {
     // Attributes.
-    pub shoot_cooldown: Timer,
     pub inventory: Inventory,
     pub docked_to: Option<Entity>,
+    /// Lives left, including the current one.
+    pub lives: u32,
+    /// Where the player respawns: the last station docked at, or else the initial spawn point.
+    pub home_station: Option<Entity>,
+    pub spawn_pos: Pt2,
+    /// Counts down to respawning while the player is dead with lives to spare.
+    pub respawn: Option<Timer>,
+    pub jumping: Option<Jump>,
+}
 
-    // Intra-frame state.
-    pub shoot: Option<Vec2>,
+impl PlayerComponent {
+    /// Whether the player has a ship in play. Dead players have no hurtbox or model.
+    pub fn is_alive(&self) -> bool {
+        self.lives > 0 && self.respawn.is_none()
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct Inventory {}
+/// Controls for one player, overriding the shared `Input` resource. A server attaches one to each
+/// remote player's ship.
+#[derive(Clone, Debug, Default)]
+pub struct InputComponent(pub Input);
+
+/// Spawns a player ship at rest at `pos`, armed with the builtin blaster and main cannon.
+pub fn spawn_player(world: &mut World, prefabs: &Prefabs, pos: Pt2) -> Entity {
+    let stats = ShipStats::default();
+    let entity = prefabs
+        .spawn(PLAYER_PREFAB, world, &Instance::at(pos))
+        .unwrap();
+    let mut entry = world.entry(entity).unwrap();
+    entry.add_component(HealthComponent::new(stats.max_hull));
+    entry.add_component(ShipSystemsComponent::new(
+        stats.reactor_output,
+        stats.battery_capacity,
+        stats.shield_capacity,
+    ));
+    entry.add_component(LoadoutComponent::new(stats.clone(), &PLAYER_MODULE_SLOTS));
+    entry.add_component(PlayerComponent {
+        inventory: Inventory::new(PLAYER_RESOURCE_CAPACITY, PLAYER_ITEM_SLOTS),
+        docked_to: None,
+        lives: PLAYER_LIVES,
+        home_station: None,
+        spawn_pos: pos,
+        respawn: None,
+        jumping: None,
+    });
+    entry.add_component(ScoreComponent::default());
+    entity
+}
 
 #[legion::system]
 #[read_component(PositionComponent)]
 #[read_component(VelocityComponent)]
 #[read_component(MassComponent)]
+#[read_component(LoadoutComponent)]
+#[read_component(InputComponent)]
 #[write_component(ForceComponent)]
 #[write_component(PlayerComponent)]
+#[write_component(WeaponComponent)]
+#[write_component(AltWeaponComponent)]
+#[write_component(ShipSystemsComponent)]
 pub fn player_plan(world: &mut SubWorld, #[resource] time: &Time, #[resource] input: &Input) {
     let mut player_query = <(
         &VelocityComponent,
         &mut ForceComponent,
         &MassComponent,
         &mut PlayerComponent,
+        Option<&mut WeaponComponent>,
+        Option<&mut AltWeaponComponent>,
+        Option<&mut ShipSystemsComponent>,
+        Option<&LoadoutComponent>,
+        Option<&InputComponent>,
     )>::query();
     let (mut player_world, pos_world) = world.split_for_query(&player_query);
+    let shared_input = input;
 
     for (
         entity,
-        (&VelocityComponent(vel), &mut ForceComponent(ref mut force), ref mass, ref mut player),
+        (
+            &VelocityComponent(vel),
+            &mut ForceComponent(ref mut force),
+            ref mass,
+            ref mut player,
+            weapon,
+            alt_weapon,
+            ship,
+            loadout,
+            own_input,
+        ),
     ) in player_query
         .iter_chunks_mut(&mut player_world)
         .flat_map(|chunk| chunk.into_iter_entities())
     {
-        if let Some(station) = player.docked_to {
+        let input = own_input.map_or(shared_input, |InputComponent(input)| input);
+        let mut trigger = None;
+        let mut alt_trigger = None;
+        if !player.is_alive() {
+            // Dead players neither move nor shoot.
+        } else if let Some(station) = player.docked_to {
             let &PositionComponent(pos) = pos_world
                 .entry_ref(entity)
                 .unwrap()
@@ -64,63 +169,50 @@ pub fn player_plan(world: &mut SubWorld, #[resource] time: &Time, #[resource] in
                 .unwrap();
 
             *force += 1e4 * (station_pos - pos);
-
-            player.shoot = None;
+        } else if player.jumping.is_some() {
+            // Brake to a stop while the jump drive spools up.
+            *force -= vel * mass.mass() / time.elapsed_seconds;
         } else {
             let deadzoned_move = map_magnitude(input.move_, |r| clamp((r - 0.5) * 2.0, 0.0, 1.0));
-            let goal_vel = 250.0 * deadzoned_move;
+            let boosting = match ship {
+                Some(ship) if input.boost => ship.try_draw(BOOST_POWER * time.elapsed_seconds),
+                _ => false,
+            };
+            let default_stats = ShipStats::default();
+            let stats = loadout.map_or(&default_stats, |loadout| loadout.stats());
+            let speed = if boosting {
+                stats.boost_speed
+            } else {
+                stats.max_speed
+            };
+            let goal_vel = speed * deadzoned_move;
             let goal_force = (goal_vel - vel) * mass.mass() / time.elapsed_seconds;
             *force += {
                 let r = goal_force.magnitude();
-                const MAX_FORCE: f32 = 1.0e6;
-                if r < MAX_FORCE {
+                if r < stats.max_force {
                     goal_force
                 } else {
-                    goal_force.normalize_to(MAX_FORCE)
+                    goal_force.normalize_to(stats.max_force)
                 }
             };
 
-            if player.shoot_cooldown.step_and_is_elapsed(time) && input.fire {
-                // TODO: shoot only when the player's shoot input is active
-                player.shoot = Some(input.aim);
-                player.shoot_cooldown.reset(0.1);
-            } else {
-                player.shoot = None;
+            if input.fire {
+                trigger = Some(input.aim);
+            }
+            if input.alt_fire {
+                alt_trigger = Some(input.aim);
             }
         }
-    }
-}
-
-#[legion::system(for_each)]
-pub fn player_act(
-    cmd: &mut CommandBuffer,
-    &PositionComponent(pos): &PositionComponent,
-    &VelocityComponent(vel): &VelocityComponent,
-    player: &mut PlayerComponent,
-) {
-    if let Some(dir) = player.shoot {
-        let bullet_pos = pos + dir.normalize_to(20.0);
-        cmd.push((
-            PositionComponent(bullet_pos),
-            InterpolateComponent {
-                prev_pos: bullet_pos,
-                interpolated_pos: bullet_pos,
-            },
-            VelocityComponent(vel + dir.normalize_to(1000.0)),
-            LifespanComponent(Timer::with_remaining(1.0)),
-            HitboxComponent {
-                shape: Circle { radius: 5.0 }.into(),
-                dbvt_index: None,
-                mask: HitboxMask::TARGET,
-                effect: HitboxEffect::Damage(1.0),
-                hit_entities: vec![],
-            },
-            RemoveOnHitComponent,
-            ModelComponent {
-                name: "shots/lemon".to_string(),
-                transform: one(),
-            },
-        ));
+        if let Some(weapon) = weapon {
+            weapon.trigger = trigger;
+        }
+        if let Some(AltWeaponComponent(alt_weapon)) = alt_weapon {
+            // Letting go of a charged weapon fires it, but dying, docking or jumping shouldn't.
+            if !player.is_alive() || player.docked_to.is_some() || player.jumping.is_some() {
+                alt_weapon.cancel_charge();
+            }
+            alt_weapon.trigger = alt_trigger;
+        }
     }
 }
 
@@ -128,9 +220,12 @@ pub fn player_act(
 #[read_component(HitboxComponent)]
 pub fn player_react(
     world: &SubWorld,
+    entity: &Entity,
     hurtbox: &HurtboxComponent,
     player: &mut PlayerComponent,
+    health: &mut HealthComponent,
     #[resource] gui_override_queue: &GuiOverrideQueue,
+    #[resource] game_event_queue: &GameEventQueue,
 ) {
     for hitbox_entity in hurtbox.hit_by_entities.iter().copied() {
         let hitbox: &HitboxComponent = world
@@ -138,9 +233,109 @@ pub fn player_react(
             .unwrap()
             .into_component::<HitboxComponent>()
             .unwrap();
-        if let HitboxEffect::StationDock = hitbox.effect {
-            player.docked_to = Some(hitbox_entity);
-            gui_override_queue.push_back(GuiOverride::StationDocked);
+        match &hitbox.effect {
+            // A ship spooling up its jump drive is already on its way out.
+            HitboxEffect::Interact(_) if player.jumping.is_some() => (),
+            HitboxEffect::Interact(Interaction::Dock) => {
+                player.docked_to = Some(hitbox_entity);
+                player.home_station = Some(hitbox_entity);
+                gui_override_queue.push_back(GuiOverride::StationDocked);
+            }
+            HitboxEffect::Interact(Interaction::Jump(target)) => {
+                player.jumping = Some(Jump::new(target.clone()));
+                game_event_queue.push_back(GameEvent::JumpStarted {
+                    player: *entity,
+                    sector: target.sector.clone(),
+                });
+            }
+            // The pickup is used up either way. Anything that doesn't fit is lost.
+            &HitboxEffect::Pickup(Pickup::Resource(resource, amount)) => {
+                player.inventory.add_up_to(resource, amount);
+            }
+            &HitboxEffect::Pickup(Pickup::Repair(amount)) => {
+                health.health = (health.health + amount).min(health.max_health);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Handles players running out of health: spends a life, then either respawns the ship after a
+/// delay or ends the game.
+#[allow(clippy::too_many_arguments)]
+#[legion::system(for_each)]
+#[read_component(PositionComponent)]
+pub fn player_life(
+    cmd: &mut CommandBuffer,
+    world: &SubWorld,
+    entity: &Entity,
+    player: &mut PlayerComponent,
+    health: &mut HealthComponent,
+    VelocityComponent(vel): &mut VelocityComponent,
+    ship: Option<&mut ShipSystemsComponent>,
+    #[resource] prefabs: &Prefabs,
+    #[resource] time: &Time,
+    #[resource] gui_override_queue: &GuiOverrideQueue,
+    #[resource] game_event_queue: &GameEventQueue,
+) {
+    if player.is_alive() {
+        if health.health > 0.0 {
+            return;
+        }
+
+        player.lives -= 1;
+        player.docked_to = None;
+        player.jumping = None;
+        cmd.remove_component::<BodyComponent>(*entity);
+        cmd.remove_component::<HurtboxComponent>(*entity);
+        cmd.remove_component::<ModelComponent>(*entity);
+        game_event_queue.push_back(GameEvent::PlayerDied {
+            player: *entity,
+            lives: player.lives,
+        });
+        if player.lives > 0 {
+            player.respawn = Some(Timer::with_remaining(RESPAWN_DELAY_SECONDS));
+        } else {
+            gui_override_queue.push_back(GuiOverride::GameOver);
+        }
+    }
+
+    // Hold the wreck still while dead.
+    *vel = zero();
+
+    if let Some(respawn) = player.respawn.as_mut() {
+        if respawn.step_and_is_elapsed(time) {
+            player.respawn = None;
+
+            // Launch just beyond the home station, if it's still around.
+            let pos = player
+                .home_station
+                .and_then(|station| world.entry_ref(station).ok())
+                .and_then(|entry| {
+                    entry
+                        .get_component::<PositionComponent>()
+                        .ok()
+                        .map(|&PositionComponent(pos)| pos + vec2(100.0, 0.0))
+                })
+                .unwrap_or(player.spawn_pos);
+            cmd.add_component(*entity, PositionComponent(pos));
+            cmd.add_component(*entity, InterpolateComponent::new(pos, 0.0));
+            let prefab = prefabs.get(PLAYER_PREFAB).unwrap();
+            if let Some(body) = prefab.body() {
+                cmd.add_component(*entity, body);
+            }
+            if let Some(hurtbox) = prefab.hurtbox(Team::Player) {
+                cmd.add_component(*entity, hurtbox);
+            }
+            if let Some(model) = prefab.model() {
+                cmd.add_component(*entity, model);
+            }
+
+            *health = HealthComponent::new(health.max_health);
+            health.invulnerable.reset(RESPAWN_INVULNERABLE_SECONDS);
+            if let Some(ship) = ship {
+                ship.restore();
+            }
         }
     }
 }
@@ -2,3 +2,8 @@ use crate::Pt2;
 
 #[derive(Clone, Debug)]
 pub struct PositionComponent(pub Pt2);
+
+/// Which way an entity faces, in radians counterclockwise from the x axis. Kept within -π to π.
+/// Entities without one are drawn and collide unrotated.
+#[derive(Clone, Debug)]
+pub struct RotationComponent(pub f32);
@@ -0,0 +1,619 @@
+use cgmath::num_traits::zero;
+use cgmath::EuclideanSpace;
+use legion::storage::Component;
+use legion::systems::CommandBuffer;
+use legion::{Entity, World};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::ai::AiDef;
+use crate::body::{BodyComponent, BodyDef};
+use crate::boss::{BossComponent, PartComponent, PartDef, Phase};
+use crate::bullet::{LifespanComponent, PierceComponent, RemoveOnHitComponent};
+use crate::collision::{ShapeDef, ShapeError};
+use crate::facing::FacingComponent;
+use crate::gunner::GunnerComponent;
+use crate::health::HealthComponent;
+use crate::hitbox::{HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent, SweptComponent};
+use crate::interpolate::InterpolateComponent;
+use crate::loot::{LootComponent, LootDrop, PickupComponent};
+use crate::model::ModelComponent;
+use crate::physics::{
+    AngularVelocityComponent, ForceComponent, MassComponent, TorqueComponent, VelocityComponent,
+};
+use crate::position::{PositionComponent, RotationComponent};
+use crate::transform::{OrbitComponent, ParentComponent};
+use crate::util::Timer;
+use crate::weapon::{AltWeaponComponent, WeaponComponent, WeaponDef, WeaponDefs};
+use crate::{rotation, scale, translation, Pt2, Vec2};
+
+/// The prefabs shipped with the game, keyed by name.
+const BUILTIN_PREFABS_JSON: &str = include_str!("../data/prefabs.json");
+
+/// Which side an entity fights on. This decides what can hit its hurtbox and what its weapons
+/// can hit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Team {
+    Player,
+    Enemy,
+    /// Can't be hit and hits nothing with its weapons.
+    #[default]
+    Neutral,
+}
+
+impl Team {
+    /// The mask of this team's hurtboxes.
+    pub fn mask(self) -> HitboxMask {
+        match self {
+            Team::Player => HitboxMask::PLAYER,
+            Team::Enemy => HitboxMask::TARGET,
+            Team::Neutral => HitboxMask::NONE,
+        }
+    }
+
+    /// The team whose hurtboxes have `mask`. Masks belonging to no team are neutral.
+    pub fn of_mask(mask: HitboxMask) -> Team {
+        if mask.overlaps(HitboxMask::PLAYER) {
+            Team::Player
+        } else if mask.overlaps(HitboxMask::TARGET) {
+            Team::Enemy
+        } else {
+            Team::Neutral
+        }
+    }
+
+    /// The mask of hurtboxes this team's weapons hit.
+    pub fn target_mask(self) -> HitboxMask {
+        match self {
+            Team::Player => Team::Enemy.mask(),
+            Team::Enemy => Team::Player.mask(),
+            Team::Neutral => HitboxMask::NONE,
+        }
+    }
+}
+
+/// A collider that affects whatever it touches on one team.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HitboxDef {
+    pub shape: ShapeDef,
+    pub hits: Team,
+    #[serde(default)]
+    pub effect: HitboxEffect,
+}
+
+/// A template for an entity. Everything but the position and velocity is optional.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Prefab {
+    /// The key this prefab is listed under in `Prefabs`.
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub team: Team,
+    /// Gives the entity a mass, so forces move it. Without one it drifts at its initial velocity.
+    #[serde(default)]
+    pub mass: Option<f32>,
+    /// Radians per second to turn at toward its aim or heading. Without one it keeps its rotation.
+    #[serde(default)]
+    pub turn_rate: Option<f32>,
+    /// Makes the entity solid, so other bodies bump off it.
+    #[serde(default)]
+    pub body: Option<BodyDef>,
+    #[serde(default)]
+    pub hurtbox: Option<ShapeDef>,
+    #[serde(default)]
+    pub hitbox: Option<HitboxDef>,
+    #[serde(default)]
+    pub health: Option<f32>,
+    /// Seconds before the entity disappears on its own.
+    #[serde(default)]
+    pub lifespan: Option<f32>,
+    #[serde(default)]
+    pub remove_on_hit: bool,
+    /// Passes through what the hitbox hits, affecting each thing once.
+    #[serde(default)]
+    pub pierce: bool,
+    /// Tests the hitbox along its whole path each step. See `SweptComponent`.
+    #[serde(default)]
+    pub swept: bool,
+    /// The main weapon, by name in `WeaponDefs`.
+    #[serde(default)]
+    pub weapon: Option<String>,
+    #[serde(default)]
+    pub alt_weapon: Option<String>,
+    /// Fires the main weapon at players on its own.
+    #[serde(default)]
+    pub gunner: Option<GunnerComponent>,
+    /// Steers the entity on its own. Needs a mass to have any effect.
+    #[serde(default)]
+    pub ai: Option<AiDef>,
+    /// Drifts toward nearby players to be collected. See `PickupComponent`.
+    #[serde(default)]
+    pub pickup: bool,
+    #[serde(default)]
+    pub loot: Vec<LootDrop>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Spawned along with the entity, on its team.
+    #[serde(default)]
+    pub parts: Vec<PartDef>,
+    /// Makes the entity a boss that changes tactics as it takes damage.
+    #[serde(default)]
+    pub phases: Vec<Phase>,
+
+    /// The weapons and parts named above, looked up when loaded.
+    #[serde(skip)]
+    weapon_def: Option<WeaponDef>,
+    #[serde(skip)]
+    alt_weapon_def: Option<WeaponDef>,
+    #[serde(skip)]
+    part_prefabs: Vec<Prefab>,
+}
+
+/// What varies between entities spawned from the same prefab.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub pos: Pt2,
+    pub rot: f32,
+    pub vel: Vec2,
+    /// Replaces the prefab's team.
+    pub team: Option<Team>,
+    /// Fraction of each weapon's cooldown left to wait at first, from zero for ready to one.
+    /// Staggering this keeps a group spawned together from opening fire at once.
+    pub weapon_cooldown: f32,
+    /// Resizes the entity's shapes and model in proportion, so that its hitbox, or else its hurtbox
+    /// or body, reaches this far. Parts keep their own size.
+    pub radius: Option<f32>,
+    /// Multiplies the damage the hitbox deals.
+    pub damage_scale: f32,
+    /// Replaces the effect of the prefab's hitbox. Parts keep their own.
+    pub effect: Option<HitboxEffect>,
+    /// Seeds whatever the prefab rolls for each entity, like which way its AI starts wandering.
+    pub seed: u64,
+}
+
+impl Instance {
+    /// An instance at rest at `pos`.
+    pub fn at(pos: Pt2) -> Instance {
+        Instance {
+            pos,
+            rot: 0.0,
+            vel: zero(),
+            team: None,
+            weapon_cooldown: 0.0,
+            radius: None,
+            damage_scale: 1.0,
+            effect: None,
+            seed: 0,
+        }
+    }
+
+    pub fn with_rot(self, rot: f32) -> Instance {
+        Instance { rot, ..self }
+    }
+
+    pub fn with_vel(self, vel: Vec2) -> Instance {
+        Instance { vel, ..self }
+    }
+
+    pub fn with_team(self, team: Team) -> Instance {
+        Instance {
+            team: Some(team),
+            ..self
+        }
+    }
+
+    pub fn with_weapon_cooldown(self, weapon_cooldown: f32) -> Instance {
+        Instance {
+            weapon_cooldown,
+            ..self
+        }
+    }
+
+    pub fn with_radius(self, radius: f32) -> Instance {
+        Instance {
+            radius: Some(radius),
+            ..self
+        }
+    }
+
+    pub fn with_damage_scale(self, damage_scale: f32) -> Instance {
+        Instance {
+            damage_scale,
+            ..self
+        }
+    }
+
+    pub fn with_effect(self, effect: HitboxEffect) -> Instance {
+        Instance {
+            effect: Some(effect),
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Instance {
+        Instance { seed, ..self }
+    }
+}
+
+/// What every entity spawned from a prefab has.
+pub type BaseComponents = (
+    PositionComponent,
+    RotationComponent,
+    InterpolateComponent,
+    VelocityComponent,
+    AngularVelocityComponent,
+);
+
+/// Somewhere to create entities: a `World` directly, or a `CommandBuffer` to create them when
+/// it's flushed.
+pub trait SpawnTarget {
+    fn push_base(&mut self, base: BaseComponents) -> Entity;
+    fn add<C: Component>(&mut self, entity: Entity, component: C);
+}
+
+impl SpawnTarget for World {
+    fn push_base(&mut self, base: BaseComponents) -> Entity {
+        self.push(base)
+    }
+
+    fn add<C: Component>(&mut self, entity: Entity, component: C) {
+        self.entry(entity).unwrap().add_component(component);
+    }
+}
+
+impl SpawnTarget for CommandBuffer {
+    fn push_base(&mut self, base: BaseComponents) -> Entity {
+        self.push(base)
+    }
+
+    fn add<C: Component>(&mut self, entity: Entity, component: C) {
+        self.add_component(entity, component);
+    }
+}
+
+impl Prefab {
+    /// The projectiles `def` fires at `hits`. They fly straight until they hit something or their
+    /// lifetime runs out.
+    pub fn projectile(def: &WeaponDef, hits: Team) -> Prefab {
+        Prefab {
+            name: def.name.clone(),
+            hitbox: Some(HitboxDef {
+                shape: def.shape.clone(),
+                hits,
+                effect: HitboxEffect::Damage(def.damage),
+            }),
+            lifespan: Some(def.lifetime),
+            remove_on_hit: !def.pierce,
+            pierce: def.pierce,
+            swept: def.swept,
+            model: Some(def.model.clone()),
+            ..Prefab::default()
+        }
+    }
+
+    /// The hurtbox this prefab gives entities on `team`, if any.
+    pub fn hurtbox(&self, team: Team) -> Option<HurtboxComponent> {
+        self.scaled_hurtbox(team, 1.0)
+    }
+
+    pub fn body(&self) -> Option<BodyComponent> {
+        self.scaled_body(1.0)
+    }
+
+    pub fn model(&self) -> Option<ModelComponent> {
+        self.scaled_model(1.0)
+    }
+
+    fn scaled_hurtbox(&self, team: Team, factor: f32) -> Option<HurtboxComponent> {
+        self.hurtbox.as_ref().map(|shape| HurtboxComponent {
+            shape: shape.scaled(factor).to_shape(),
+            dbvt_index: None,
+            mask: team.mask(),
+            hit_by_entities: vec![],
+        })
+    }
+
+    fn scaled_body(&self, factor: f32) -> Option<BodyComponent> {
+        self.body.as_ref().map(|body| {
+            BodyDef {
+                shape: body.shape.scaled(factor),
+                ..body.clone()
+            }
+            .to_component()
+        })
+    }
+
+    fn scaled_model(&self, factor: f32) -> Option<ModelComponent> {
+        self.model.as_ref().map(|name| ModelComponent {
+            name: name.clone(),
+            transform: scale(factor),
+        })
+    }
+
+    /// The shape that decides the entity's size: the hitbox, or else the hurtbox or body.
+    fn sizing_shape(&self) -> Option<&ShapeDef> {
+        match (&self.hitbox, &self.hurtbox, &self.body) {
+            (Some(hitbox), _, _) => Some(&hitbox.shape),
+            (None, Some(hurtbox), _) => Some(hurtbox),
+            (None, None, Some(body)) => Some(&body.shape),
+            (None, None, None) => None,
+        }
+    }
+
+    /// Creates an entity from this template, placed and aimed as `instance` says.
+    pub fn spawn(&self, target: &mut impl SpawnTarget, instance: &Instance) -> Entity {
+        let pos = instance.pos;
+        let team = instance.team.unwrap_or(self.team);
+        let factor = match (instance.radius, self.sizing_shape()) {
+            (Some(radius), Some(shape)) if shape.radius() > 0.0 => radius / shape.radius(),
+            _ => 1.0,
+        };
+        let entity = target.push_base((
+            PositionComponent(pos),
+            RotationComponent(instance.rot),
+            InterpolateComponent::new(pos, instance.rot),
+            VelocityComponent(instance.vel),
+            AngularVelocityComponent::default(),
+        ));
+
+        if let Some(mass) = self.mass {
+            // Spin like a solid body of the entity's shape.
+            let shape = self
+                .body
+                .as_ref()
+                .map(|body| &body.shape)
+                .or(self.hurtbox.as_ref())
+                .or_else(|| self.hitbox.as_ref().map(|hitbox| &hitbox.shape));
+            let mass_component = match shape {
+                Some(shape) => {
+                    MassComponent::new(mass).with_inertia(shape.inertia(mass) * factor * factor)
+                }
+                None => MassComponent::new(mass),
+            };
+            target.add(entity, ForceComponent::default());
+            target.add(entity, TorqueComponent::default());
+            target.add(entity, mass_component);
+        }
+        if let Some(turn_rate) = self.turn_rate {
+            target.add(entity, FacingComponent { turn_rate });
+        }
+        if let Some(body) = self.scaled_body(factor) {
+            target.add(entity, body);
+        }
+        if let Some(hurtbox) = self.scaled_hurtbox(team, factor) {
+            target.add(entity, hurtbox);
+        }
+        if let Some(hitbox) = &self.hitbox {
+            let effect = match instance.effect.as_ref().unwrap_or(&hitbox.effect) {
+                HitboxEffect::Damage(damage) => {
+                    HitboxEffect::Damage(damage * instance.damage_scale)
+                }
+                effect => effect.clone(),
+            };
+            target.add(
+                entity,
+                HitboxComponent {
+                    shape: hitbox.shape.scaled(factor).to_shape(),
+                    dbvt_index: None,
+                    mask: hitbox.hits.mask(),
+                    effect,
+                    hit_entities: vec![],
+                },
+            );
+        }
+        if let Some(health) = self.health {
+            target.add(entity, HealthComponent::new(health));
+        }
+        if let Some(lifespan) = self.lifespan {
+            target.add(entity, LifespanComponent(Timer::with_remaining(lifespan)));
+        }
+        if self.remove_on_hit {
+            target.add(entity, RemoveOnHitComponent);
+        }
+        if self.pierce {
+            target.add(entity, PierceComponent::default());
+        }
+        if self.swept {
+            target.add(entity, SweptComponent { prev_pos: pos });
+        }
+        if let Some(def) = &self.weapon_def {
+            let weapon = WeaponComponent::new(def.clone(), team.target_mask())
+                .with_cooldown(instance.weapon_cooldown * def.cooldown);
+            target.add(entity, weapon);
+        }
+        if let Some(def) = &self.alt_weapon_def {
+            let weapon = WeaponComponent::new(def.clone(), team.target_mask())
+                .with_cooldown(instance.weapon_cooldown * def.cooldown);
+            target.add(entity, AltWeaponComponent(weapon));
+        }
+        if let Some(gunner) = &self.gunner {
+            target.add(entity, gunner.clone());
+        }
+        if let Some(ai) = &self.ai {
+            let mut rng = Pcg32::seed_from_u64(instance.seed);
+            target.add(entity, ai.to_component(&mut rng));
+        }
+        if self.pickup {
+            target.add(entity, PickupComponent);
+        }
+        if !self.loot.is_empty() {
+            target.add(
+                entity,
+                LootComponent {
+                    drops: self.loot.clone(),
+                },
+            );
+        }
+        if let Some(model) = self.scaled_model(factor) {
+            target.add(entity, model);
+        }
+        if !self.phases.is_empty() {
+            target.add(
+                entity,
+                BossComponent {
+                    phases: self.phases.clone(),
+                    phase: 0,
+                },
+            );
+        }
+
+        for (def, prefab) in self.parts.iter().zip(&self.part_prefabs) {
+            let offset = Vec2::from(def.offset);
+            let xform = translation(pos.to_vec()) * rotation(instance.rot) * translation(offset);
+            let part = prefab.spawn(
+                target,
+                &Instance {
+                    pos: Pt2::from_vec(xform.z.truncate()),
+                    team: Some(team),
+                    radius: None,
+                    effect: None,
+                    ..instance.clone()
+                },
+            );
+            target.add(part, ParentComponent::new(entity, offset, def.orphan));
+            target.add(
+                part,
+                PartComponent {
+                    damage_multiplier: def.damage_multiplier,
+                },
+            );
+            if def.orbit != 0.0 {
+                target.add(
+                    part,
+                    OrbitComponent {
+                        angular_speed: def.orbit,
+                    },
+                );
+            }
+        }
+        entity
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrefabError {
+    /// The JSON is malformed or doesn't match the format.
+    Parse(String),
+    /// A prefab names a weapon that isn't defined.
+    UnknownWeapon { prefab: String, weapon: String },
+    /// A prefab names a part that isn't defined, or that has parts of its own.
+    BadPart { prefab: String, part: String },
+    /// A prefab has a shape collision detection can't handle.
+    BadShape { prefab: String, error: ShapeError },
+    /// There's no prefab by this name.
+    NotFound(String),
+}
+
+impl Display for PrefabError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefabError::Parse(message) => write!(f, "couldn't parse prefabs: {}", message),
+            PrefabError::UnknownWeapon { prefab, weapon } => {
+                write!(f, "prefab \"{}\" has unknown weapon \"{}\"", prefab, weapon)
+            }
+            PrefabError::BadPart { prefab, part } => write!(
+                f,
+                "prefab \"{}\" has part \"{}\", which is unknown or has parts of its own",
+                prefab, part
+            ),
+            PrefabError::BadShape { prefab, error } => {
+                write!(f, "prefab \"{}\" has a bad shape: {}", prefab, error)
+            }
+            PrefabError::NotFound(name) => write!(f, "no prefab named \"{}\"", name),
+        }
+    }
+}
+
+impl From<serde_json::Error> for PrefabError {
+    fn from(e: serde_json::Error) -> Self {
+        PrefabError::Parse(e.to_string())
+    }
+}
+
+/// A table of prefabs by name.
+#[derive(Clone, Debug, Default)]
+pub struct Prefabs(HashMap<String, Prefab>);
+
+impl Prefabs {
+    pub fn builtin() -> Prefabs {
+        Prefabs::from_json(BUILTIN_PREFABS_JSON, &WeaponDefs::builtin()).unwrap()
+    }
+
+    /// Loads prefabs, looking up the weapons they name in `weapon_defs`.
+    pub fn from_json(json: &str, weapon_defs: &WeaponDefs) -> Result<Prefabs, PrefabError> {
+        let mut prefabs: HashMap<String, Prefab> = serde_json::from_str(json)?;
+        for (name, prefab) in &mut prefabs {
+            prefab.name = name.clone();
+            let shapes = prefab
+                .body
+                .iter()
+                .map(|body| &body.shape)
+                .chain(&prefab.hurtbox)
+                .chain(prefab.hitbox.iter().map(|hitbox| &hitbox.shape));
+            for shape in shapes {
+                shape.validate().map_err(|error| PrefabError::BadShape {
+                    prefab: name.clone(),
+                    error,
+                })?;
+            }
+            let lookup = |weapon: &Option<String>| match weapon {
+                Some(weapon) => match weapon_defs.get(weapon) {
+                    Some(def) => Ok(Some(def.clone())),
+                    None => Err(PrefabError::UnknownWeapon {
+                        prefab: name.clone(),
+                        weapon: weapon.clone(),
+                    }),
+                },
+                None => Ok(None),
+            };
+            prefab.weapon_def = lookup(&prefab.weapon)?;
+            prefab.alt_weapon_def = lookup(&prefab.alt_weapon)?;
+            for phase in &mut prefab.phases {
+                phase.weapon_def = lookup(&phase.weapon)?;
+            }
+            prefab
+                .phases
+                .sort_by(|a, b| b.below.partial_cmp(&a.below).unwrap());
+        }
+
+        // Parts are copied in whole, so look them up once the rest is resolved.
+        let names: Vec<String> = prefabs.keys().cloned().collect();
+        for name in names {
+            let part_prefabs = prefabs[&name]
+                .parts
+                .iter()
+                .map(|def| match prefabs.get(&def.prefab) {
+                    Some(part) if part.parts.is_empty() => Ok(part.clone()),
+                    _ => Err(PrefabError::BadPart {
+                        prefab: name.clone(),
+                        part: def.prefab.clone(),
+                    }),
+                })
+                .collect::<Result<_, _>>()?;
+            prefabs.get_mut(&name).unwrap().part_prefabs = part_prefabs;
+        }
+        Ok(Prefabs(prefabs))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.0.get(name)
+    }
+
+    /// Spawns the prefab `name`.
+    pub fn spawn(
+        &self,
+        name: &str,
+        target: &mut impl SpawnTarget,
+        instance: &Instance,
+    ) -> Result<Entity, PrefabError> {
+        let prefab = self
+            .get(name)
+            .ok_or_else(|| PrefabError::NotFound(name.to_string()))?;
+        Ok(prefab.spawn(target, instance))
+    }
+}
@@ -0,0 +1,315 @@
+use legion::{Entity, EntityStore, World};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::inventory::{Inventory, ItemStack, Resource};
+use crate::module::{LoadoutComponent, ModuleDefs};
+use crate::player::PlayerComponent;
+use crate::weapon::{WeaponComponent, WeaponDefs};
+
+/// The version written by this build.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// The storage key profiles are saved under.
+pub const PROFILE_KEY: &str = "profile";
+
+/// Best runs kept in a profile.
+pub const MAX_BEST_SCORES: usize = 10;
+
+/// The hull every pilot starts out able to fly.
+pub const STARTER_SHIP: &str = "player";
+
+/// Upgrades a profile's JSON from one version to the next.
+pub type Migration = fn(&mut Value);
+
+/// Migration `i` takes a version `i + 1` profile to version `i + 2`.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Somewhere to keep saved data between sessions, as text under string keys.
+pub trait Storage {
+    /// The text saved under `key`, or `None` if nothing has been.
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError>;
+    fn write(&mut self, key: &str, text: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StorageError {
+    /// The backing store can't be used at all, such as when the browser blocks it.
+    Unavailable,
+    /// Reading or writing failed.
+    Io(String),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Unavailable => f.write_str("storage unavailable"),
+            StorageError::Io(message) => write!(f, "storage error: {}", message),
+        }
+    }
+}
+
+/// Saves each key as a JSON file in a directory. For native tools and tests.
+#[derive(Clone, Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> FileStorage {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl Storage for FileStorage {
+    fn read(&self, key: &str) -> Result<Option<String>, StorageError> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StorageError::Io(e.to_string())),
+        }
+    }
+
+    /// Writes to a temporary file first, so a failed save leaves the old one intact.
+    fn write(&mut self, key: &str, text: &str) -> Result<(), StorageError> {
+        let io_error = |e: std::io::Error| StorageError::Io(e.to_string());
+        fs::create_dir_all(&self.dir).map_err(io_error)?;
+        let tmp = self.dir.join(format!("{}.json.tmp", key));
+        fs::write(&tmp, text).map_err(io_error)?;
+        fs::rename(&tmp, self.path(key)).map_err(io_error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProfileError {
+    Storage(StorageError),
+    /// The saved profile isn't valid JSON or doesn't have the expected shape.
+    Corrupt(String),
+    /// The profile was saved by a newer version of the game.
+    TooNew {
+        version: u32,
+    },
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Storage(e) => e.fmt(f),
+            ProfileError::Corrupt(message) => write!(f, "profile is corrupt: {}", message),
+            ProfileError::TooNew { version } => write!(
+                f,
+                "profile version {} is newer than this game supports ({})",
+                version, PROFILE_VERSION
+            ),
+        }
+    }
+}
+
+impl From<StorageError> for ProfileError {
+    fn from(e: StorageError) -> Self {
+        ProfileError::Storage(e)
+    }
+}
+
+impl From<serde_json::Error> for ProfileError {
+    fn from(e: serde_json::Error) -> Self {
+        ProfileError::Corrupt(e.to_string())
+    }
+}
+
+/// Player preferences.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Draw collision shapes instead of models.
+    pub draw_hitboxes: bool,
+}
+
+/// A finished run, for the high score table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BestScore {
+    pub score: u64,
+    /// The wave the run ended on.
+    pub wave: u32,
+}
+
+/// Everything about a pilot that lasts between sessions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub version: u32,
+    /// Cores and scrap, which stations take as payment.
+    pub resources: HashMap<Resource, u32>,
+    pub items: Vec<ItemStack>,
+    /// The equipped main weapon.
+    pub weapon: Option<String>,
+    /// The module in each of the ship's slots.
+    pub modules: Vec<Option<String>>,
+    pub unlocked_ships: BTreeSet<String>,
+    /// Every module the pilot has owned.
+    pub unlocked_modules: BTreeSet<String>,
+    pub settings: Settings,
+    /// Highest first.
+    pub best_scores: Vec<BestScore>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            version: PROFILE_VERSION,
+            resources: HashMap::new(),
+            items: vec![],
+            weapon: None,
+            modules: vec![],
+            unlocked_ships: [STARTER_SHIP.to_string()].iter().cloned().collect(),
+            unlocked_modules: BTreeSet::new(),
+            settings: Settings::default(),
+            best_scores: vec![],
+        }
+    }
+}
+
+/// Brings profile JSON up to the version `migrations` ends at, applying each migration past the
+/// profile's own version in turn. Profiles without a version are taken to be version 1.
+pub fn upgrade(mut value: Value, migrations: &[Migration]) -> Result<Value, ProfileError> {
+    let current = migrations.len() as u32 + 1;
+    let version = match value.get("version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| ProfileError::Corrupt("version isn't a number".to_string()))?
+            as u32,
+        None => 1,
+    };
+    if version > current {
+        return Err(ProfileError::TooNew { version });
+    }
+    for migration in &migrations[version.max(1) as usize - 1..] {
+        migration(&mut value);
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_string(), current.into());
+    }
+    Ok(value)
+}
+
+impl Profile {
+    pub fn from_json(json: &str) -> Result<Profile, ProfileError> {
+        let value = upgrade(serde_json::from_str(json)?, MIGRATIONS)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Loads the saved profile, or a fresh one if there isn't one yet.
+    pub fn load(storage: &dyn Storage) -> Result<Profile, ProfileError> {
+        match storage.read(PROFILE_KEY)? {
+            Some(json) => Profile::from_json(&json),
+            None => Ok(Profile::default()),
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), ProfileError> {
+        Ok(storage.write(PROFILE_KEY, &self.to_json())?)
+    }
+
+    /// Copies a player's inventory and ship into the profile, unlocking any modules among them.
+    pub fn capture(&mut self, world: &World, player: Entity, module_defs: &ModuleDefs) {
+        let entry = match world.entry_ref(player) {
+            Ok(entry) => entry,
+            Err(_) => return,
+        };
+        if let Ok(player) = entry.get_component::<PlayerComponent>() {
+            let inventory = &player.inventory;
+            self.resources = Resource::ALL
+                .iter()
+                .map(|&resource| (resource, inventory.count(resource)))
+                .collect();
+            self.items = inventory.items().to_vec();
+        }
+        if let Ok(weapon) = entry.get_component::<WeaponComponent>() {
+            self.weapon = Some(weapon.def.name.clone());
+        }
+        if let Ok(loadout) = entry.get_component::<LoadoutComponent>() {
+            self.modules = loadout
+                .slots()
+                .iter()
+                .map(|(_, installed)| installed.clone())
+                .collect();
+        }
+
+        let installed = self.modules.iter().flatten();
+        let stored = self.items.iter().map(|stack| &stack.item);
+        let owned: Vec<String> = installed
+            .chain(stored)
+            .filter(|item| module_defs.get(item).is_some())
+            .cloned()
+            .collect();
+        self.unlocked_modules.extend(owned);
+    }
+
+    /// Gives a freshly spawned player the profile's inventory and ship. Anything that no longer
+    /// exists or no longer fits is left out.
+    pub fn restore(
+        &self,
+        world: &mut World,
+        player: Entity,
+        weapon_defs: &WeaponDefs,
+        module_defs: &ModuleDefs,
+    ) {
+        let mut entry = match world.entry_mut(player) {
+            Ok(entry) => entry,
+            Err(_) => return,
+        };
+        if let Ok(player) = entry.get_component_mut::<PlayerComponent>() {
+            let old = &player.inventory;
+            let mut inventory = Inventory::new(old.resource_capacity, old.item_slots);
+            for (&resource, &amount) in &self.resources {
+                inventory.add_up_to(resource, amount);
+            }
+            for stack in &self.items {
+                let _ = inventory.add_item(&stack.item, stack.count);
+            }
+            player.inventory = inventory;
+        }
+        if let (Some(name), Ok(weapon)) =
+            (&self.weapon, entry.get_component_mut::<WeaponComponent>())
+        {
+            if let Some(def) = weapon_defs.get(name) {
+                *weapon = WeaponComponent::new(def.clone(), weapon.target_mask);
+            }
+        }
+        if let Ok(loadout) = entry.get_component_mut::<LoadoutComponent>() {
+            for (index, module) in self.modules.iter().enumerate() {
+                if let Some(module) = module {
+                    let _ = loadout.install(module_defs, index, module);
+                }
+            }
+        }
+    }
+
+    /// Adds a finished run to the best scores. Returns whether it made the table.
+    pub fn record_score(&mut self, score: u64, wave: u32) -> bool {
+        let rank = self
+            .best_scores
+            .iter()
+            .position(|best| score > best.score)
+            .unwrap_or(self.best_scores.len());
+        if rank >= MAX_BEST_SCORES {
+            return false;
+        }
+        self.best_scores.insert(rank, BestScore { score, wave });
+        self.best_scores.truncate(MAX_BEST_SCORES);
+        true
+    }
+}
@@ -1,3 +1,6 @@
+use cgmath::num_traits::zero;
+use legion::Entity;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
@@ -15,17 +18,35 @@ pub struct Subframe(pub f32);
 
 /// TODO: These fields are an incoherent mix of player inputs and standard-mapped gamepad inputs.
 /// Pick one.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Input {
     pub move_: Vec2,
     pub aim: Vec2,
     pub fire: bool,
+    pub alt_fire: bool,
+    pub boost: bool,
     pub dpad_up: bool,
     pub dpad_down: bool,
     pub confirm: bool,
     pub start: bool,
 }
 
+impl Default for Input {
+    fn default() -> Self {
+        Input {
+            move_: zero(),
+            aim: zero(),
+            fire: false,
+            alt_fire: false,
+            boost: false,
+            dpad_up: false,
+            dpad_down: false,
+            confirm: false,
+            start: false,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct CollideCounters {
     pub hitboxes: usize,
@@ -44,6 +65,10 @@ pub struct CollideCounters {
 #[derive(Clone, Debug)]
 pub enum GuiOverride {
     StationDocked,
+    /// The player is out of lives.
+    GameOver,
+    /// Every wave was cleared.
+    Victory,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -60,3 +85,41 @@ impl GuiOverrideQueue {
         self.queue.lock().unwrap().drain(..).collect()
     }
 }
+
+/// Something that happened during a step that code outside the game may want to react to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A player's ship was destroyed. `lives` is how many it has left, counting the one it will
+    /// respawn with.
+    PlayerDied { player: Entity, lives: u32 },
+    /// Something was added to or removed from a player's inventory. Also sent once for each new
+    /// player.
+    InventoryChanged { player: Entity },
+    /// Wave number `wave`, counting from one, started spawning.
+    WaveStarted { wave: u32 },
+    /// Every enemy in wave number `wave` was destroyed.
+    WaveCleared { wave: u32 },
+    /// The last wave was cleared.
+    WavesWon,
+    /// Every player ran out of ships during wave number `wave`.
+    WavesLost { wave: u32 },
+    /// A player flew into a jump gate and is spooling up to jump to `sector`.
+    JumpStarted { player: Entity, sector: String },
+    /// A player arrived in `sector`, by its key in `Sectors`.
+    SectorEntered { player: Entity, sector: String },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GameEventQueue {
+    queue: Arc<Mutex<VecDeque<GameEvent>>>,
+}
+
+impl GameEventQueue {
+    pub fn push_back(&self, event: GameEvent) {
+        self.queue.lock().unwrap().push_back(event);
+    }
+
+    pub fn drain(&self) -> Vec<GameEvent> {
+        self.queue.lock().unwrap().drain(..).collect()
+    }
+}
@@ -0,0 +1,147 @@
+use legion::world::SubWorld;
+use legion::{Entity, EntityStore, IntoQuery};
+use std::collections::HashMap;
+
+use crate::enemy::EnemyKind;
+use crate::hitbox::HitboxComponent;
+use crate::player::PlayerComponent;
+use crate::resource::Time;
+use crate::util::Timer;
+
+/// Highest the combo multiplier goes.
+pub const MAX_COMBO: u32 = 8;
+
+/// How long the combo multiplier holds after a kill before dropping by one. It keeps dropping at
+/// this rate until the next kill or until it's back to one.
+pub const COMBO_DECAY_SECONDS: f32 = 2.0;
+
+impl EnemyKind {
+    /// Points for destroying an enemy of this kind, before the combo multiplier.
+    pub fn points(self) -> u32 {
+        match self {
+            EnemyKind::Sniper => 100,
+            EnemyKind::Charger => 150,
+            EnemyKind::Dreadnought => 2000,
+        }
+    }
+}
+
+/// What a player did over a run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunStats {
+    /// Projectiles fired.
+    pub shots_fired: u32,
+    /// Projectiles that hit something. A piercing projectile counts once, however many it hits.
+    pub hits: u32,
+    pub damage_dealt: f32,
+    /// Includes damage soaked up by shields.
+    pub damage_taken: f32,
+    /// Seconds spent with lives left.
+    pub time_survived: f32,
+    pub kills: HashMap<EnemyKind, u32>,
+}
+
+impl RunStats {
+    /// Hits per shot, from zero to one.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+}
+
+/// A player's score, combo and stats for the current run.
+#[derive(Clone, Debug)]
+pub struct ScoreComponent {
+    pub score: u64,
+    /// Multiplies the points for each kill. Starts at one.
+    pub combo: u32,
+    /// Counts down to the combo dropping.
+    pub combo_timer: Timer,
+    pub stats: RunStats,
+}
+
+impl Default for ScoreComponent {
+    fn default() -> Self {
+        ScoreComponent {
+            score: 0,
+            combo: 1,
+            combo_timer: Timer::elapsed(),
+            stats: RunStats::default(),
+        }
+    }
+}
+
+impl ScoreComponent {
+    /// Scores a kill at the current multiplier, then raises the multiplier.
+    pub fn record_kill(&mut self, kind: EnemyKind) {
+        self.score += (kind.points() * self.combo) as u64;
+        self.combo = (self.combo + 1).min(MAX_COMBO);
+        self.combo_timer.reset(COMBO_DECAY_SECONDS);
+        *self.stats.kills.entry(kind).or_insert(0) += 1;
+    }
+}
+
+/// Links a projectile to the scoring entity that fired it.
+#[derive(Clone, Debug)]
+pub struct ShotComponent {
+    pub shooter: Entity,
+    /// Whether this projectile has been counted as a hit.
+    pub has_hit: bool,
+}
+
+/// Counts projectiles that hit something. Runs between collision detection and damage.
+#[legion::system]
+#[read_component(HitboxComponent)]
+#[write_component(ShotComponent)]
+#[write_component(ScoreComponent)]
+pub fn shot_hits(world: &mut SubWorld) {
+    let mut shot_query = <(&HitboxComponent, &mut ShotComponent)>::query();
+    let (mut shot_world, mut score_world) = world.split_for_query(&shot_query);
+    for (hitbox, shot) in shot_query.iter_mut(&mut shot_world) {
+        if shot.has_hit || hitbox.hit_entities.is_empty() {
+            continue;
+        }
+        shot.has_hit = true;
+        if let Some(score) = score_world
+            .entry_mut(shot.shooter)
+            .ok()
+            .and_then(|entry| entry.into_component_mut::<ScoreComponent>().ok())
+        {
+            score.stats.hits += 1;
+        }
+    }
+}
+
+/// Runs the combo down and the run clock forward.
+#[legion::system(for_each)]
+pub fn score(
+    score: &mut ScoreComponent,
+    player: Option<&PlayerComponent>,
+    #[resource] time: &Time,
+) {
+    let alive = match player {
+        Some(player) => player.is_alive(),
+        None => true,
+    };
+    if !alive {
+        score.combo = 1;
+    } else if score.combo > 1 && score.combo_timer.step_and_is_elapsed(time) {
+        score.combo -= 1;
+        score.combo_timer.reset(COMBO_DECAY_SECONDS);
+    }
+
+    let lives_left = match player {
+        Some(player) => player.lives > 0,
+        None => true,
+    };
+    if lives_left {
+        score.stats.time_survived += time.elapsed_seconds;
+    }
+}
@@ -0,0 +1,388 @@
+use legion::systems::CommandBuffer;
+use legion::{Entity, Resources, World};
+use rand_distr::Distribution;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::enemy::{spawn_enemy, EnemyKind};
+use crate::game::Game;
+use crate::hitbox::HitboxEffect;
+use crate::player::spawn_player;
+use crate::position::PositionComponent;
+use crate::prefab::{Instance, Prefabs};
+use crate::station::spawn_station;
+use crate::travel::{Interaction, JumpTarget};
+use crate::util::{Timer, UnitDisc};
+use crate::wave::{SpawnerComponent, WaveDirector, WavePlan};
+use crate::{Point, Pt2};
+
+/// The sectors shipped with the game, keyed by name.
+const BUILTIN_SECTORS_JSON: &str = include_str!("../data/sectors.json");
+
+/// The sector a new game starts in.
+pub const START_SECTOR: &str = "frontier";
+
+/// The prefab jump gates are built from.
+pub const JUMP_GATE_PREFAB: &str = "jump_gate";
+
+/// Fastest an enemy from a spawn list starts out drifting.
+const SPAWN_LIST_DRIFT_SPEED: f32 = 100.0;
+
+fn to_pt2([x, y]: Point) -> Pt2 {
+    Pt2::new(x, y)
+}
+
+/// The look and feel of a sector.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ambient {
+    /// A CSS color drawn behind everything.
+    pub background: String,
+}
+
+impl Default for Ambient {
+    fn default() -> Self {
+        Ambient {
+            background: "#000".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StationDef {
+    pub position: Point,
+    #[serde(default = "StationDef::default_radius")]
+    pub radius: f32,
+}
+
+impl StationDef {
+    fn default_radius() -> f32 {
+        50.0
+    }
+}
+
+/// A jump gate to another sector.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GateDef {
+    pub position: Point,
+    #[serde(default = "GateDef::default_radius")]
+    pub radius: f32,
+    /// The key of the sector it leads to.
+    pub destination: String,
+    /// Where ships come out in the destination. Defaults to its player spawn.
+    #[serde(default)]
+    pub arrival: Option<Point>,
+}
+
+impl GateDef {
+    fn default_radius() -> f32 {
+        40.0
+    }
+}
+
+/// Some number of one kind of enemy.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyGroup {
+    pub kind: EnemyKind,
+    #[serde(default = "EnemyGroup::default_count")]
+    pub count: u32,
+}
+
+impl EnemyGroup {
+    fn default_count() -> u32 {
+        1
+    }
+}
+
+/// Enemies present from the start, scattered at random within a disc.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnList {
+    pub kind: EnemyKind,
+    #[serde(default = "EnemyGroup::default_count")]
+    pub count: u32,
+    #[serde(default)]
+    pub center: Point,
+    pub radius: f32,
+}
+
+/// A fixed point that releases enemies one at a time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnerDef {
+    pub position: Point,
+    pub enemies: Vec<EnemyGroup>,
+    /// Seconds between releases.
+    pub interval: f32,
+    /// Seconds before the first release.
+    #[serde(default)]
+    pub delay: f32,
+}
+
+/// Everything needed to set up a level. Enemies are kept inside the boundary, a circle around the
+/// origin; players and stations aren't.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sector {
+    /// The key this sector is listed under in `Sectors`, or that it was given when loaded on its
+    /// own. Saves and gates refer to it by this, so it stays put when the name changes.
+    #[serde(skip)]
+    pub id: String,
+    /// What to call the sector in menus.
+    pub name: String,
+    pub boundary: f32,
+    #[serde(default)]
+    pub player_spawn: Point,
+    #[serde(default)]
+    pub stations: Vec<StationDef>,
+    #[serde(default)]
+    pub gates: Vec<GateDef>,
+    #[serde(default)]
+    pub enemies: Vec<SpawnList>,
+    #[serde(default)]
+    pub spawners: Vec<SpawnerDef>,
+    /// Waves to run, if any. Omitted fields take the usual values.
+    #[serde(default)]
+    pub waves: Option<WavePlan>,
+    #[serde(default)]
+    pub ambient: Ambient,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SectorError {
+    /// The JSON is malformed or doesn't match the format.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The JSON is well formed but describes something impossible. `path` says where, like
+    /// `frontier.spawners[1].interval`.
+    Invalid { path: String, message: String },
+    /// There's no sector by this name.
+    NotFound(String),
+}
+
+impl Display for SectorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SectorError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            SectorError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+            SectorError::NotFound(name) => write!(f, "no sector named \"{}\"", name),
+        }
+    }
+}
+
+impl From<serde_json::Error> for SectorError {
+    fn from(e: serde_json::Error) -> Self {
+        // serde_json's message ends with its own position. Keep just the description.
+        let message = e.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(end) => message[..end].to_string(),
+            None => message,
+        };
+        SectorError::Parse {
+            line: e.line(),
+            column: e.column(),
+            message,
+        }
+    }
+}
+
+/// Fails with an `Invalid` error at `path` unless `value` is positive.
+fn check_positive(path: &str, value: f32) -> Result<(), SectorError> {
+    if value > 0.0 {
+        Ok(())
+    } else {
+        Err(SectorError::Invalid {
+            path: path.to_string(),
+            message: format!("must be positive, not {}", value),
+        })
+    }
+}
+
+impl Sector {
+    /// Loads a sector on its own, keyed by `id`. Its gates may lead back to itself or to any sector
+    /// in `sectors`.
+    pub fn from_json(id: &str, json: &str, sectors: &Sectors) -> Result<Sector, SectorError> {
+        let mut sector: Sector = serde_json::from_str(json)?;
+        sector.id = id.to_string();
+        sector.validate(id)?;
+        sector.validate_gates(id, |destination| {
+            destination == id || sectors.0.contains_key(destination)
+        })?;
+        Ok(sector)
+    }
+
+    /// Checks what the format can't express. `path` names the sector in errors.
+    fn validate(&self, path: &str) -> Result<(), SectorError> {
+        check_positive(&format!("{}.boundary", path), self.boundary)?;
+        for (i, station) in self.stations.iter().enumerate() {
+            check_positive(&format!("{}.stations[{}].radius", path, i), station.radius)?;
+        }
+        for (i, list) in self.enemies.iter().enumerate() {
+            check_positive(&format!("{}.enemies[{}].radius", path, i), list.radius)?;
+        }
+        for (i, gate) in self.gates.iter().enumerate() {
+            check_positive(&format!("{}.gates[{}].radius", path, i), gate.radius)?;
+        }
+        for (i, spawner) in self.spawners.iter().enumerate() {
+            let path = format!("{}.spawners[{}]", path, i);
+            check_positive(&format!("{}.interval", path), spawner.interval)?;
+            if spawner.enemies.is_empty() {
+                return Err(SectorError::Invalid {
+                    path: format!("{}.enemies", path),
+                    message: "a spawner needs at least one enemy".to_string(),
+                });
+            }
+        }
+        if let Some(waves) = &self.waves {
+            let path = format!("{}.waves", path);
+            check_positive(&format!("{}.spawn_interval", path), waves.spawn_interval)?;
+            if waves.spawn_radius <= self.boundary {
+                return Err(SectorError::Invalid {
+                    path: format!("{}.spawn_radius", path),
+                    message: format!(
+                        "must be beyond the boundary ({}) so waves arrive from outside",
+                        self.boundary
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails unless every gate leads to a sector for which `exists` is true.
+    fn validate_gates(&self, path: &str, exists: impl Fn(&str) -> bool) -> Result<(), SectorError> {
+        for (i, gate) in self.gates.iter().enumerate() {
+            if !exists(&gate.destination) {
+                return Err(SectorError::Invalid {
+                    path: format!("{}.gates[{}].destination", path, i),
+                    message: format!("no sector named \"{}\"", gate.destination),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up this sector in an empty world, with its resources as a game keeps them, starting
+    /// any waves.
+    pub(crate) fn build(&self, world: &mut World, resources: &mut Resources, rng: &mut Pcg32) {
+        let prefabs = resources.get::<Prefabs>().unwrap();
+
+        for station in &self.stations {
+            spawn_station(world, &prefabs, to_pt2(station.position), station.radius);
+        }
+        for gate in &self.gates {
+            spawn_gate(world, &prefabs, gate);
+        }
+
+        let mut cmd = CommandBuffer::new(world);
+        for list in &self.enemies {
+            for _ in 0..list.count {
+                let pos = to_pt2(list.center) + UnitDisc.sample(rng) * list.radius;
+                let vel = UnitDisc.sample(rng) * SPAWN_LIST_DRIFT_SPEED;
+                spawn_enemy(&mut cmd, rng, &prefabs, list.kind, pos, vel, self.boundary);
+            }
+        }
+        for spawner in &self.spawners {
+            // Release in the order listed.
+            let mut pending: Vec<EnemyKind> = spawner
+                .enemies
+                .iter()
+                .flat_map(|group| (0..group.count).map(move |_| group.kind))
+                .collect();
+            pending.reverse();
+            cmd.push((
+                PositionComponent(to_pt2(spawner.position)),
+                SpawnerComponent {
+                    pending,
+                    interval: spawner.interval,
+                    timer: Timer::with_remaining(spawner.delay),
+                    arena_radius: self.boundary,
+                },
+            ));
+        }
+        cmd.flush(world);
+        drop(prefabs);
+
+        let mut director = resources.get_mut::<WaveDirector>().unwrap();
+        match &self.waves {
+            Some(plan) => director.start(WavePlan {
+                arena_radius: self.boundary,
+                ..plan.clone()
+            }),
+            None => director.stop(),
+        }
+        drop(director);
+        resources.insert(self.ambient.clone());
+    }
+
+    /// Clears the game and starts it in this sector. Returns the new player.
+    pub fn populate(&self, game: &mut Game) -> Entity {
+        game.start_in(self);
+        let (prefabs, world) = game.prefabs_and_world_mut();
+        spawn_player(world, &prefabs, to_pt2(self.player_spawn))
+    }
+
+    /// Where players arrive by jump gates that don't say.
+    pub fn player_spawn(&self) -> Pt2 {
+        to_pt2(self.player_spawn)
+    }
+}
+
+fn spawn_gate(world: &mut World, prefabs: &Prefabs, gate: &GateDef) -> Entity {
+    // Where the gate leads is up to the sector, not the prefab.
+    let instance = Instance::at(to_pt2(gate.position))
+        .with_radius(gate.radius)
+        .with_effect(HitboxEffect::Interact(Interaction::Jump(JumpTarget {
+            sector: gate.destination.clone(),
+            arrival: gate.arrival,
+        })));
+    prefabs.spawn(JUMP_GATE_PREFAB, world, &instance).unwrap()
+}
+
+/// A table of sectors by name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Sectors(HashMap<String, Sector>);
+
+impl Sectors {
+    pub fn builtin() -> Sectors {
+        Sectors::from_json(BUILTIN_SECTORS_JSON).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Sectors, SectorError> {
+        let mut sectors: HashMap<String, Sector> = serde_json::from_str(json)?;
+        for (id, sector) in &mut sectors {
+            sector.id = id.clone();
+        }
+        for (id, sector) in &sectors {
+            sector.validate(id)?;
+            sector.validate_gates(id, |destination| sectors.contains_key(destination))?;
+        }
+        Ok(Sectors(sectors))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Sector, SectorError> {
+        self.0
+            .get(name)
+            .ok_or_else(|| SectorError::NotFound(name.to_string()))
+    }
+}
+
+/// Loads the sector `name` from the builtin sectors into `game`. Returns the new player.
+pub fn load_sector(game: &mut Game, name: &str) -> Result<Entity, SectorError> {
+    // Parse errors are reported like any other, rather than panicking as `Sectors::builtin` would.
+    Ok(Sectors::from_json(BUILTIN_SECTORS_JSON)?
+        .get(name)?
+        .populate(game))
+}
@@ -0,0 +1,122 @@
+use crate::resource::Time;
+use crate::util::Timer;
+
+/// How quickly the displayed reactor load follows the actual draw. Higher is snappier.
+const LOAD_SMOOTHING_PER_SECOND: f32 = 5.0;
+
+/// A ship's power and defense systems. A reactor steadily charges a battery, which everything else
+/// draws from: weapons, engines and shield regeneration. Shields soak up damage before it reaches
+/// the hull (`HealthComponent`).
+#[derive(Clone, Debug)]
+pub struct ShipSystemsComponent {
+    // Attributes.
+    /// Energy produced per second.
+    pub reactor_output: f32,
+    pub battery_capacity: f32,
+    pub shield_capacity: f32,
+    /// Shield points restored per second while recharging.
+    pub shield_recharge_rate: f32,
+    /// Seconds after taking damage before shields start recharging.
+    pub shield_recharge_delay: f32,
+    /// Energy spent per shield point restored.
+    pub shield_energy_cost: f32,
+
+    // State.
+    pub battery: f32,
+    pub shield: f32,
+    pub shield_recharge_timer: Timer,
+    /// Smoothed fraction of reactor output being drawn.
+    pub load: f32,
+
+    // Intra-frame state.
+    /// Energy drawn so far this step.
+    pub drawn: f32,
+}
+
+impl ShipSystemsComponent {
+    /// Systems with a full battery and shields.
+    pub fn new(reactor_output: f32, battery_capacity: f32, shield_capacity: f32) -> Self {
+        ShipSystemsComponent {
+            reactor_output,
+            battery_capacity,
+            shield_capacity,
+            shield_recharge_rate: 2.0,
+            shield_recharge_delay: 3.0,
+            shield_energy_cost: 2.0,
+
+            battery: battery_capacity,
+            shield: shield_capacity,
+            shield_recharge_timer: Timer::elapsed(),
+            load: 0.0,
+
+            drawn: 0.0,
+        }
+    }
+
+    /// Refills the battery and shields, as after a respawn.
+    pub fn restore(&mut self) {
+        self.battery = self.battery_capacity;
+        self.shield = self.shield_capacity;
+        self.shield_recharge_timer = Timer::elapsed();
+    }
+
+    /// Takes `energy` from the battery if there's enough. Returns whether it was taken.
+    pub fn try_draw(&mut self, energy: f32) -> bool {
+        if energy <= self.battery {
+            self.battery -= energy;
+            self.drawn += energy;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Lets the shields soak up as much of `damage` as they can, returning the rest for the hull.
+    pub fn absorb(&mut self, damage: f32) -> f32 {
+        let absorbed = damage.min(self.shield);
+        self.shield -= absorbed;
+        self.shield_recharge_timer.reset(self.shield_recharge_delay);
+        damage - absorbed
+    }
+
+    pub fn battery_fraction(&self) -> f32 {
+        if self.battery_capacity > 0.0 {
+            self.battery / self.battery_capacity
+        } else {
+            0.0
+        }
+    }
+
+    pub fn shield_fraction(&self) -> f32 {
+        if self.shield_capacity > 0.0 {
+            self.shield / self.shield_capacity
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Runs the reactor and shield regeneration. Runs before anything else draws power in a step.
+#[legion::system(for_each)]
+pub fn ship_systems(ship: &mut ShipSystemsComponent, #[resource] time: &Time) {
+    let dt = time.elapsed_seconds;
+
+    // Settle last step's load.
+    if ship.reactor_output > 0.0 && dt > 0.0 {
+        let load = ship.drawn / (ship.reactor_output * dt);
+        let k = (LOAD_SMOOTHING_PER_SECOND * dt).min(1.0);
+        ship.load += (load - ship.load) * k;
+    }
+    ship.drawn = 0.0;
+
+    ship.battery = (ship.battery + ship.reactor_output * dt).min(ship.battery_capacity);
+
+    if ship.shield_recharge_timer.step_and_is_elapsed(time) && ship.shield < ship.shield_capacity {
+        let points = (ship.shield_recharge_rate * dt)
+            .min(ship.shield_capacity - ship.shield)
+            .min(ship.battery / ship.shield_energy_cost);
+        if ship.try_draw(points * ship.shield_energy_cost) {
+            ship.shield += points;
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use legion::{Entity, EntityStore, World};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use crate::health::HealthComponent;
+use crate::inventory::{Inventory, InventoryError, Resource};
+use crate::module::{LoadoutComponent, LoadoutError, ModuleDefs};
+use crate::player::PlayerComponent;
+use crate::prefab::{Instance, Prefabs};
+use crate::ship::ShipSystemsComponent;
+use crate::weapon::{WeaponComponent, WeaponDefs};
+use crate::Pt2;
+
+/// What the station sells, in display order.
+const BUILTIN_SHOP_JSON: &str = include_str!("../data/shop.json");
+
+pub const SCRAP_PER_HULL_POINT: u32 = 3;
+pub const SCRAP_PER_SHIELD_POINT: u32 = 1;
+
+/// Fraction of an item's shop price paid out when selling it back.
+pub const SELL_FRACTION: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    #[serde(default)]
+    pub cores: u32,
+    #[serde(default)]
+    pub scrap: u32,
+}
+
+impl Price {
+    pub fn costs(&self) -> [(Resource, u32); 2] {
+        [(Resource::Cores, self.cores), (Resource::Scrap, self.scrap)]
+    }
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.cores, self.scrap) {
+            (0, 0) => write!(f, "free"),
+            (cores, 0) => write!(f, "{} cores", cores),
+            (0, scrap) => write!(f, "{} scrap", scrap),
+            (cores, scrap) => write!(f, "{} cores, {} scrap", cores, scrap),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShopOffer {
+    /// The inventory item bought.
+    pub item: String,
+    /// What to call the item in menus.
+    pub name: String,
+    pub price: Price,
+}
+
+impl ShopOffer {
+    pub fn sell_price(&self) -> Price {
+        Price {
+            cores: (self.price.cores as f32 * SELL_FRACTION) as u32,
+            scrap: (self.price.scrap as f32 * SELL_FRACTION) as u32,
+        }
+    }
+}
+
+/// A station's shop. It only buys back what it sells.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Catalog(Vec<ShopOffer>);
+
+impl Catalog {
+    pub fn builtin() -> Catalog {
+        Catalog::from_json(BUILTIN_SHOP_JSON).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Catalog> {
+        serde_json::from_str(json)
+    }
+
+    pub fn offers(&self) -> &[ShopOffer] {
+        &self.0
+    }
+
+    pub fn get(&self, item: &str) -> Option<&ShopOffer> {
+        self.0.iter().find(|offer| offer.item == item)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StationError {
+    NotDocked,
+    NothingToRepair,
+    /// The station doesn't deal in that item.
+    NotForSale,
+    NotAWeapon,
+    Inventory(InventoryError),
+    Loadout(LoadoutError),
+}
+
+impl From<InventoryError> for StationError {
+    fn from(err: InventoryError) -> Self {
+        StationError::Inventory(err)
+    }
+}
+
+impl From<LoadoutError> for StationError {
+    fn from(err: LoadoutError) -> Self {
+        StationError::Loadout(err)
+    }
+}
+
+impl Display for StationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StationError::NotDocked => f.write_str("not docked at a station"),
+            StationError::NothingToRepair => f.write_str("nothing needs repair"),
+            StationError::NotForSale => f.write_str("the station doesn't trade in that"),
+            StationError::NotAWeapon => f.write_str("that can't be equipped"),
+            StationError::Inventory(err) => err.fmt(f),
+            StationError::Loadout(err) => err.fmt(f),
+        }
+    }
+}
+
+/// The prefab stations are built from.
+pub const STATION_PREFAB: &str = "station";
+
+/// Spawns a station that players dock with by flying into it. `radius` is how close they have to
+/// get; the solid part of the station scales along with it.
+pub fn spawn_station(world: &mut World, prefabs: &Prefabs, pos: Pt2, radius: f32) -> Entity {
+    prefabs
+        .spawn(
+            STATION_PREFAB,
+            world,
+            &Instance::at(pos).with_radius(radius),
+        )
+        .unwrap()
+}
+
+/// The docked player's inventory. All station services go through here.
+fn docked_inventory(world: &mut World, player: Entity) -> Result<&mut Inventory, StationError> {
+    let player = world
+        .entry_mut(player)
+        .ok()
+        .and_then(|entry| entry.into_component_mut::<PlayerComponent>().ok())
+        .filter(|player| player.docked_to.is_some())
+        .ok_or(StationError::NotDocked)?;
+    Ok(&mut player.inventory)
+}
+
+/// What it would cost to fully repair the player's hull and shields.
+pub fn repair_price(world: &World, player: Entity) -> Price {
+    let entry = match world.entry_ref(player) {
+        Ok(entry) => entry,
+        Err(_) => return Price::default(),
+    };
+    let hull = entry
+        .get_component::<HealthComponent>()
+        .map(|health| (health.max_health - health.health).max(0.0).ceil() as u32)
+        .unwrap_or(0);
+    let shield = entry
+        .get_component::<ShipSystemsComponent>()
+        .map(|ship| (ship.shield_capacity - ship.shield).max(0.0).ceil() as u32)
+        .unwrap_or(0);
+    Price {
+        cores: 0,
+        scrap: hull * SCRAP_PER_HULL_POINT + shield * SCRAP_PER_SHIELD_POINT,
+    }
+}
+
+/// Fully repairs the player's hull and shields. Returns what was paid.
+pub fn repair(world: &mut World, player: Entity) -> Result<Price, StationError> {
+    let price = repair_price(world, player);
+    let inventory = docked_inventory(world, player)?;
+    if price == Price::default() {
+        return Err(StationError::NothingToRepair);
+    }
+    inventory.spend(&price.costs())?;
+
+    let mut entry = world.entry_mut(player).unwrap();
+    if let Ok(health) = entry.get_component_mut::<HealthComponent>() {
+        health.health = health.max_health;
+    }
+    if let Ok(ship) = entry.get_component_mut::<ShipSystemsComponent>() {
+        ship.shield = ship.shield_capacity;
+    }
+    Ok(price)
+}
+
+/// Buys one of `item` into the player's inventory.
+pub fn buy(
+    world: &mut World,
+    player: Entity,
+    catalog: &Catalog,
+    item: &str,
+) -> Result<(), StationError> {
+    let inventory = docked_inventory(world, player)?;
+    let offer = catalog.get(item).ok_or(StationError::NotForSale)?;
+    if !inventory.can_afford(&offer.price.costs()) {
+        return Err(InventoryError::NotEnough.into());
+    }
+    inventory.add_item(item, 1)?;
+    inventory.spend(&offer.price.costs()).unwrap();
+    Ok(())
+}
+
+/// Sells one of `item` from the player's inventory. Returns what was paid for it. Resources past
+/// the inventory's capacity are lost.
+pub fn sell(
+    world: &mut World,
+    player: Entity,
+    catalog: &Catalog,
+    item: &str,
+) -> Result<Price, StationError> {
+    let inventory = docked_inventory(world, player)?;
+    let price = catalog
+        .get(item)
+        .ok_or(StationError::NotForSale)?
+        .sell_price();
+    inventory.remove_item(item, 1)?;
+    for &(resource, amount) in &price.costs() {
+        inventory.add_up_to(resource, amount);
+    }
+    Ok(price)
+}
+
+/// Mounts a weapon from the player's inventory as their main weapon. The weapon it replaces goes
+/// into the inventory.
+pub fn equip(
+    world: &mut World,
+    player: Entity,
+    weapon_defs: &WeaponDefs,
+    item: &str,
+) -> Result<(), StationError> {
+    let def = weapon_defs.get(item).ok_or(StationError::NotAWeapon)?;
+    let old = world
+        .entry_ref(player)
+        .ok()
+        .and_then(|entry| entry.get_component::<WeaponComponent>().ok().cloned())
+        .ok_or(StationError::NotAWeapon)?;
+
+    let inventory = docked_inventory(world, player)?;
+    inventory.remove_item(item, 1)?;
+    if let Err(err) = inventory.add_item(&old.def.name, 1) {
+        inventory.add_item(item, 1).unwrap();
+        return Err(err.into());
+    }
+
+    // Modules boost whatever weapon is mounted.
+    let mut weapon = WeaponComponent::new(def.clone(), old.target_mask);
+    weapon.cooldown_scale = old.cooldown_scale;
+    weapon.damage_scale = old.damage_scale;
+    let mut entry = world.entry_mut(player).unwrap();
+    *entry.get_component_mut::<WeaponComponent>().unwrap() = weapon;
+    Ok(())
+}
+
+/// Fits a module from the player's inventory to their ship. Any module it displaces goes into the
+/// inventory.
+pub fn install(
+    world: &mut World,
+    player: Entity,
+    module_defs: &ModuleDefs,
+    item: &str,
+) -> Result<(), StationError> {
+    docked_inventory(world, player)?;
+    let (index, old) = {
+        let entry = world.entry_ref(player).unwrap();
+        let loadout = entry
+            .get_component::<LoadoutComponent>()
+            .map_err(|_| LoadoutError::NoSlot)?;
+        let index = loadout.slot_for(module_defs, item)?;
+        (index, loadout.slots()[index].1.clone())
+    };
+
+    let inventory = docked_inventory(world, player)?;
+    inventory.remove_item(item, 1)?;
+    if let Some(old) = &old {
+        if let Err(err) = inventory.add_item(old, 1) {
+            inventory.add_item(item, 1).unwrap();
+            return Err(err.into());
+        }
+    }
+
+    let mut entry = world.entry_mut(player).unwrap();
+    let loadout = entry.get_component_mut::<LoadoutComponent>().unwrap();
+    loadout.install(module_defs, index, item).unwrap();
+    Ok(())
+}
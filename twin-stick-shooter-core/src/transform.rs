@@ -0,0 +1,172 @@
+use cgmath::EuclideanSpace;
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::{Entity, EntityStore, IntoQuery};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::physics::{AngularVelocityComponent, VelocityComponent};
+use crate::position::{PositionComponent, RotationComponent};
+use crate::resource::Time;
+use crate::util::wrap_angle;
+use crate::{rotation, translation, Mat3, Pt2, Vec2};
+
+/// What becomes of a child when its parent is removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orphan {
+    /// It's removed too. Its own children then go the way their `orphan` says.
+    #[default]
+    Destroy,
+    /// It stays where it is, drifting on at its last velocity.
+    Detach,
+}
+
+/// Attaches an entity to a parent that it moves and turns along with. The child's position,
+/// rotation and velocities are derived from the parent's each step, so setting them does nothing.
+#[derive(Clone, Debug)]
+pub struct ParentComponent {
+    pub parent: Entity,
+    /// Takes the child's space to the parent's.
+    pub local: Mat3,
+    pub orphan: Orphan,
+}
+
+impl ParentComponent {
+    /// Attaches to `parent` at `offset` from it.
+    pub fn new(parent: Entity, offset: Vec2, orphan: Orphan) -> ParentComponent {
+        ParentComponent {
+            parent,
+            local: translation(offset),
+            orphan,
+        }
+    }
+
+    /// Where the child sits relative to its parent.
+    pub fn offset(&self) -> Vec2 {
+        self.local.z.truncate()
+    }
+}
+
+/// Turns a child about its parent.
+#[derive(Clone, Debug)]
+pub struct OrbitComponent {
+    /// Radians per second, counterclockwise.
+    pub angular_speed: f32,
+}
+
+#[legion::system(for_each)]
+pub fn orbit(child: &mut ParentComponent, orbit: &OrbitComponent, #[resource] time: &Time) {
+    child.local = rotation(orbit.angular_speed * time.elapsed_seconds) * child.local;
+}
+
+/// Places every child where its parent's transform and its local transform put it, parents
+/// first. Children whose parents are gone are removed or detached.
+#[legion::system]
+#[read_component(ParentComponent)]
+#[write_component(PositionComponent)]
+#[write_component(RotationComponent)]
+#[write_component(VelocityComponent)]
+#[write_component(AngularVelocityComponent)]
+pub fn propagate_transforms(
+    cmd: &mut CommandBuffer,
+    world: &mut SubWorld,
+    #[resource] time: &Time,
+) {
+    let children: HashMap<Entity, ParentComponent> = <(Entity, &ParentComponent)>::query()
+        .iter(world)
+        .map(|(&entity, child)| (entity, child.clone()))
+        .collect();
+    let mut placed = HashMap::new();
+    let mut propagate = Propagate {
+        cmd,
+        world,
+        time,
+        children: &children,
+        placed: &mut placed,
+    };
+    for &entity in children.keys() {
+        propagate.place(entity, 0);
+    }
+}
+
+struct Propagate<'a, 'b> {
+    cmd: &'a mut CommandBuffer,
+    world: &'a mut SubWorld<'b>,
+    time: &'a Time,
+    children: &'a HashMap<Entity, ParentComponent>,
+    /// The world transform of each entity visited so far. `None` if it's being removed.
+    placed: &'a mut HashMap<Entity, Option<Mat3>>,
+}
+
+impl Propagate<'_, '_> {
+    /// Places `entity` after its ancestors, returning its new world transform.
+    fn place(&mut self, entity: Entity, depth: usize) -> Option<Mat3> {
+        if let Some(&xform) = self.placed.get(&entity) {
+            return xform;
+        }
+        let current = self.world.entry_ref(entity).ok().and_then(|entry| {
+            let &PositionComponent(pos) = entry.get_component().ok()?;
+            let rot = match entry.get_component::<RotationComponent>() {
+                Ok(&RotationComponent(rot)) => rot,
+                Err(_) => 0.0,
+            };
+            Some(translation(pos.to_vec()) * rotation(rot))
+        });
+        let child = match self.children.get(&entity) {
+            Some(child) => child,
+            None => return current,
+        };
+        // A cycle never reaches a root. Leave it be.
+        if depth > self.children.len() {
+            return current;
+        }
+
+        let xform = match self.place(child.parent, depth + 1) {
+            Some(parent_xform) => {
+                let xform = parent_xform * child.local;
+                self.move_to(entity, &xform);
+                Some(xform)
+            }
+            None => match child.orphan {
+                Orphan::Destroy => {
+                    self.cmd.remove(entity);
+                    None
+                }
+                Orphan::Detach => {
+                    self.cmd.remove_component::<ParentComponent>(entity);
+                    current
+                }
+            },
+        };
+        self.placed.insert(entity, xform);
+        xform
+    }
+
+    fn move_to(&mut self, entity: Entity, xform: &Mat3) {
+        let dt = self.time.elapsed_seconds;
+        let mut entry = self.world.entry_mut(entity).unwrap();
+
+        let PositionComponent(pos) = entry.get_component_mut::<PositionComponent>().unwrap();
+        let moved = Pt2::from_vec(xform.z.truncate()) - *pos;
+        *pos += moved;
+        // `physics` already moved the child by its velocity this step. Its new velocity is
+        // whatever gets it from where it was before that to where it is now.
+        if let Ok(VelocityComponent(vel)) = entry.get_component_mut::<VelocityComponent>() {
+            if dt > 0.0 {
+                *vel += moved / dt;
+            }
+        }
+
+        if let Ok(RotationComponent(rot)) = entry.get_component_mut::<RotationComponent>() {
+            let turned = wrap_angle(xform.x.y.atan2(xform.x.x) - *rot);
+            *rot = wrap_angle(*rot + turned);
+            if let Ok(AngularVelocityComponent(ang_vel)) =
+                entry.get_component_mut::<AngularVelocityComponent>()
+            {
+                if dt > 0.0 {
+                    *ang_vel += turned / dt;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use legion::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::player::PlayerComponent;
+use crate::resource::Time;
+use crate::util::Timer;
+use crate::Point;
+
+/// How long a jump drive spools up before the ship leaves the sector.
+pub const JUMP_SECONDS: f32 = 1.0;
+
+/// Something a player does by flying into a hitbox.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Interaction {
+    /// Docks with a station, opening its services.
+    Dock,
+    /// Jumps to another sector.
+    Jump(JumpTarget),
+}
+
+/// Where a jump gate leads.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JumpTarget {
+    /// The destination's key in `Sectors`.
+    pub sector: String,
+    /// Where the ship comes out. Defaults to the destination's player spawn.
+    #[serde(default)]
+    pub arrival: Option<Point>,
+}
+
+/// A jump in progress. The ship holds still while the drive spools up.
+#[derive(Clone, Debug)]
+pub struct Jump {
+    pub target: JumpTarget,
+    pub timer: Timer,
+}
+
+impl Jump {
+    pub fn new(target: JumpTarget) -> Jump {
+        Jump {
+            target,
+            timer: Timer::with_remaining(JUMP_SECONDS),
+        }
+    }
+
+    /// From zero as the drive starts spooling up to one as the ship leaves.
+    pub fn progress(&self) -> f32 {
+        1.0 - self.timer.remaining() / JUMP_SECONDS
+    }
+}
+
+/// Players whose jump drives finished spooling up this step. The game moves them to their
+/// destinations once the step is done.
+#[derive(Clone, Debug, Default)]
+pub struct Departures(pub Vec<(Entity, JumpTarget)>);
+
+#[legion::system(for_each)]
+pub fn jump(
+    entity: &Entity,
+    player: &mut PlayerComponent,
+    #[resource] time: &Time,
+    #[resource] departures: &mut Departures,
+) {
+    if let Some(jump) = player.jumping.as_mut() {
+        if jump.timer.step_and_is_elapsed(time) {
+            departures.0.push((*entity, jump.target.clone()));
+            player.jumping = None;
+        }
+    }
+}
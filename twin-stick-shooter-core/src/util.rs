@@ -41,6 +41,18 @@ where
     }
 }
 
+/// `angle` brought within -π to π.
+pub fn wrap_angle(angle: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    // `rem_euclid` can round up to `TAU` itself.
+    if wrapped < -PI {
+        wrapped + TAU
+    } else {
+        wrapped
+    }
+}
+
 pub struct UnitDisc;
 
 impl Distribution<Vec2> for UnitDisc {
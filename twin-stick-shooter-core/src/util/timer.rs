@@ -18,6 +18,10 @@ impl Timer {
         self.remaining = (self.remaining - time.elapsed_seconds).max(0.0);
     }
 
+    pub fn remaining(&self) -> f32 {
+        self.remaining
+    }
+
     pub fn is_elapsed(&self) -> bool {
         self.remaining == 0.0
     }
@@ -0,0 +1,282 @@
+use cgmath::{EuclideanSpace, InnerSpace};
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::{Entity, IntoQuery};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::enemy::{spawn_enemy, EnemyComponent, EnemyKind, ARENA_RADIUS};
+use crate::player::PlayerComponent;
+use crate::position::PositionComponent;
+use crate::prefab::Prefabs;
+use crate::resource::{GameEvent, GameEventQueue, GuiOverride, GuiOverrideQueue, Time};
+use crate::util::Timer;
+use crate::Pt2;
+
+/// Candidate spawn points considered per spawner. The one farthest from any player wins.
+const SPAWN_POINT_CANDIDATES: usize = 8;
+
+/// Speed at which enemies leave a spawner, headed for the arena.
+const SPAWN_SPEED: f32 = 100.0;
+
+/// How a run of waves escalates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WavePlan {
+    /// Clearing this many waves wins.
+    pub waves: u32,
+    pub first_wave_size: u32,
+    /// Each wave is this many times the size of the one before.
+    pub growth: f32,
+    /// Fraction of the first wave that are chargers. The rest are snipers.
+    pub charger_share: f32,
+    /// How much the charger share rises each wave.
+    pub charger_share_step: f32,
+    pub max_charger_share: f32,
+    /// Quiet time before each wave.
+    pub breather_seconds: f32,
+    /// Seconds between enemies leaving the same spawner.
+    pub spawn_interval: f32,
+    /// Spawners are placed this far from the arena's center, beyond what the camera shows.
+    pub spawn_radius: f32,
+    /// Enemies are turned back past this distance from the arena's center. Set from the sector's
+    /// boundary.
+    #[serde(skip)]
+    pub arena_radius: f32,
+}
+
+impl Default for WavePlan {
+    fn default() -> Self {
+        WavePlan {
+            waves: 10,
+            first_wave_size: 6,
+            growth: 1.25,
+            charger_share: 0.2,
+            charger_share_step: 0.05,
+            max_charger_share: 0.6,
+            breather_seconds: 5.0,
+            spawn_interval: 0.5,
+            spawn_radius: 800.0,
+            arena_radius: ARENA_RADIUS,
+        }
+    }
+}
+
+impl WavePlan {
+    /// The enemies in wave number `wave`, counting from one.
+    pub fn composition(&self, wave: u32) -> Vec<EnemyKind> {
+        let escalation = wave.saturating_sub(1);
+        let size = (self.first_wave_size as f32 * self.growth.powi(escalation as i32)).round();
+        let share = (self.charger_share + self.charger_share_step * escalation as f32)
+            .min(self.max_charger_share);
+        let chargers = (size * share).round() as usize;
+        let mut enemies = vec![EnemyKind::Charger; chargers];
+        enemies.resize(size as usize, EnemyKind::Sniper);
+        enemies
+    }
+
+    /// How many spawners wave number `wave` comes from. Later waves arrive from more directions.
+    pub fn spawners(&self, wave: u32) -> usize {
+        1 + wave as usize / 3
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WavePhase {
+    /// No waves are being run.
+    Idle,
+    /// Counting down to the next wave.
+    Breather(Timer),
+    /// The current wave is spawning or still has enemies left.
+    Fighting,
+    /// Every wave was cleared.
+    Won,
+    /// Every player ran out of ships.
+    Lost,
+}
+
+/// Schedules waves of enemies and decides when a run is won or lost.
+#[derive(Clone, Debug)]
+pub struct WaveDirector {
+    pub plan: WavePlan,
+    /// The current wave, counting from one, or zero before the first.
+    wave: u32,
+    phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        WaveDirector {
+            plan: WavePlan::default(),
+            wave: 0,
+            phase: WavePhase::Idle,
+        }
+    }
+}
+
+impl WaveDirector {
+    /// Starts a new run, with a breather before the first wave.
+    pub fn start(&mut self, plan: WavePlan) {
+        self.phase = WavePhase::Breather(Timer::with_remaining(plan.breather_seconds));
+        self.plan = plan;
+        self.wave = 0;
+    }
+
+    pub fn stop(&mut self) {
+        *self = WaveDirector::default();
+    }
+
+    pub fn wave(&self) -> u32 {
+        self.wave
+    }
+
+    pub fn phase(&self) -> &WavePhase {
+        &self.phase
+    }
+}
+
+/// Releases queued enemies one at a time from a fixed point, then disappears.
+#[derive(Clone, Debug)]
+pub struct SpawnerComponent {
+    /// Released from the back.
+    pub pending: Vec<EnemyKind>,
+    pub interval: f32,
+    pub timer: Timer,
+    /// Released enemies are turned back past this distance from the arena's center.
+    pub arena_radius: f32,
+}
+
+#[legion::system(for_each)]
+pub fn spawner(
+    cmd: &mut CommandBuffer,
+    entity: &Entity,
+    &PositionComponent(pos): &PositionComponent,
+    spawner: &mut SpawnerComponent,
+    #[resource] prefabs: &Prefabs,
+    #[resource] time: &Time,
+    #[resource] rng: &mut Pcg32,
+) {
+    if !spawner.timer.step_and_is_elapsed(time) {
+        return;
+    }
+    match spawner.pending.pop() {
+        Some(kind) => {
+            let vel = (Pt2::origin() - pos).normalize_to(SPAWN_SPEED);
+            spawn_enemy(cmd, rng, prefabs, kind, pos, vel, spawner.arena_radius);
+            spawner.timer.reset(spawner.interval);
+        }
+        None => cmd.remove(*entity),
+    }
+}
+
+/// Picks a spot on the spawn circle that's as far as possible from every living player.
+fn spawn_point(plan: &WavePlan, players: &[Pt2], rng: &mut Pcg32) -> Pt2 {
+    let distance_to_players = |point: Pt2| {
+        players
+            .iter()
+            .map(|&player| (player - point).magnitude())
+            .fold(f32::INFINITY, f32::min)
+    };
+    (0..SPAWN_POINT_CANDIDATES)
+        .map(|_| {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            Pt2::new(angle.cos(), angle.sin()) * plan.spawn_radius
+        })
+        .max_by(|&a, &b| {
+            distance_to_players(a)
+                .partial_cmp(&distance_to_players(b))
+                .unwrap()
+        })
+        .unwrap()
+}
+
+#[allow(clippy::too_many_arguments)]
+#[legion::system]
+#[read_component(PositionComponent)]
+#[read_component(PlayerComponent)]
+#[read_component(EnemyComponent)]
+#[read_component(SpawnerComponent)]
+pub fn wave_director(
+    cmd: &mut CommandBuffer,
+    world: &mut SubWorld,
+    #[resource] director: &mut WaveDirector,
+    #[resource] time: &Time,
+    #[resource] rng: &mut Pcg32,
+    #[resource] game_event_queue: &GameEventQueue,
+    #[resource] gui_override_queue: &GuiOverrideQueue,
+) {
+    if matches!(
+        director.phase,
+        WavePhase::Idle | WavePhase::Won | WavePhase::Lost
+    ) {
+        return;
+    }
+
+    let players: Vec<(Pt2, &PlayerComponent)> = <(&PositionComponent, &PlayerComponent)>::query()
+        .iter(world)
+        .map(|(&PositionComponent(pos), player)| (pos, player))
+        .collect();
+    if !players.is_empty() && players.iter().all(|(_, player)| player.lives == 0) {
+        director.phase = WavePhase::Lost;
+        game_event_queue.push_back(GameEvent::WavesLost {
+            wave: director.wave,
+        });
+        return;
+    }
+
+    match &mut director.phase {
+        WavePhase::Breather(timer) => {
+            if !timer.step_and_is_elapsed(time) {
+                return;
+            }
+            director.wave += 1;
+            director.phase = WavePhase::Fighting;
+
+            let plan = &director.plan;
+            let living: Vec<Pt2> = players
+                .iter()
+                .filter(|(_, player)| player.is_alive())
+                .map(|&(pos, _)| pos)
+                .collect();
+            let mut enemies = plan.composition(director.wave);
+            enemies.shuffle(rng);
+            let spawners = plan.spawners(director.wave);
+            for i in 0..spawners {
+                let pos = spawn_point(plan, &living, rng);
+                cmd.push((
+                    PositionComponent(pos),
+                    SpawnerComponent {
+                        pending: enemies.iter().copied().skip(i).step_by(spawners).collect(),
+                        interval: plan.spawn_interval,
+                        timer: Timer::elapsed(),
+                        arena_radius: plan.arena_radius,
+                    },
+                ));
+            }
+            game_event_queue.push_back(GameEvent::WaveStarted {
+                wave: director.wave,
+            });
+        }
+        WavePhase::Fighting => {
+            let remaining = <&EnemyComponent>::query().iter(world).count()
+                + <&SpawnerComponent>::query().iter(world).count();
+            if remaining > 0 {
+                return;
+            }
+            game_event_queue.push_back(GameEvent::WaveCleared {
+                wave: director.wave,
+            });
+            if director.wave >= director.plan.waves {
+                director.phase = WavePhase::Won;
+                game_event_queue.push_back(GameEvent::WavesWon);
+                gui_override_queue.push_back(GuiOverride::Victory);
+            } else {
+                director.phase =
+                    WavePhase::Breather(Timer::with_remaining(director.plan.breather_seconds));
+            }
+        }
+        WavePhase::Idle | WavePhase::Won | WavePhase::Lost => unreachable!(),
+    }
+}
@@ -0,0 +1,357 @@
+use cgmath::num_traits::zero;
+use cgmath::{Basis2, InnerSpace, Rad, Rotation, Rotation2};
+use legion::systems::CommandBuffer;
+use legion::Entity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::fmt::{self, Display, Formatter};
+
+use crate::collision::{ShapeDef, ShapeError};
+use crate::hitbox::HitboxMask;
+use crate::physics::VelocityComponent;
+use crate::position::PositionComponent;
+use crate::prefab::{Instance, Prefab, Team};
+use crate::resource::Time;
+use crate::score::{ScoreComponent, ShotComponent};
+use crate::ship::ShipSystemsComponent;
+use crate::util::Timer;
+use crate::{Pt2, Vec2};
+
+/// The weapons shipped with the game, keyed by name.
+const BUILTIN_WEAPONS_JSON: &str = include_str!("../data/weapons.json");
+
+/// Everything about how a weapon fires and what its projectiles do.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WeaponDef {
+    /// The key this def is listed under in `WeaponDefs`.
+    #[serde(skip)]
+    pub name: String,
+    /// Seconds between volleys.
+    pub cooldown: f32,
+    /// Projectiles per volley.
+    pub projectile_count: u32,
+    /// Angle in radians that a volley's projectiles are fanned evenly across, centered on the aim.
+    /// A full turn or more makes a ring instead, with the projectiles evenly spaced all the way
+    /// around.
+    pub spread: f32,
+    /// How far in front of the shooter projectiles appear.
+    pub muzzle_offset: f32,
+    /// Projectile speed relative to the shooter.
+    pub speed: f32,
+    /// Seconds before an unspent projectile disappears.
+    pub lifetime: f32,
+    pub damage: f32,
+    pub shape: ShapeDef,
+    pub model: String,
+    /// Piercing projectiles pass through what they hit, damaging each thing once.
+    #[serde(default)]
+    pub pierce: bool,
+    /// Tests projectiles along their whole path each step, for ones fast enough to skip past
+    /// things. See `SweptComponent`.
+    #[serde(default)]
+    pub swept: bool,
+    /// Makes this a charged weapon: it builds up while the trigger is held and fires on release.
+    #[serde(default)]
+    pub charge: Option<ChargeDef>,
+    /// Energy drawn from the shooter's battery per volley, scaled by damage for charged shots. Free
+    /// for shooters without ship systems.
+    #[serde(default)]
+    pub energy: f32,
+}
+
+/// How a charged weapon's shot grows with charge time. A fully charged shot has its size, damage
+/// and speed multiplied by the `full_*` factors; partial charges scale linearly in between.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChargeDef {
+    /// Seconds to reach full charge.
+    pub seconds: f32,
+    /// Releasing before this much charge fizzles without firing.
+    pub min_seconds: f32,
+    pub full_size: f32,
+    pub full_damage: f32,
+    pub full_speed: f32,
+}
+
+impl WeaponDef {
+    /// The direction of each projectile in a volley aimed along `dir`.
+    pub fn directions(&self, dir: Vec2) -> Vec<Vec2> {
+        if self.projectile_count <= 1 {
+            return vec![dir];
+        }
+        // Fanning a ring across its ends would put the first and last projectiles on top of each
+        // other.
+        let gaps = if self.is_ring() {
+            self.projectile_count
+        } else {
+            self.projectile_count - 1
+        };
+        (0..self.projectile_count)
+            .map(|i| {
+                let t = i as f32 / gaps as f32 - 0.5;
+                Basis2::from_angle(Rad(self.spread * t)).rotate_vector(dir)
+            })
+            .collect()
+    }
+
+    /// Whether the spread goes all the way around. Allows for full turns written out to a few
+    /// decimal places.
+    pub fn is_ring(&self) -> bool {
+        self.spread >= TAU - 1e-3
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WeaponDefError {
+    /// The JSON is malformed or doesn't match the format.
+    Parse(String),
+    /// A weapon's projectiles have a shape collision detection can't handle.
+    BadShape { weapon: String, error: ShapeError },
+}
+
+impl Display for WeaponDefError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WeaponDefError::Parse(message) => write!(f, "couldn't parse weapons: {}", message),
+            WeaponDefError::BadShape { weapon, error } => {
+                write!(f, "weapon \"{}\" has a bad shape: {}", weapon, error)
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for WeaponDefError {
+    fn from(e: serde_json::Error) -> Self {
+        WeaponDefError::Parse(e.to_string())
+    }
+}
+
+/// A table of weapon definitions by name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WeaponDefs(HashMap<String, WeaponDef>);
+
+impl WeaponDefs {
+    pub fn builtin() -> WeaponDefs {
+        WeaponDefs::from_json(BUILTIN_WEAPONS_JSON).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<WeaponDefs, WeaponDefError> {
+        let mut defs: HashMap<String, WeaponDef> = serde_json::from_str(json)?;
+        for (name, def) in &mut defs {
+            def.name = name.clone();
+            def.shape
+                .validate()
+                .map_err(|error| WeaponDefError::BadShape {
+                    weapon: name.clone(),
+                    error,
+                })?;
+        }
+        Ok(WeaponDefs(defs))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WeaponDef> {
+        self.0.get(name)
+    }
+}
+
+/// A weapon mounted on an entity. Whatever controls the entity aims it by setting `trigger`.
+#[derive(Clone, Debug)]
+pub struct WeaponComponent {
+    // Attributes.
+    pub def: WeaponDef,
+    /// Which hurtboxes the projectiles can hit.
+    pub target_mask: HitboxMask,
+    /// Multiplies the def's cooldown.
+    pub cooldown_scale: f32,
+    /// Multiplies the def's damage.
+    pub damage_scale: f32,
+    pub cooldown: Timer,
+    /// Seconds of charge built up. Only used by charged weapons.
+    pub charge: f32,
+    /// The last nonzero direction the trigger was held in. Charged weapons fire this way on
+    /// release.
+    pub aim: Vec2,
+
+    // Intra-frame state.
+    /// Set while the trigger is held, to the direction to fire in. A zero direction still counts as
+    /// held.
+    pub trigger: Option<Vec2>,
+}
+
+/// A shooter that keeps score, and so tracks its shots.
+type Scorer<'a> = Option<(Entity, &'a mut ScoreComponent)>;
+
+/// Multipliers applied to a single shot's projectiles.
+struct ShotScale {
+    size: f32,
+    damage: f32,
+    speed: f32,
+}
+
+impl Default for ShotScale {
+    fn default() -> Self {
+        ShotScale {
+            size: 1.0,
+            damage: 1.0,
+            speed: 1.0,
+        }
+    }
+}
+
+/// A second weapon slot, fired with the alt-fire input.
+#[derive(Clone, Debug)]
+pub struct AltWeaponComponent(pub WeaponComponent);
+
+impl WeaponComponent {
+    /// Starts a full cooldown away from firing. See `with_cooldown`.
+    pub fn new(def: WeaponDef, target_mask: HitboxMask) -> WeaponComponent {
+        WeaponComponent {
+            cooldown: Timer::with_remaining(def.cooldown),
+            def,
+            target_mask,
+            cooldown_scale: 1.0,
+            damage_scale: 1.0,
+            charge: 0.0,
+            aim: zero(),
+            trigger: None,
+        }
+    }
+
+    /// Starts `seconds` away from firing instead. Zero makes the weapon ready at once.
+    pub fn with_cooldown(mut self, seconds: f32) -> WeaponComponent {
+        self.cooldown = Timer::with_remaining(seconds);
+        self
+    }
+
+    /// How charged the weapon is, from zero to one. Always zero for uncharged weapons.
+    pub fn charge_fraction(&self) -> f32 {
+        match &self.def.charge {
+            Some(charge) => self.charge / charge.seconds,
+            None => 0.0,
+        }
+    }
+
+    /// Drops any built-up charge without firing.
+    pub fn cancel_charge(&mut self) {
+        self.charge = 0.0;
+    }
+
+    fn step(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        pos: Pt2,
+        vel: Vec2,
+        ship: Option<&mut ShipSystemsComponent>,
+        scorer: Scorer,
+        time: &Time,
+    ) {
+        let ready = self.cooldown.step_and_is_elapsed(time);
+        let trigger = self.trigger.take();
+        if let Some(dir) = trigger.filter(|dir| dir.magnitude2() > 1e-6) {
+            self.aim = dir;
+        }
+
+        match self.def.charge.clone() {
+            None => {
+                if let (true, Some(dir)) = (ready, trigger) {
+                    if dir.magnitude2() > 1e-6 && draw(ship, self.def.energy) {
+                        self.fire(cmd, pos, vel, dir, &ShotScale::default(), scorer);
+                    }
+                }
+            }
+            Some(charge) => match trigger {
+                Some(_) if ready => {
+                    self.charge = (self.charge + time.elapsed_seconds).min(charge.seconds);
+                }
+                Some(_) => (),
+                None => {
+                    if self.charge >= charge.min_seconds && self.aim.magnitude2() > 1e-6 {
+                        let t = self.charge_fraction();
+                        let lerp = |full: f32| 1.0 + (full - 1.0) * t;
+                        let shot_scale = ShotScale {
+                            size: lerp(charge.full_size),
+                            damage: lerp(charge.full_damage),
+                            speed: lerp(charge.full_speed),
+                        };
+                        if draw(ship, self.def.energy * shot_scale.damage) {
+                            self.fire(cmd, pos, vel, self.aim, &shot_scale, scorer);
+                        }
+                    }
+                    self.charge = 0.0;
+                }
+            },
+        }
+    }
+
+    fn fire(
+        &mut self,
+        cmd: &mut CommandBuffer,
+        pos: Pt2,
+        vel: Vec2,
+        dir: Vec2,
+        shot_scale: &ShotScale,
+        mut scorer: Scorer,
+    ) {
+        let def = &self.def;
+        let projectile = Prefab::projectile(def, Team::of_mask(self.target_mask));
+        for dir in def.directions(dir) {
+            let instance = Instance::at(pos + dir.normalize_to(def.muzzle_offset))
+                .with_rot(dir.y.atan2(dir.x))
+                .with_vel(vel + dir.normalize_to(def.speed * shot_scale.speed))
+                .with_radius(def.shape.radius() * shot_scale.size)
+                .with_damage_scale(shot_scale.damage * self.damage_scale);
+            let projectile = projectile.spawn(cmd, &instance);
+            if let Some((shooter, score)) = scorer.as_mut() {
+                cmd.add_component(
+                    projectile,
+                    ShotComponent {
+                        shooter: *shooter,
+                        has_hit: false,
+                    },
+                );
+                score.stats.shots_fired += 1;
+            }
+        }
+        self.cooldown.reset(def.cooldown * self.cooldown_scale);
+    }
+}
+
+/// Draws `energy` from the shooter's battery, if it has one. Returns whether the shot can go ahead.
+fn draw(ship: Option<&mut ShipSystemsComponent>, energy: f32) -> bool {
+    match ship {
+        Some(ship) => ship.try_draw(energy),
+        None => true,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[legion::system(for_each)]
+pub fn weapon(
+    cmd: &mut CommandBuffer,
+    entity: &Entity,
+    &PositionComponent(pos): &PositionComponent,
+    &VelocityComponent(vel): &VelocityComponent,
+    weapon: &mut WeaponComponent,
+    ship: Option<&mut ShipSystemsComponent>,
+    score: Option<&mut ScoreComponent>,
+    #[resource] time: &Time,
+) {
+    let scorer = score.map(|score| (*entity, score));
+    weapon.step(cmd, pos, vel, ship, scorer, time);
+}
+
+#[allow(clippy::too_many_arguments)]
+#[legion::system(for_each)]
+pub fn alt_weapon(
+    cmd: &mut CommandBuffer,
+    entity: &Entity,
+    &PositionComponent(pos): &PositionComponent,
+    &VelocityComponent(vel): &VelocityComponent,
+    AltWeaponComponent(weapon): &mut AltWeaponComponent,
+    ship: Option<&mut ShipSystemsComponent>,
+    score: Option<&mut ScoreComponent>,
+    #[resource] time: &Time,
+) {
+    let scorer = score.map(|score| (*entity, score));
+    weapon.step(cmd, pos, vel, ship, scorer, time);
+}
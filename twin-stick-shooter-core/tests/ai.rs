@@ -0,0 +1,109 @@
+use cgmath::num_traits::{one, zero};
+use cgmath::{vec2, InnerSpace};
+use legion::{Entity, EntityStore, World};
+use twin_stick_shooter_core::ai::{AiComponent, Behavior, Steering};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::model::ModelComponent;
+use twin_stick_shooter_core::physics::{ForceComponent, MassComponent, VelocityComponent};
+use twin_stick_shooter_core::player::spawn_player;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::Pt2;
+
+fn spawn_agent(world: &mut World, pos: Pt2, steering: Vec<Steering>) -> Entity {
+    world.push((
+        PositionComponent(pos),
+        VelocityComponent(zero()),
+        ForceComponent::default(),
+        MassComponent::new(100.0),
+        AiComponent {
+            max_speed: 100.0,
+            max_accel: 200.0,
+            steering,
+        },
+        ModelComponent {
+            name: "test/target".to_string(),
+            transform: one(),
+        },
+    ))
+}
+
+fn velocity(game: &Game, entity: Entity) -> cgmath::Vector2<f32> {
+    let &VelocityComponent(vel) = game
+        .world()
+        .entry_ref(entity)
+        .unwrap()
+        .into_component::<VelocityComponent>()
+        .unwrap();
+    vel
+}
+
+#[test]
+fn seek_and_flee_steer_through_forces() {
+    let mut game = Game::new();
+    spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let seeker = spawn_agent(
+        game.world_mut(),
+        Pt2::new(200.0, 0.0),
+        vec![Steering::new(Behavior::Seek, 1.0)],
+    );
+    let fleer = spawn_agent(
+        game.world_mut(),
+        Pt2::new(0.0, 200.0),
+        vec![Steering::new(
+            Behavior::Flee {
+                panic_distance: 300.0,
+            },
+            1.0,
+        )],
+    );
+
+    game.step(0.01, Input::default());
+
+    // Acceleration is capped, so one step only nudges the velocity.
+    let seeker_vel = velocity(&game, seeker);
+    assert!(seeker_vel.x < 0.0);
+    assert!(seeker_vel.magnitude() <= 200.0 * 0.01 + 1e-3);
+    let fleer_vel = velocity(&game, fleer);
+    assert!(fleer_vel.y > 0.0);
+}
+
+#[test]
+fn target_behaviors_idle_without_players() {
+    let mut game = Game::new();
+    let agent = spawn_agent(
+        game.world_mut(),
+        Pt2::new(200.0, 0.0),
+        vec![
+            Steering::new(Behavior::Seek, 1.0),
+            Steering::new(
+                Behavior::Orbit {
+                    radius: 100.0,
+                    clockwise: true,
+                },
+                1.0,
+            ),
+            Steering::new(Behavior::strafe(100.0), 1.0),
+        ],
+    );
+
+    game.step(0.01, Input::default());
+
+    assert_eq!(velocity(&game, agent), vec2(0.0, 0.0));
+}
+
+#[test]
+fn separation_pushes_agents_apart() {
+    let mut game = Game::new();
+    let separation = || vec![Steering::new(Behavior::Separation { radius: 50.0 }, 1.0)];
+    let left = spawn_agent(game.world_mut(), Pt2::new(-10.0, 0.0), separation());
+    let right = spawn_agent(game.world_mut(), Pt2::new(10.0, 0.0), separation());
+    let far = spawn_agent(game.world_mut(), Pt2::new(500.0, 0.0), separation());
+
+    game.step(0.01, Input::default());
+
+    assert!(velocity(&game, left).x < 0.0);
+    assert!(velocity(&game, right).x > 0.0);
+    assert_eq!(velocity(&game, far), vec2(0.0, 0.0));
+}
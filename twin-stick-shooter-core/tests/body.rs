@@ -0,0 +1,105 @@
+use cgmath::num_traits::zero;
+use cgmath::{vec2, InnerSpace};
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::body::BodyComponent;
+use twin_stick_shooter_core::collision::{Circle, Rectangle, Shape};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::physics::{
+    AngularVelocityComponent, MassComponent, VelocityComponent,
+};
+use twin_stick_shooter_core::position::{PositionComponent, RotationComponent};
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::{Pt2, Vec2};
+
+fn body(shape: Shape) -> BodyComponent {
+    BodyComponent {
+        shape,
+        restitution: 0.5,
+        friction: 0.5,
+    }
+}
+
+/// A ball of radius 10 that can be knocked around and spun.
+fn ball(game: &mut Game, pos: Pt2, vel: Vec2, mass: f32) -> Entity {
+    game.world_mut().push((
+        PositionComponent(pos),
+        RotationComponent(0.0),
+        VelocityComponent(vel),
+        AngularVelocityComponent(0.0),
+        MassComponent::new(mass).with_inertia(0.5 * mass * 100.0),
+        body(Circle::new(10.0).into()),
+    ))
+}
+
+fn pos_and_vel(game: &Game, entity: Entity) -> (Pt2, Vec2) {
+    let entry = game.world().entry_ref(entity).unwrap();
+    (
+        entry.get_component::<PositionComponent>().unwrap().0,
+        entry.get_component::<VelocityComponent>().unwrap().0,
+    )
+}
+
+#[test]
+fn ramming_conserves_momentum_and_bounces() {
+    let mut game = Game::new();
+    let light = ball(&mut game, Pt2::new(0.0, 0.0), vec2(200.0, 0.0), 100.0);
+    let heavy = ball(&mut game, Pt2::new(30.0, 0.0), zero(), 300.0);
+
+    for _ in 0..10 {
+        game.step(0.01, Input::default());
+    }
+    let (light_pos, light_vel) = pos_and_vel(&game, light);
+    let (heavy_pos, heavy_vel) = pos_and_vel(&game, heavy);
+    let momentum = light_vel * 100.0 + heavy_vel * 300.0;
+    assert!(
+        (momentum - vec2(20000.0, 0.0)).magnitude() < 1.0,
+        "{:?}",
+        momentum
+    );
+    // They part at half the speed they met at, so the light one bounces back.
+    assert!(((heavy_vel - light_vel).x - 100.0).abs() < 1.0);
+    assert!(light_vel.x < 0.0);
+    assert!(heavy_pos.x - light_pos.x > 19.0);
+}
+
+#[test]
+fn immovable_bodies_stop_what_hits_them() {
+    let mut game = Game::new();
+    // No mass, so nothing moves it.
+    let wall = game.world_mut().push((
+        PositionComponent(Pt2::new(50.0, 0.0)),
+        body(Rectangle::new(20.0, 200.0).into()),
+    ));
+    let ship = ball(&mut game, Pt2::new(0.0, 0.0), vec2(400.0, 0.0), 100.0);
+
+    for _ in 0..20 {
+        game.step(0.01, Input::default());
+    }
+    let entry = game.world().entry_ref(wall).unwrap();
+    assert_eq!(
+        entry.get_component::<PositionComponent>().unwrap().0,
+        Pt2::new(50.0, 0.0)
+    );
+    let (pos, vel) = pos_and_vel(&game, ship);
+    assert!(pos.x < 31.0, "{:?}", pos);
+    assert!((vel.x + 200.0).abs() < 1.0, "{:?}", vel);
+}
+
+#[test]
+fn glancing_blows_drag_and_spin() {
+    let mut game = Game::new();
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, -20.0)),
+        body(Rectangle::new(1000.0, 20.0).into()),
+    ));
+    // Skimming along the wall while sinking into it.
+    let ship = ball(&mut game, Pt2::new(0.0, 0.0), vec2(300.0, -100.0), 100.0);
+
+    game.step(0.01, Input::default());
+    let (_, vel) = pos_and_vel(&game, ship);
+    assert!(vel.y > 0.0, "{:?}", vel);
+    assert!(vel.x < 300.0, "{:?}", vel);
+    let entry = game.world().entry_ref(ship).unwrap();
+    // Rolling along it, clockwise.
+    assert!(entry.get_component::<AngularVelocityComponent>().unwrap().0 < 0.0);
+}
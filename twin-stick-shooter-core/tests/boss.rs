@@ -0,0 +1,188 @@
+use cgmath::vec2;
+use legion::systems::CommandBuffer;
+use legion::{Entity, EntityStore, IntoQuery};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use twin_stick_shooter_core::ai::AiComponent;
+use twin_stick_shooter_core::boss::{BossComponent, PartComponent};
+use twin_stick_shooter_core::bullet::RemoveOnHitComponent;
+use twin_stick_shooter_core::collision::Circle;
+use twin_stick_shooter_core::enemy::{spawn_enemy, EnemyKind, ARENA_RADIUS};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{HitboxComponent, HitboxEffect, HitboxMask};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::{Instance, PrefabError, Prefabs};
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::transform::ParentComponent;
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+fn spawn_dreadnought(game: &mut Game) -> Entity {
+    let mut cmd = CommandBuffer::new(game.world());
+    let boss = spawn_enemy(
+        &mut cmd,
+        &mut Pcg32::seed_from_u64(0),
+        &Prefabs::builtin(),
+        EnemyKind::Dreadnought,
+        Pt2::new(0.0, 0.0),
+        vec2(0.0, 0.0),
+        ARENA_RADIUS,
+    );
+    cmd.flush(game.world_mut());
+    boss
+}
+
+fn parts(game: &Game) -> Vec<(Entity, ParentComponent, Pt2)> {
+    <(Entity, &PartComponent, &ParentComponent, &PositionComponent)>::query()
+        .iter(game.world())
+        .map(|(&entity, _, child, pos)| (entity, child.clone(), pos.0))
+        .collect()
+}
+
+fn health(game: &Game, entity: Entity) -> f32 {
+    game.world()
+        .entry_ref(entity)
+        .unwrap()
+        .get_component::<HealthComponent>()
+        .unwrap()
+        .health
+}
+
+fn hit(game: &mut Game, pos: Pt2, damage: f32) {
+    game.world_mut().push((
+        PositionComponent(pos),
+        HitboxComponent {
+            shape: Circle { radius: 1.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            effect: HitboxEffect::Damage(damage),
+            hit_entities: vec![],
+        },
+        RemoveOnHitComponent,
+    ));
+    game.step(0.01, Input::default());
+}
+
+#[test]
+fn parts_move_and_die_with_their_body() {
+    let mut game = Game::new();
+    let boss = Prefabs::builtin()
+        .spawn(
+            "dreadnought",
+            game.world_mut(),
+            &Instance::at(Pt2::new(0.0, 0.0)).with_vel(vec2(100.0, 0.0)),
+        )
+        .unwrap();
+
+    game.step(0.1, Input::default());
+    let body_pos = game
+        .world()
+        .entry_ref(boss)
+        .unwrap()
+        .get_component::<PositionComponent>()
+        .unwrap()
+        .0;
+    // The body's AI slows it down, but it has moved.
+    assert!(body_pos.x > 0.0);
+    let parts = parts(&game);
+    assert_eq!(parts.len(), 3);
+    for (_, child, pos) in &parts {
+        assert_eq!(child.parent, boss);
+        assert_eq!(*pos, body_pos + child.offset());
+    }
+
+    game.world_mut().remove(boss);
+    game.step(0.1, Input::default());
+    assert!(self::parts(&game).is_empty());
+}
+
+#[test]
+fn weak_points_pass_on_extra_damage_and_turrets_take_their_own() {
+    let mut game = Game::new();
+    let boss = spawn_dreadnought(&mut game);
+    let turret = parts(&game)
+        .into_iter()
+        .find(|(_, child, _)| child.offset() == vec2(0.0, 75.0))
+        .unwrap()
+        .0;
+
+    // The engine at the back takes triple damage, all of it passed to the hull.
+    hit(&mut game, Pt2::new(-75.0, 0.0), 2.0);
+    assert_eq!(health(&game, boss), 54.0);
+
+    hit(&mut game, Pt2::new(0.0, 75.0), 2.0);
+    assert_eq!(health(&game, turret), 6.0);
+    assert_eq!(health(&game, boss), 54.0);
+
+    hit(&mut game, Pt2::new(0.0, 75.0), 6.0);
+    assert!(game.world().entry_ref(turret).is_err());
+    assert_eq!(parts(&game).len(), 2);
+}
+
+#[test]
+fn bosses_change_phase_as_they_wear_down() {
+    let mut game = Game::new();
+    let boss = spawn_dreadnought(&mut game);
+    let set_health = |game: &mut Game, health: f32| {
+        let mut entry = game.world_mut().entry(boss).unwrap();
+        entry.get_component_mut::<HealthComponent>().unwrap().health = health;
+    };
+
+    set_health(&mut game, 39.0);
+    game.step(0.01, Input::default());
+    let entry = game.world().entry_ref(boss).unwrap();
+    assert_eq!(entry.get_component::<BossComponent>().unwrap().phase, 1);
+    assert_eq!(
+        entry.get_component::<WeaponComponent>().unwrap().def.name,
+        "flak"
+    );
+    assert_eq!(
+        entry.get_component::<AiComponent>().unwrap().max_speed,
+        90.0
+    );
+    assert!(entry
+        .get_component::<HealthComponent>()
+        .unwrap()
+        .is_invulnerable());
+
+    // Hits while it regroups do nothing.
+    hit(&mut game, Pt2::new(0.0, 0.0), 5.0);
+    assert_eq!(health(&game, boss), 39.0);
+
+    set_health(&mut game, 10.0);
+    game.step(0.01, Input::default());
+    let entry = game.world().entry_ref(boss).unwrap();
+    assert_eq!(entry.get_component::<BossComponent>().unwrap().phase, 2);
+    assert_eq!(
+        entry.get_component::<AiComponent>().unwrap().max_speed,
+        150.0
+    );
+}
+
+#[test]
+fn parts_must_name_prefabs_without_parts() {
+    let weapon_defs = WeaponDefs::builtin();
+
+    let json = r#"{ "hull": { "parts": [{ "prefab": "turret", "offset": [0.0, 0.0] }] } }"#;
+    assert_eq!(
+        Prefabs::from_json(json, &weapon_defs).unwrap_err(),
+        PrefabError::BadPart {
+            prefab: "hull".to_string(),
+            part: "turret".to_string(),
+        }
+    );
+
+    let json = r#"{
+        "hull": { "parts": [{ "prefab": "wing", "offset": [0.0, 0.0] }] },
+        "wing": { "parts": [{ "prefab": "pod", "offset": [0.0, 0.0] }] },
+        "pod": {}
+    }"#;
+    assert_eq!(
+        Prefabs::from_json(json, &weapon_defs).unwrap_err(),
+        PrefabError::BadPart {
+            prefab: "hull".to_string(),
+            part: "wing".to_string(),
+        }
+    );
+}
@@ -0,0 +1,44 @@
+use twin_stick_shooter_core::net::{
+    validate_chat_text, ChatError, ChatRateLimiter, MAX_CHAT_MESSAGE_CHARS,
+};
+use twin_stick_shooter_core::resource::Time;
+
+#[test]
+fn validate_normalizes_text() {
+    assert_eq!(
+        validate_chat_text("  hello\nthere \t"),
+        Ok("hello there".to_string())
+    );
+    assert_eq!(validate_chat_text(" \n "), Err(ChatError::Empty));
+    // Control characters that aren't whitespace are still blank once they're spaces.
+    assert_eq!(validate_chat_text("\u{0}"), Err(ChatError::Empty));
+    assert_eq!(validate_chat_text("\u{1b}hi\u{7f}"), Ok("hi".to_string()));
+}
+
+#[test]
+fn validate_limits_length_in_chars() {
+    let longest = "é".repeat(MAX_CHAT_MESSAGE_CHARS);
+    assert_eq!(validate_chat_text(&longest), Ok(longest.clone()));
+    assert_eq!(
+        validate_chat_text(&format!("{}é", longest)),
+        Err(ChatError::TooLong)
+    );
+}
+
+#[test]
+fn rate_limiter_allows_burst_then_refills() {
+    let mut limiter = ChatRateLimiter::new();
+    while limiter.try_send().is_ok() {}
+    assert_eq!(limiter.try_send(), Err(ChatError::RateLimited));
+
+    limiter.step(&Time {
+        elapsed_seconds: 0.5,
+    });
+    assert_eq!(limiter.try_send(), Err(ChatError::RateLimited));
+
+    limiter.step(&Time {
+        elapsed_seconds: 2.0,
+    });
+    assert_eq!(limiter.try_send(), Ok(()));
+    assert_eq!(limiter.try_send(), Err(ChatError::RateLimited));
+}
@@ -0,0 +1,59 @@
+use twin_stick_shooter_core::net::{ClockSync, TICKS_PER_SECOND};
+
+#[test]
+fn no_estimate_before_first_pong() {
+    assert!(ClockSync::new().estimate().is_none());
+}
+
+#[test]
+fn symmetric_round_trips() {
+    // The server clock reads 100 s ahead of the client clock and every round trip takes 50 ms.
+    let mut clock_sync = ClockSync::new();
+    for i in 0..10 {
+        let send = i as f64;
+        clock_sync.record_pong(send, send + 100.025, send + 0.05);
+    }
+
+    let estimate = clock_sync.estimate().unwrap();
+    assert!((estimate.rtt - 0.05).abs() < 1e-9);
+    assert!(estimate.jitter.abs() < 1e-9);
+    assert!((estimate.offset - 100.0).abs() < 1e-9);
+    assert!((estimate.server_tick(20.0) - 120.0 * TICKS_PER_SECOND).abs() < 1e-6);
+
+    // The client should run ahead of the server by at least the one-way trip.
+    assert!(estimate.target_tick(20.0) > estimate.server_tick(20.0) + 0.025 * TICKS_PER_SECOND);
+}
+
+#[test]
+fn offset_prefers_fastest_round_trip() {
+    let mut clock_sync = ClockSync::new();
+    // A slow round trip whose reply was delayed on the way back skews the naive offset.
+    clock_sync.record_pong(0.0, 100.025, 0.25);
+    clock_sync.record_pong(1.0, 101.025, 1.05);
+    clock_sync.record_pong(2.0, 102.025, 2.15);
+
+    let estimate = clock_sync.estimate().unwrap();
+    assert!((estimate.offset - 100.0).abs() < 1e-9);
+    assert!((estimate.rtt - 0.15).abs() < 1e-9);
+    assert!(estimate.jitter > 0.0);
+}
+
+#[test]
+fn time_dilation_is_gentle() {
+    let mut clock_sync = ClockSync::new();
+    clock_sync.record_pong(0.0, 100.025, 0.05);
+    let estimate = clock_sync.estimate().unwrap();
+    let target = estimate.target_tick(10.0);
+
+    assert!((estimate.time_dilation(target, 10.0) - 1.0).abs() < 1e-6);
+
+    let behind = estimate.time_dilation(target - 2.0, 10.0);
+    assert!(behind > 1.0);
+    assert!(behind <= 1.05);
+
+    let ahead = estimate.time_dilation(target + 2.0, 10.0);
+    assert!(ahead < 1.0);
+    assert!(ahead >= 0.95);
+
+    assert!((estimate.time_dilation(target - 1000.0, 10.0) - 1.05).abs() < 1e-6);
+}
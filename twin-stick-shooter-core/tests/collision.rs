@@ -3,13 +3,16 @@ use collision::dbvt::DynamicBoundingVolumeTree;
 use legion::{Resources, Schedule, World};
 use rand::SeedableRng;
 use rand_pcg::Pcg32;
-use twin_stick_shooter_core::collision::{Circle, Shape};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use twin_stick_shooter_core::collision::{
+    Circle, ConvexPolygon, Line, Particle, Rectangle, Shape, ShapeDef, ShapeError, Square,
+};
 use twin_stick_shooter_core::hitbox::{
     hitbox_system, HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent,
 };
-use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::position::{PositionComponent, RotationComponent};
 use twin_stick_shooter_core::resource::CollideCounters;
-use twin_stick_shooter_core::{translation, Pt2};
+use twin_stick_shooter_core::{rotation, translation, Mat3, Pt2};
 
 #[test]
 fn non_overlapping() {
@@ -102,3 +105,206 @@ fn system() {
         &[],
     );
 }
+
+/// One of each kind of shape, with how far it reaches to either side along x.
+fn shapes() -> Vec<(Shape, f32)> {
+    vec![
+        (Particle::new().into(), 0.0),
+        (
+            Line::new(Pt2::new(0.0, -1.0), Pt2::new(0.0, 1.0)).into(),
+            0.0,
+        ),
+        (Circle::new(1.0).into(), 1.0),
+        (Rectangle::new(4.0, 2.0).into(), 2.0),
+        (Square::new(2.0).into(), 1.0),
+        (
+            ConvexPolygon::new(vec![
+                Pt2::new(-1.0, -1.0),
+                Pt2::new(1.0, 0.0),
+                Pt2::new(-1.0, 1.0),
+            ])
+            .into(),
+            1.0,
+        ),
+    ]
+}
+
+fn test(shape_a: &Shape, xform_a: &Mat3, shape_b: &Shape, xform_b: &Mat3) -> bool {
+    twin_stick_shooter_core::collision::test(shape_a, xform_a, shape_b, xform_b)
+}
+
+#[test]
+fn every_pair_of_shapes() {
+    let origin = translation(vec2(0.0, 0.0));
+    for (shape_a, reach_a) in shapes() {
+        for (shape_b, reach_b) in shapes() {
+            let apart = translation(vec2(reach_a + reach_b + 0.1, 0.0));
+            assert!(
+                !test(&shape_a, &origin, &shape_b, &apart),
+                "{:?} and {:?} apart",
+                shape_a,
+                shape_b
+            );
+
+            // A particle can't overlap another shape without area except by landing exactly on it.
+            let is_particle = |shape: &Shape| matches!(shape, Shape::Particle(_));
+            let is_line = |shape: &Shape| matches!(shape, Shape::Line(_));
+            if (is_particle(&shape_a) || is_particle(&shape_b))
+                && (is_particle(&shape_a) || is_line(&shape_a))
+                && (is_particle(&shape_b) || is_line(&shape_b))
+            {
+                continue;
+            }
+            // Turned around, every shape reaches back as far as it did forward. Lines are turned
+            // to cross instead.
+            let turn = if is_line(&shape_a) && is_line(&shape_b) {
+                FRAC_PI_2
+            } else {
+                PI
+            };
+            let overlapping = translation(vec2(reach_a + reach_b - 0.1, 0.0)) * rotation(turn);
+            assert!(
+                test(&shape_a, &origin, &shape_b, &overlapping),
+                "{:?} and {:?} overlapping",
+                shape_a,
+                shape_b
+            );
+        }
+    }
+}
+
+#[test]
+fn shape_defs_agree_on_inertia() {
+    let rectangle = ShapeDef::Rectangle {
+        width: 4.0,
+        height: 2.0,
+    };
+    let polygon = ShapeDef::Polygon {
+        vertices: vec![[-2.0, -1.0], [2.0, -1.0], [2.0, 1.0], [-2.0, 1.0]],
+    };
+    assert!((rectangle.inertia(3.0) - 5.0).abs() < 1e-4);
+    assert!((polygon.inertia(3.0) - 5.0).abs() < 1e-4);
+    assert!((polygon.scaled(2.0).inertia(3.0) - 20.0).abs() < 1e-4);
+
+    let square = ShapeDef::Square { size: 2.0 };
+    let polygon = ShapeDef::Polygon {
+        vertices: vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]],
+    };
+    assert!((square.inertia(3.0) - polygon.inertia(3.0)).abs() < 1e-4);
+    assert!((square.radius() - polygon.radius()).abs() < 1e-4);
+
+    // A rod of length 2 about its middle.
+    let line = ShapeDef::Line {
+        from: [-1.0, 0.0],
+        to: [1.0, 0.0],
+    };
+    assert!((line.inertia(3.0) - 1.0).abs() < 1e-4);
+    assert!((line.scaled(2.0).radius() - 2.0).abs() < 1e-4);
+    assert_eq!(ShapeDef::Particle.inertia(3.0), 0.0);
+}
+
+#[test]
+fn every_kind_of_shape_loads_from_data() {
+    let json = r#"[
+        { "type": "Particle" },
+        { "type": "Line", "from": [0.0, -1.0], "to": [0.0, 1.0] },
+        { "type": "Circle", "radius": 1.0 },
+        { "type": "Rectangle", "width": 2.0, "height": 1.0 },
+        { "type": "Square", "size": 2.0 },
+        { "type": "Polygon", "vertices": [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]] }
+    ]"#;
+    let defs: Vec<ShapeDef> = serde_json::from_str(json).unwrap();
+    let shapes: Vec<Shape> = defs
+        .iter()
+        .inspect(|def| assert_eq!(def.validate(), Ok(())))
+        .map(ShapeDef::to_shape)
+        .collect();
+    assert!(matches!(shapes[0], Shape::Particle(_)));
+    assert!(matches!(shapes[1], Shape::Line(_)));
+    assert!(matches!(shapes[4], Shape::Square(_)));
+
+    let point = ShapeDef::Line {
+        from: [1.0, 1.0],
+        to: [1.0, 1.0],
+    };
+    assert_eq!(point.validate(), Err(ShapeError::ZeroLength));
+}
+
+#[test]
+fn polygons_must_be_convex_and_counterclockwise() {
+    let polygon = |vertices: &[[f32; 2]]| ShapeDef::Polygon {
+        vertices: vertices.to_vec(),
+    };
+    let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    assert_eq!(polygon(&square).validate(), Ok(()));
+    let mut backward = square;
+    backward.reverse();
+    assert_eq!(polygon(&backward).validate(), Err(ShapeError::Clockwise));
+    assert_eq!(
+        polygon(&square[..2]).validate(),
+        Err(ShapeError::TooFewVertices(2))
+    );
+
+    // An arrowhead, notched at the back.
+    let arrowhead = [[0.0, 0.0], [2.0, 1.0], [0.0, 2.0], [0.5, 1.0]];
+    assert_eq!(polygon(&arrowhead).validate(), Err(ShapeError::NotConvex));
+    // A pentagram turns left at every point but winds around twice.
+    let star: Vec<[f32; 2]> = (0..5)
+        .map(|i| {
+            let angle = i as f32 * 4.0 * PI / 5.0;
+            [angle.cos(), angle.sin()]
+        })
+        .collect();
+    assert_eq!(polygon(&star).validate(), Err(ShapeError::NotConvex));
+}
+
+#[test]
+fn system_with_rotated_shapes() {
+    let mut world = World::default();
+    // A long bar along the diagonal.
+    let bar = world.push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        RotationComponent(FRAC_PI_4),
+        HitboxComponent {
+            shape: Rectangle::new(20.0, 1.0).into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            effect: HitboxEffect::None,
+            hit_entities: vec![],
+        },
+    ));
+    let hurtbox = || HurtboxComponent {
+        shape: Circle::new(1.0).into(),
+        dbvt_index: None,
+        mask: HitboxMask::TARGET,
+        hit_by_entities: vec![],
+    };
+    let on_the_diagonal = world.push((PositionComponent(Pt2::new(6.0, 6.0)), hurtbox()));
+    let where_it_would_be_unturned = world.push((PositionComponent(Pt2::new(8.0, 0.0)), hurtbox()));
+    let mut resources = Resources::default();
+    resources.insert(Pcg32::from_rng(rand::thread_rng()).unwrap());
+    resources.insert(CollideCounters::default());
+    let mut schedule = Schedule::builder()
+        .add_system(hitbox_system(DynamicBoundingVolumeTree::new()))
+        .build();
+    schedule.execute(&mut world, &mut resources);
+
+    assert_eq!(
+        world
+            .entry(bar)
+            .unwrap()
+            .get_component::<HitboxComponent>()
+            .unwrap()
+            .hit_entities,
+        &[on_the_diagonal]
+    );
+    assert_eq!(
+        world
+            .entry(where_it_would_be_unturned)
+            .unwrap()
+            .get_component::<HurtboxComponent>()
+            .unwrap()
+            .hit_by_entities,
+        &[],
+    );
+}
@@ -0,0 +1,64 @@
+use cgmath::num_traits::{one, zero};
+use cgmath::{vec2, InnerSpace};
+use legion::EntityStore;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::gunner::{intercept_direction, Aim, GunnerComponent};
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::HitboxMask;
+use twin_stick_shooter_core::model::ModelComponent;
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::player::{spawn_player, PLAYER_MAX_HEALTH};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::ship::ShipSystemsComponent;
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+#[test]
+fn leading_shot_meets_moving_target() {
+    let rel_pos = vec2(300.0, 0.0);
+    let rel_vel = vec2(0.0, 100.0);
+    let shot_speed = 400.0;
+    let aim = intercept_direction(rel_pos, rel_vel, shot_speed).unwrap();
+
+    // The aim point is where the target will be when the shot arrives.
+    let t = aim.magnitude() / shot_speed;
+    let target_then = rel_pos + rel_vel * t;
+    assert!((target_then - aim).magnitude() < 1e-2);
+
+    // A target outrunning the shot can't be intercepted.
+    assert!(intercept_direction(rel_pos, vec2(500.0, 0.0), shot_speed).is_none());
+}
+
+#[test]
+fn gunner_shots_damage_player() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let mut def = WeaponDefs::builtin().get("sniper").unwrap().clone();
+    def.damage = 2.0;
+    game.world_mut().push((
+        PositionComponent(Pt2::new(100.0, 0.0)),
+        VelocityComponent(zero()),
+        GunnerComponent {
+            aim: Aim::Direct,
+            range: 500.0,
+        },
+        WeaponComponent::new(def, HitboxMask::PLAYER).with_cooldown(0.0),
+        ModelComponent {
+            name: "test/target".to_string(),
+            transform: one(),
+        },
+    ));
+
+    for _ in 0..50 {
+        game.step(0.01, Input::default());
+    }
+
+    // The shields take the hit, leaving the hull intact.
+    let entry = game.world().entry_ref(player).unwrap();
+    let ship = entry.get_component::<ShipSystemsComponent>().unwrap();
+    assert_eq!(ship.shield, ship.shield_capacity - 2.0);
+    let health = entry.get_component::<HealthComponent>().unwrap().health;
+    assert_eq!(health, PLAYER_MAX_HEALTH);
+}
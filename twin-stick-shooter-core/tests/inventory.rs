@@ -0,0 +1,125 @@
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::inventory::{
+    Inventory, InventoryError, ItemStack, Resource, MAX_STACK,
+};
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent, RESPAWN_DELAY_SECONDS};
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::{GameEvent, Input};
+use twin_stick_shooter_core::Pt2;
+
+fn inventory_mut(game: &mut Game, player: Entity) -> &mut Inventory {
+    &mut game
+        .world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .into_component_mut::<PlayerComponent>()
+        .unwrap()
+        .inventory
+}
+
+#[test]
+fn resources_respect_capacity_and_balance() {
+    let mut inventory = Inventory::new(100, 4);
+    assert_eq!(inventory.add(Resource::Scrap, 60), Ok(()));
+    assert_eq!(
+        inventory.add(Resource::Scrap, 50),
+        Err(InventoryError::Full)
+    );
+    assert_eq!(inventory.count(Resource::Scrap), 60);
+    // Too much to even count is still just full.
+    assert_eq!(
+        inventory.add(Resource::Scrap, u32::MAX),
+        Err(InventoryError::Full)
+    );
+    assert_eq!(inventory.count(Resource::Scrap), 60);
+    assert_eq!(inventory.add(Resource::Cores, 100), Ok(()));
+
+    assert_eq!(
+        inventory.remove(Resource::Scrap, 61),
+        Err(InventoryError::NotEnough)
+    );
+    assert_eq!(
+        inventory.spend(&[(Resource::Cores, 10), (Resource::Scrap, 61)]),
+        Err(InventoryError::NotEnough)
+    );
+    assert_eq!(inventory.count(Resource::Cores), 100);
+    assert_eq!(
+        inventory.spend(&[(Resource::Cores, 10), (Resource::Scrap, 60)]),
+        Ok(())
+    );
+    assert_eq!(inventory.count(Resource::Cores), 90);
+    assert_eq!(inventory.count(Resource::Scrap), 0);
+}
+
+#[test]
+fn items_stack_into_limited_slots() {
+    let mut inventory = Inventory::new(0, 2);
+    assert_eq!(inventory.add_item("mine", MAX_STACK - 1), Ok(()));
+    assert_eq!(inventory.add_item("mine", 2), Ok(()));
+    assert_eq!(
+        inventory.items(),
+        &[
+            ItemStack {
+                item: "mine".to_string(),
+                count: MAX_STACK,
+            },
+            ItemStack {
+                item: "mine".to_string(),
+                count: 1,
+            },
+        ][..]
+    );
+    assert_eq!(inventory.add_item("laser", 1), Err(InventoryError::Full));
+    assert_eq!(
+        inventory.add_item("mine", MAX_STACK),
+        Err(InventoryError::Full)
+    );
+    assert_eq!(inventory.item_count("mine"), MAX_STACK + 1);
+
+    assert_eq!(inventory.remove_item("mine", 2), Ok(()));
+    assert_eq!(inventory.items().len(), 1);
+    assert_eq!(inventory.add_item("laser", 1), Ok(()));
+    assert_eq!(
+        inventory.remove_item("laser", 2),
+        Err(InventoryError::NotEnough)
+    );
+}
+
+#[test]
+fn changes_are_announced_and_survive_respawn() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.step(0.01, Input::default());
+    assert_eq!(
+        game.game_event_queue().drain(),
+        vec![GameEvent::InventoryChanged { player }]
+    );
+    game.step(0.01, Input::default());
+    assert!(game.game_event_queue().drain().is_empty());
+
+    inventory_mut(&mut game, player)
+        .add(Resource::Cores, 5)
+        .unwrap();
+    game.step(0.01, Input::default());
+    assert_eq!(
+        game.game_event_queue().drain(),
+        vec![GameEvent::InventoryChanged { player }]
+    );
+
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<HealthComponent>()
+        .unwrap()
+        .health = 0.0;
+    let steps = (RESPAWN_DELAY_SECONDS / 0.01).ceil() as usize + 2;
+    for _ in 0..steps {
+        game.step(0.01, Input::default());
+    }
+    let entry = game.world().entry_ref(player).unwrap();
+    let player = entry.get_component::<PlayerComponent>().unwrap();
+    assert!(player.is_alive());
+    assert_eq!(player.inventory.count(Resource::Cores), 5);
+}
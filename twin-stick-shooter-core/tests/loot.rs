@@ -0,0 +1,140 @@
+use cgmath::num_traits::zero;
+use cgmath::InnerSpace;
+use legion::{Entity, EntityStore, IntoQuery};
+use twin_stick_shooter_core::bullet::RemoveOnHitComponent;
+use twin_stick_shooter_core::collision::Circle;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{
+    HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent,
+};
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::loot::{LootComponent, LootDrop, Pickup, PickupComponent};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::Pt2;
+
+fn pickups(game: &Game) -> Vec<(Pt2, HitboxEffect)> {
+    <(&PositionComponent, &HitboxComponent, &PickupComponent)>::query()
+        .iter(game.world())
+        .map(|(&PositionComponent(pos), hitbox, _)| (pos, hitbox.effect.clone()))
+        .collect()
+}
+
+fn player_component(game: &Game, player: Entity) -> PlayerComponent {
+    game.world()
+        .entry_ref(player)
+        .unwrap()
+        .into_component::<PlayerComponent>()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn destroyed_targets_drop_loot() {
+    let mut game = Game::new();
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        HurtboxComponent {
+            shape: Circle { radius: 20.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            hit_by_entities: vec![],
+        },
+        HealthComponent::new(1.0),
+        LootComponent {
+            drops: vec![
+                LootDrop {
+                    pickup: Pickup::Resource(Resource::Scrap, 5),
+                    chance: 1.0,
+                },
+                LootDrop {
+                    pickup: Pickup::Repair(1.0),
+                    chance: 0.0,
+                },
+            ],
+        },
+    ));
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        HitboxComponent {
+            shape: Circle { radius: 1.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            effect: HitboxEffect::Damage(1.0),
+            hit_entities: vec![],
+        },
+        RemoveOnHitComponent,
+    ));
+
+    game.step(0.01, Input::default());
+
+    let pickups = pickups(&game);
+    assert_eq!(pickups.len(), 1);
+    assert!(matches!(
+        pickups[0].1,
+        HitboxEffect::Pickup(Pickup::Resource(Resource::Scrap, 5))
+    ));
+}
+
+#[test]
+fn pickups_are_pulled_in_and_collected() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<HealthComponent>()
+        .unwrap()
+        .health = 5.0;
+
+    let pickup = |pickup, pos| {
+        (
+            PositionComponent(pos),
+            VelocityComponent(zero()),
+            HitboxComponent {
+                shape: Circle { radius: 10.0 }.into(),
+                dbvt_index: None,
+                mask: HitboxMask::PLAYER,
+                effect: HitboxEffect::Pickup(pickup),
+                hit_entities: vec![],
+            },
+            RemoveOnHitComponent,
+            PickupComponent,
+        )
+    };
+    game.world_mut().push(pickup(
+        Pickup::Resource(Resource::Cores, 2),
+        Pt2::new(100.0, 0.0),
+    ));
+    game.world_mut()
+        .push(pickup(Pickup::Repair(2.0), Pt2::new(0.0, -120.0)));
+    // Out of the magnet's reach.
+    game.world_mut().push(pickup(
+        Pickup::Resource(Resource::Scrap, 5),
+        Pt2::new(500.0, 0.0),
+    ));
+
+    for _ in 0..100 {
+        game.step(0.01, Input::default());
+    }
+
+    let remaining = pickups(&game);
+    assert_eq!(remaining.len(), 1);
+    assert!((remaining[0].0 - Pt2::new(500.0, 0.0)).magnitude() < 1e-3);
+
+    let inventory = player_component(&game, player).inventory;
+    assert_eq!(inventory.count(Resource::Cores), 2);
+    assert_eq!(inventory.count(Resource::Scrap), 0);
+    let health = game
+        .world()
+        .entry_ref(player)
+        .unwrap()
+        .into_component::<HealthComponent>()
+        .unwrap()
+        .health;
+    assert_eq!(health, 7.0);
+}
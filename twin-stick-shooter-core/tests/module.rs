@@ -0,0 +1,119 @@
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::module::{
+    LoadoutComponent, LoadoutError, Modifier, ModuleDefs, Op, ShipStats, Slot, Stat,
+};
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent, PLAYER_MAX_HEALTH};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::station::install;
+use twin_stick_shooter_core::weapon::WeaponComponent;
+use twin_stick_shooter_core::Pt2;
+
+fn docked_player(game: &mut Game) -> Entity {
+    let station = game
+        .world_mut()
+        .push((PositionComponent(Pt2::new(0.0, 0.0)),));
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<PlayerComponent>()
+        .unwrap()
+        .docked_to = Some(station);
+    player
+}
+
+#[test]
+fn adds_stack_before_multiplies() {
+    let modifiers = [
+        Modifier {
+            stat: Stat::MaxSpeed,
+            op: Op::Multiply,
+            value: 2.0,
+        },
+        Modifier {
+            stat: Stat::MaxSpeed,
+            op: Op::Add,
+            value: 50.0,
+        },
+        Modifier {
+            stat: Stat::MaxSpeed,
+            op: Op::Multiply,
+            value: 0.5,
+        },
+    ];
+    let base = ShipStats::default();
+    let stats = base.modified(modifiers.iter());
+    assert_eq!(stats.max_speed, base.max_speed + 50.0);
+    assert_eq!(stats.max_force, base.max_force);
+}
+
+#[test]
+fn modules_need_a_matching_slot() {
+    let defs = ModuleDefs::builtin();
+    let mut loadout = LoadoutComponent::new(ShipStats::default(), &[Slot::Engine, Slot::Engine]);
+    assert_eq!(
+        loadout.slot_for(&defs, "heavy_plating"),
+        Err(LoadoutError::NoSlot)
+    );
+    assert_eq!(
+        loadout.slot_for(&defs, "warp_drive"),
+        Err(LoadoutError::UnknownModule)
+    );
+
+    assert_eq!(loadout.slot_for(&defs, "ion_thrusters"), Ok(0));
+    assert_eq!(loadout.install(&defs, 0, "ion_thrusters"), Ok(None));
+    assert_eq!(loadout.slot_for(&defs, "ion_thrusters"), Ok(1));
+    assert_eq!(loadout.install(&defs, 1, "ion_thrusters"), Ok(None));
+    assert_eq!(
+        loadout.stats().max_speed,
+        ShipStats::default().max_speed + 100.0
+    );
+
+    assert_eq!(
+        loadout.uninstall(&defs, 0),
+        Some("ion_thrusters".to_string())
+    );
+    assert_eq!(
+        loadout.stats().max_speed,
+        ShipStats::default().max_speed + 50.0
+    );
+}
+
+#[test]
+fn installed_modules_change_the_ship() {
+    let mut game = Game::new();
+    let player = docked_player(&mut game);
+    let defs = ModuleDefs::builtin();
+    {
+        let mut entry = game.world_mut().entry_mut(player).unwrap();
+        let inventory = &mut entry
+            .get_component_mut::<PlayerComponent>()
+            .unwrap()
+            .inventory;
+        inventory.add_item("autoloader", 1).unwrap();
+        inventory.add_item("heavy_plating", 1).unwrap();
+    }
+
+    assert_eq!(
+        install(game.world_mut(), player, &defs, "autoloader"),
+        Ok(())
+    );
+    assert_eq!(
+        install(game.world_mut(), player, &defs, "heavy_plating"),
+        Ok(())
+    );
+    game.step(0.01, Input::default());
+
+    let entry = game.world().entry_ref(player).unwrap();
+    let weapon = entry.get_component::<WeaponComponent>().unwrap();
+    assert_eq!(weapon.cooldown_scale, 0.75);
+    let health = entry.get_component::<HealthComponent>().unwrap();
+    assert_eq!(health.max_health, PLAYER_MAX_HEALTH + 5.0);
+    assert_eq!(health.health, PLAYER_MAX_HEALTH);
+    let player = entry.get_component::<PlayerComponent>().unwrap();
+    assert!(player.inventory.items().is_empty());
+}
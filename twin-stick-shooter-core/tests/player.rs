@@ -0,0 +1,135 @@
+use cgmath::vec2;
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::HurtboxComponent;
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::player::{
+    spawn_player, InputComponent, PlayerComponent, PLAYER_LIVES, PLAYER_MAX_HEALTH,
+    RESPAWN_DELAY_SECONDS,
+};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::{GameEvent, GuiOverride, Input};
+use twin_stick_shooter_core::Pt2;
+
+fn kill(game: &mut Game, player: Entity) {
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<HealthComponent>()
+        .unwrap()
+        .health = 0.0;
+    game.step(0.01, Input::default());
+}
+
+fn player_component(game: &Game, player: Entity) -> PlayerComponent {
+    game.world()
+        .entry_ref(player)
+        .unwrap()
+        .into_component::<PlayerComponent>()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn death_spends_a_life_and_respawns() {
+    let mut game = Game::new();
+    let spawn_pos = Pt2::new(10.0, 20.0);
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), spawn_pos);
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<PositionComponent>()
+        .unwrap()
+        .0 = Pt2::new(500.0, 500.0);
+    game.step(0.01, Input::default());
+    game.game_event_queue().drain();
+
+    kill(&mut game, player);
+    assert_eq!(
+        game.game_event_queue().drain(),
+        vec![GameEvent::PlayerDied {
+            player,
+            lives: PLAYER_LIVES - 1,
+        }]
+    );
+    assert!(!player_component(&game, player).is_alive());
+    let entry = game.world().entry_ref(player).unwrap();
+    assert!(entry.get_component::<HurtboxComponent>().is_err());
+
+    let steps = (RESPAWN_DELAY_SECONDS / 0.01).ceil() as usize + 1;
+    for _ in 0..steps {
+        game.step(0.01, Input::default());
+    }
+
+    assert!(player_component(&game, player).is_alive());
+    let entry = game.world().entry_ref(player).unwrap();
+    assert!(entry.get_component::<HurtboxComponent>().is_ok());
+    let &PositionComponent(pos) = entry.get_component::<PositionComponent>().unwrap();
+    assert_eq!(pos, spawn_pos);
+    let health = entry.get_component::<HealthComponent>().unwrap();
+    assert_eq!(health.health, PLAYER_MAX_HEALTH);
+    assert!(health.is_invulnerable());
+    assert!(game.gui_override_queue().drain().is_empty());
+}
+
+#[test]
+fn last_death_is_game_over() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<PlayerComponent>()
+        .unwrap()
+        .lives = 1;
+
+    kill(&mut game, player);
+
+    let overrides = game.gui_override_queue().drain();
+    assert_eq!(overrides.len(), 1);
+    assert!(matches!(overrides[0], GuiOverride::GameOver));
+    let player = player_component(&game, player);
+    assert_eq!(player.lives, 0);
+    assert!(player.respawn.is_none());
+    assert!(!player.is_alive());
+}
+
+#[test]
+fn own_input_overrides_the_shared_input() {
+    let mut game = Game::new();
+    let local = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let remote = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(100.0, 0.0));
+    game.world_mut()
+        .entry(remote)
+        .unwrap()
+        .add_component(InputComponent(Input {
+            move_: vec2(0.0, -1.0),
+            ..Input::default()
+        }));
+
+    let input = Input {
+        move_: vec2(1.0, 0.0),
+        ..Input::default()
+    };
+    for _ in 0..10 {
+        game.step(0.01, input.clone());
+    }
+    let vel = |game: &Game, player: Entity| {
+        game.world()
+            .entry_ref(player)
+            .unwrap()
+            .get_component::<VelocityComponent>()
+            .unwrap()
+            .0
+    };
+    let local_vel = vel(&game, local);
+    assert!(local_vel.x > 0.0 && local_vel.y == 0.0, "{:?}", local_vel);
+    let remote_vel = vel(&game, remote);
+    assert!(
+        remote_vel.y < 0.0 && remote_vel.x == 0.0,
+        "{:?}",
+        remote_vel
+    );
+}
@@ -0,0 +1,216 @@
+use cgmath::vec2;
+use legion::systems::CommandBuffer;
+use legion::{Entity, EntityStore, World};
+use twin_stick_shooter_core::ai::AiComponent;
+use twin_stick_shooter_core::body::BodyComponent;
+use twin_stick_shooter_core::bullet::{LifespanComponent, PierceComponent, RemoveOnHitComponent};
+use twin_stick_shooter_core::collision::{Shape, ShapeError};
+use twin_stick_shooter_core::gunner::GunnerComponent;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{
+    HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent, SweptComponent,
+};
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::loot::{spawn_pickup, LootComponent, Pickup, PickupComponent};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::{Instance, Prefab, PrefabError, Prefabs, Team};
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+fn get<T: Clone + Send + Sync + 'static>(world: &World, entity: Entity) -> T {
+    world
+        .entry_ref(entity)
+        .unwrap()
+        .get_component::<T>()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn instances_override_the_template() {
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+
+    let enemy = prefabs
+        .spawn("sniper", &mut world, &Instance::at(Pt2::new(10.0, 20.0)))
+        .unwrap();
+    assert_eq!(
+        get::<PositionComponent>(&world, enemy).0,
+        Pt2::new(10.0, 20.0)
+    );
+    assert_eq!(get::<HealthComponent>(&world, enemy).max_health, 3.0);
+    let hurtbox = get::<HurtboxComponent>(&world, enemy);
+    assert!(hurtbox.mask.overlaps(HitboxMask::TARGET));
+    let weapon = get::<WeaponComponent>(&world, enemy);
+    assert!(weapon.target_mask.overlaps(HitboxMask::PLAYER));
+    assert!(weapon.cooldown.is_elapsed());
+
+    let instance = Instance::at(Pt2::new(0.0, 0.0))
+        .with_vel(vec2(5.0, 0.0))
+        .with_team(Team::Player)
+        .with_weapon_cooldown(0.5);
+    let turncoat = prefabs.spawn("sniper", &mut world, &instance).unwrap();
+    assert_eq!(get::<VelocityComponent>(&world, turncoat).0, vec2(5.0, 0.0));
+    let hurtbox = get::<HurtboxComponent>(&world, turncoat);
+    assert!(hurtbox.mask.overlaps(HitboxMask::PLAYER));
+    assert!(!hurtbox.mask.overlaps(HitboxMask::TARGET));
+    let weapon = get::<WeaponComponent>(&world, turncoat);
+    assert!(weapon.target_mask.overlaps(HitboxMask::TARGET));
+    assert!(!weapon.cooldown.is_elapsed());
+}
+
+#[test]
+fn spawns_through_a_command_buffer() {
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+    let mut cmd = CommandBuffer::new(&world);
+    let entity = prefabs
+        .spawn("charger", &mut cmd, &Instance::at(Pt2::new(1.0, 2.0)))
+        .unwrap();
+    cmd.flush(&mut world);
+
+    let entry = world.entry_ref(entity).unwrap();
+    assert_eq!(
+        entry.get_component::<PositionComponent>().unwrap().0,
+        Pt2::new(1.0, 2.0)
+    );
+    assert_eq!(
+        entry.get_component::<WeaponComponent>().unwrap().def.name,
+        "shotgun"
+    );
+    assert_eq!(
+        entry.get_component::<LootComponent>().unwrap().drops.len(),
+        4
+    );
+}
+
+#[test]
+fn loading_reports_bad_templates() {
+    let weapon_defs = WeaponDefs::builtin();
+
+    let json = r#"{ "turret": { "team": "Enemy", "weapon": "death_ray" } }"#;
+    assert_eq!(
+        Prefabs::from_json(json, &weapon_defs).unwrap_err(),
+        PrefabError::UnknownWeapon {
+            prefab: "turret".to_string(),
+            weapon: "death_ray".to_string(),
+        }
+    );
+
+    let json = r#"{ "turret": { "team": "Enemy", "helth": 3.0 } }"#;
+    match Prefabs::from_json(json, &weapon_defs) {
+        Err(PrefabError::Parse(message)) => assert!(message.contains("helth"), "{}", message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let json = r#"{
+        "wedge": {
+            "hurtbox": { "type": "Polygon", "vertices": [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0]] }
+        }
+    }"#;
+    assert_eq!(
+        Prefabs::from_json(json, &weapon_defs).unwrap_err(),
+        PrefabError::BadShape {
+            prefab: "wedge".to_string(),
+            error: ShapeError::Clockwise,
+        }
+    );
+
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+    assert_eq!(
+        prefabs
+            .spawn("mothership", &mut world, &Instance::at(Pt2::new(0.0, 0.0)))
+            .unwrap_err(),
+        PrefabError::NotFound("mothership".to_string())
+    );
+}
+
+fn circle_radius(shape: &Shape) -> f32 {
+    match shape {
+        Shape::Circle(circle) => circle.radius,
+        _ => panic!("not a circle: {:?}", shape),
+    }
+}
+
+#[test]
+fn radius_resizes_every_shape_in_proportion() {
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+    let station = prefabs
+        .spawn(
+            "station",
+            &mut world,
+            &Instance::at(Pt2::new(0.0, 0.0)).with_radius(100.0),
+        )
+        .unwrap();
+
+    // The station prefab's hitbox is 50 across its radius and its body 35.
+    let hitbox = get::<HitboxComponent>(&world, station);
+    assert_eq!(circle_radius(&hitbox.shape), 100.0);
+    let body = get::<BodyComponent>(&world, station);
+    assert_eq!(circle_radius(&body.shape), 70.0);
+}
+
+#[test]
+fn projectiles_spawn_from_their_weapon() {
+    let weapon_defs = WeaponDefs::builtin();
+    let mut world = World::default();
+    let rail = Prefab::projectile(weapon_defs.get("rail_blaster").unwrap(), Team::Enemy);
+    let instance = Instance::at(Pt2::new(0.0, 0.0))
+        .with_radius(8.0)
+        .with_damage_scale(2.0);
+    let shot = rail.spawn(&mut world, &instance);
+
+    let hitbox = get::<HitboxComponent>(&world, shot);
+    assert!(hitbox.mask.overlaps(HitboxMask::TARGET));
+    assert_eq!(circle_radius(&hitbox.shape), 8.0);
+    match hitbox.effect {
+        HitboxEffect::Damage(damage) => assert_eq!(damage, 6.0),
+        ref effect => panic!("{:?}", effect),
+    }
+    assert_eq!(get::<LifespanComponent>(&world, shot).0.remaining(), 0.5);
+    let entry = world.entry_ref(shot).unwrap();
+    assert!(entry.get_component::<PierceComponent>().is_ok());
+    assert!(entry.get_component::<SweptComponent>().is_ok());
+    assert!(entry.get_component::<RemoveOnHitComponent>().is_err());
+}
+
+#[test]
+fn enemies_get_their_ai_and_gunner_from_the_template() {
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+
+    let sniper = prefabs
+        .spawn("sniper", &mut world, &Instance::at(Pt2::new(0.0, 0.0)))
+        .unwrap();
+    let ai = get::<AiComponent>(&world, sniper);
+    assert_eq!(ai.max_speed, 150.0);
+    assert_eq!(ai.steering.len(), 3);
+    assert_eq!(get::<GunnerComponent>(&world, sniper).range, 400.0);
+}
+
+#[test]
+fn pickups_carry_what_they_were_dropped_as() {
+    let prefabs = Prefabs::builtin();
+    let mut world = World::default();
+    let mut cmd = CommandBuffer::new(&world);
+    let pickup = Pickup::Resource(Resource::Scrap, 7);
+    let entity = spawn_pickup(
+        &mut cmd,
+        &prefabs,
+        pickup,
+        Pt2::new(5.0, 0.0),
+        vec2(1.0, 0.0),
+    );
+    cmd.flush(&mut world);
+
+    let entry = world.entry_ref(entity).unwrap();
+    assert!(entry.get_component::<PickupComponent>().is_ok());
+    assert!(matches!(
+        get::<HitboxComponent>(&world, entity).effect,
+        HitboxEffect::Pickup(Pickup::Resource(Resource::Scrap, 7))
+    ));
+    assert_eq!(get::<VelocityComponent>(&world, entity).0, vec2(1.0, 0.0));
+}
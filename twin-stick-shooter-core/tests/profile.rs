@@ -0,0 +1,148 @@
+use legion::{Entity, EntityStore};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::module::{LoadoutComponent, ModuleDefs};
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent};
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::profile::{
+    upgrade, FileStorage, Migration, Profile, ProfileError, MAX_BEST_SCORES, PROFILE_VERSION,
+};
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "twin-stick-shooter-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn player_state(game: &Game, player: Entity) -> (u32, u32, String, Vec<Option<String>>) {
+    let entry = game.world().entry_ref(player).unwrap();
+    let inventory = &entry.get_component::<PlayerComponent>().unwrap().inventory;
+    let loadout = entry.get_component::<LoadoutComponent>().unwrap();
+    (
+        inventory.count(Resource::Scrap),
+        inventory.item_count("autoloader"),
+        entry
+            .get_component::<WeaponComponent>()
+            .unwrap()
+            .def
+            .name
+            .clone(),
+        loadout
+            .slots()
+            .iter()
+            .map(|(_, module)| module.clone())
+            .collect(),
+    )
+}
+
+#[test]
+fn profiles_carry_ships_between_sessions() {
+    let weapon_defs = WeaponDefs::builtin();
+    let module_defs = ModuleDefs::builtin();
+    let mut storage = FileStorage::new(temp_dir("profile"));
+    assert_eq!(Profile::load(&storage), Ok(Profile::default()));
+
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    {
+        let mut entry = game.world_mut().entry_mut(player).unwrap();
+        let inventory = &mut entry
+            .get_component_mut::<PlayerComponent>()
+            .unwrap()
+            .inventory;
+        inventory.add(Resource::Scrap, 42).unwrap();
+        inventory.add_item("autoloader", 2).unwrap();
+        let weapon = entry.get_component_mut::<WeaponComponent>().unwrap();
+        *weapon = WeaponComponent::new(
+            weapon_defs.get("rail_blaster").unwrap().clone(),
+            weapon.target_mask,
+        );
+        let loadout = entry.get_component_mut::<LoadoutComponent>().unwrap();
+        let index = loadout.slot_for(&module_defs, "heavy_plating").unwrap();
+        loadout
+            .install(&module_defs, index, "heavy_plating")
+            .unwrap();
+    }
+    let mut profile = Profile::default();
+    profile.settings.draw_hitboxes = true;
+    profile.capture(game.world(), player, &module_defs);
+    profile.save(&mut storage).unwrap();
+
+    let loaded = Profile::load(&storage).unwrap();
+    assert_eq!(loaded, profile);
+    assert!(loaded.settings.draw_hitboxes);
+    assert!(loaded.unlocked_modules.contains("heavy_plating"));
+    assert!(loaded.unlocked_modules.contains("autoloader"));
+    assert!(!loaded.unlocked_modules.contains("rail_blaster"));
+
+    let mut next_game = Game::new();
+    let next_player = spawn_player(
+        next_game.world_mut(),
+        &Prefabs::builtin(),
+        Pt2::new(0.0, 0.0),
+    );
+    loaded.restore(
+        next_game.world_mut(),
+        next_player,
+        &weapon_defs,
+        &module_defs,
+    );
+    assert_eq!(
+        player_state(&next_game, next_player),
+        player_state(&game, player)
+    );
+}
+
+#[test]
+fn old_profiles_are_migrated_and_new_ones_refused() {
+    fn rename_credits(value: &mut Value) {
+        let credits = value["credits"].take();
+        value["resources"] = json!({ "Cores": credits });
+    }
+    let migrations: &[Migration] = &[rename_credits];
+
+    let upgraded = upgrade(json!({ "credits": 7 }), migrations).unwrap();
+    assert_eq!(upgraded["version"], json!(2));
+    assert_eq!(upgraded["resources"], json!({ "Cores": 7 }));
+
+    // Already current, so nothing to do.
+    let current = json!({ "version": 2, "credits": 7 });
+    assert_eq!(upgrade(current, migrations).unwrap()["credits"], json!(7));
+
+    let future = format!("{{ \"version\": {} }}", PROFILE_VERSION + 1);
+    assert_eq!(
+        Profile::from_json(&future),
+        Err(ProfileError::TooNew {
+            version: PROFILE_VERSION + 1
+        })
+    );
+    assert!(matches!(
+        Profile::from_json("not json"),
+        Err(ProfileError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn best_scores_stay_sorted_and_capped() {
+    let mut profile = Profile::default();
+    for score in 1..=MAX_BEST_SCORES as u64 {
+        assert!(profile.record_score(score * 100, 1));
+    }
+    assert!(!profile.record_score(50, 1));
+    assert!(profile.record_score(550, 4));
+
+    let scores: Vec<u64> = profile.best_scores.iter().map(|best| best.score).collect();
+    assert_eq!(scores.len(), MAX_BEST_SCORES);
+    assert_eq!(scores[0], MAX_BEST_SCORES as u64 * 100);
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+    assert!(!scores.contains(&100));
+    assert_eq!(profile.best_scores[5].wave, 4);
+}
@@ -0,0 +1,132 @@
+use cgmath::num_traits::zero;
+use cgmath::vec2;
+use legion::{Entity, EntityStore};
+use std::f32::consts::{FRAC_PI_2, PI};
+use twin_stick_shooter_core::facing::FacingComponent;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::interpolate::InterpolateComponent;
+use twin_stick_shooter_core::physics::{
+    AngularVelocityComponent, MassComponent, TorqueComponent, VelocityComponent,
+};
+use twin_stick_shooter_core::player::spawn_player;
+use twin_stick_shooter_core::position::{PositionComponent, RotationComponent};
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::{Input, Subframe};
+use twin_stick_shooter_core::transform::{Orphan, ParentComponent};
+use twin_stick_shooter_core::Pt2;
+
+fn rotation(game: &Game, entity: Entity) -> f32 {
+    game.world()
+        .entry_ref(entity)
+        .unwrap()
+        .get_component::<RotationComponent>()
+        .unwrap()
+        .0
+}
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+}
+
+#[test]
+fn torque_spins_bodies_and_angles_interpolate_the_short_way() {
+    let mut game = Game::new();
+    let pos = Pt2::new(0.0, 0.0);
+    let entity = game.world_mut().push((
+        PositionComponent(pos),
+        RotationComponent(3.0),
+        InterpolateComponent::new(pos, 3.0),
+        VelocityComponent(zero()),
+        AngularVelocityComponent(0.0),
+        TorqueComponent(8.0),
+        MassComponent::new(2.0).with_inertia(4.0),
+    ));
+
+    game.step(0.5, Input::default());
+    let entry = game.world().entry_ref(entity).unwrap();
+    assert_close(
+        entry.get_component::<AngularVelocityComponent>().unwrap().0,
+        1.0,
+    );
+    assert_close(entry.get_component::<TorqueComponent>().unwrap().0, 0.0);
+    // Past π, it wraps around to the negative side.
+    assert_close(rotation(&game, entity), 3.5 - 2.0 * PI);
+
+    // Halfway from 3.0 to 3.5 is 3.25, wrapped, not back across zero.
+    game.interpolate(Subframe(0.5));
+    let entry = game.world().entry_ref(entity).unwrap();
+    assert_close(
+        entry
+            .get_component::<InterpolateComponent>()
+            .unwrap()
+            .interpolated_rot,
+        3.25 - 2.0 * PI,
+    );
+}
+
+#[test]
+fn ships_face_their_aim_or_else_their_heading() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let turn_rate = game
+        .world()
+        .entry_ref(player)
+        .unwrap()
+        .get_component::<FacingComponent>()
+        .unwrap()
+        .turn_rate;
+
+    // Aiming straight up turns at the turn rate, then stops on target.
+    let aiming = Input {
+        aim: vec2(0.0, 1.0),
+        fire: true,
+        ..Input::default()
+    };
+    game.step(0.05, aiming.clone());
+    assert_close(rotation(&game, player), turn_rate * 0.05);
+    for _ in 0..20 {
+        game.step(0.05, aiming.clone());
+    }
+    assert_close(rotation(&game, player), FRAC_PI_2);
+
+    // Without aiming, it turns to face the way it's going.
+    let moving = Input {
+        move_: vec2(-1.0, 0.0),
+        ..Input::default()
+    };
+    for _ in 0..20 {
+        game.step(0.05, moving.clone());
+    }
+    assert_close(rotation(&game, player).abs(), PI);
+}
+
+#[test]
+fn children_turn_with_their_parents() {
+    let mut game = Game::new();
+    let parent = game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        RotationComponent(0.0),
+        VelocityComponent(zero()),
+        AngularVelocityComponent(FRAC_PI_2),
+    ));
+    let child = game.world_mut().push((
+        PositionComponent(Pt2::new(10.0, 0.0)),
+        RotationComponent(0.0),
+        VelocityComponent(zero()),
+        AngularVelocityComponent(0.0),
+        ParentComponent::new(parent, vec2(10.0, 0.0), Orphan::Destroy),
+    ));
+
+    for _ in 0..10 {
+        game.step(0.1, Input::default());
+    }
+    assert_close(rotation(&game, child), FRAC_PI_2);
+    let entry = game.world().entry_ref(child).unwrap();
+    let pos = entry.get_component::<PositionComponent>().unwrap().0;
+    assert_close(pos.x, 0.0);
+    assert_close(pos.y, 10.0);
+    assert_close(
+        entry.get_component::<AngularVelocityComponent>().unwrap().0,
+        FRAC_PI_2,
+    );
+}
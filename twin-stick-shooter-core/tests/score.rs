@@ -0,0 +1,120 @@
+use cgmath::vec2;
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::bullet::RemoveOnHitComponent;
+use twin_stick_shooter_core::collision::Circle;
+use twin_stick_shooter_core::enemy::{EnemyComponent, EnemyKind};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{
+    HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent,
+};
+use twin_stick_shooter_core::player::spawn_player;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::score::{ScoreComponent, COMBO_DECAY_SECONDS};
+use twin_stick_shooter_core::Pt2;
+
+fn score(game: &Game, player: Entity) -> ScoreComponent {
+    game.world()
+        .entry_ref(player)
+        .unwrap()
+        .into_component::<ScoreComponent>()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn combo_multiplies_kills_and_decays() {
+    let mut tally = ScoreComponent::default();
+    tally.record_kill(EnemyKind::Sniper);
+    tally.record_kill(EnemyKind::Charger);
+    tally.record_kill(EnemyKind::Sniper);
+    assert_eq!(tally.score, 100 + 150 * 2 + 100 * 3);
+    assert_eq!(tally.combo, 4);
+    assert_eq!(tally.stats.kills[&EnemyKind::Sniper], 2);
+
+    // Held in a game, the combo drops one step per decay period.
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    *game
+        .world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<ScoreComponent>()
+        .unwrap() = tally;
+    // A quarter second divides the decay period exactly.
+    let steps = (COMBO_DECAY_SECONDS / 0.25) as usize;
+    for _ in 0..steps * 2 {
+        game.step(0.25, Input::default());
+    }
+    assert_eq!(score(&game, player).combo, 2);
+}
+
+#[test]
+fn shooting_an_enemy_records_stats() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let enemy = game.world_mut().push((
+        PositionComponent(Pt2::new(200.0, 0.0)),
+        HurtboxComponent {
+            shape: Circle { radius: 20.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            hit_by_entities: vec![],
+        },
+        HealthComponent::new(2.0),
+        EnemyComponent {
+            kind: EnemyKind::Sniper,
+        },
+    ));
+
+    let fire = Input {
+        aim: vec2(1.0, 0.0),
+        fire: true,
+        ..Input::default()
+    };
+    for _ in 0..100 {
+        if game.world().entry_ref(enemy).is_err() {
+            break;
+        }
+        game.step(0.01, fire.clone());
+    }
+    assert!(game.world().entry_ref(enemy).is_err());
+    // Let stray shots expire.
+    for _ in 0..200 {
+        game.step(0.01, Input::default());
+    }
+
+    let score = score(&game, player);
+    let stats = &score.stats;
+    assert_eq!(score.score, EnemyKind::Sniper.points() as u64);
+    assert_eq!(stats.kills[&EnemyKind::Sniper], 1);
+    assert!(stats.shots_fired >= 2);
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.damage_dealt, 2.0);
+    assert_eq!(stats.accuracy(), 2.0 / stats.shots_fired as f32);
+    assert!(stats.time_survived > 1.0);
+}
+
+#[test]
+fn damage_taken_includes_shields() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        HitboxComponent {
+            shape: Circle { radius: 1.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::PLAYER,
+            effect: HitboxEffect::Damage(3.0),
+            hit_entities: vec![],
+        },
+        RemoveOnHitComponent,
+    ));
+    game.step(0.01, Input::default());
+
+    let score = score(&game, player);
+    assert_eq!(score.stats.damage_taken, 3.0);
+    assert_eq!(score.stats.damage_dealt, 0.0);
+}
@@ -0,0 +1,160 @@
+use legion::{EntityStore, IntoQuery};
+use twin_stick_shooter_core::enemy::{EnemyComponent, EnemyKind};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::model::ModelComponent;
+use twin_stick_shooter_core::player::PlayerComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::sector::{Sector, SectorError, Sectors, START_SECTOR};
+use twin_stick_shooter_core::wave::{SpawnerComponent, WavePhase};
+use twin_stick_shooter_core::Pt2;
+
+fn count_stations(game: &Game) -> usize {
+    <&ModelComponent>::query()
+        .iter(game.world())
+        .filter(|model| model.name == "ships/station")
+        .count()
+}
+
+#[test]
+fn builtin_sectors_populate() {
+    let sectors = Sectors::builtin();
+
+    let mut game = Game::new();
+    let player = sectors.get(START_SECTOR).unwrap().populate(&mut game);
+    let entry = game.world().entry_ref(player).unwrap();
+    assert!(entry.get_component::<PlayerComponent>().is_ok());
+    assert_eq!(count_stations(&game), 1);
+    assert!(matches!(
+        game.wave_director().phase(),
+        WavePhase::Breather(_)
+    ));
+
+    // Loading another sector replaces the first.
+    sectors.get("proving_grounds").unwrap().populate(&mut game);
+    let kinds: Vec<EnemyKind> = <&EnemyComponent>::query()
+        .iter(game.world())
+        .map(|enemy| enemy.kind)
+        .collect();
+    assert_eq!(kinds.len(), 32);
+    assert_eq!(
+        kinds
+            .iter()
+            .filter(|&&kind| kind == EnemyKind::Charger)
+            .count(),
+        8
+    );
+    let spawners: Vec<(&PositionComponent, &SpawnerComponent)> =
+        <(&PositionComponent, &SpawnerComponent)>::query()
+            .iter(game.world())
+            .collect();
+    assert_eq!(spawners.len(), 1);
+    assert_eq!(spawners[0].0 .0, Pt2::new(600.0, 0.0));
+    assert_eq!(
+        spawners[0].1.pending.last(),
+        Some(&EnemyKind::Charger),
+        "spawners release in the order listed"
+    );
+    assert_eq!(<&PlayerComponent>::query().iter(game.world()).count(), 1);
+    assert_eq!(count_stations(&game), 1);
+    assert!(matches!(game.wave_director().phase(), WavePhase::Idle));
+    assert_eq!(game.ambient().background, "#0a0a1e");
+}
+
+#[test]
+fn parse_errors_point_at_the_mistake() {
+    let unknown_kind = r#"{
+    "name": "Typo",
+    "boundary": 400.0,
+    "enemies": [{ "kind": "Snipper", "radius": 100.0 }]
+}"#;
+    match Sector::from_json("typo", unknown_kind, &Sectors::builtin()) {
+        Err(SectorError::Parse { line, message, .. }) => {
+            assert_eq!(line, 4);
+            assert!(message.contains("Snipper"), "{}", message);
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let unknown_field = r#"{
+    "name": "Typo",
+    "boundary": 400.0,
+    "station": []
+}"#;
+    match Sector::from_json("typo", unknown_field, &Sectors::builtin()) {
+        Err(SectorError::Parse { line, message, .. }) => {
+            assert_eq!(line, 4);
+            assert!(message.contains("station"), "{}", message);
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_values_name_their_path() {
+    let json = r#"{
+    "name": "Broken",
+    "boundary": 400.0,
+    "stations": [
+        { "position": [0.0, 0.0] },
+        { "position": [100.0, 0.0], "radius": -5.0 }
+    ]
+}"#;
+    let err = Sector::from_json("broken", json, &Sectors::builtin()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "broken.stations[1].radius: must be positive, not -5"
+    );
+
+    let json = r#"{
+    "broken": { "name": "Broken", "boundary": 400.0, "waves": { "spawn_radius": 300.0 } }
+}"#;
+    match Sectors::from_json(json) {
+        Err(SectorError::Invalid { path, .. }) => assert_eq!(path, "broken.waves.spawn_radius"),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+
+    assert_eq!(
+        Sectors::builtin().get("nowhere").unwrap_err(),
+        SectorError::NotFound("nowhere".to_string())
+    );
+}
+
+#[test]
+fn lone_sectors_go_by_their_id() {
+    let sector = Sector::from_json(
+        "lone",
+        r#"{ "name": "Lone", "boundary": 500.0 }"#,
+        &Sectors::builtin(),
+    )
+    .unwrap();
+    assert_eq!(sector.id, "lone");
+
+    let mut game = Game::new();
+    sector.populate(&mut game);
+    assert_eq!(game.current_sector(), "lone");
+    assert!(game.sector_world("lone").is_some());
+}
+
+#[test]
+fn lone_sector_gates_must_lead_somewhere() {
+    let gates = |destination: &str| {
+        format!(
+            r#"{{
+    "name": "Lone",
+    "boundary": 500.0,
+    "gates": [{{ "position": [0.0, 0.0], "destination": "{}" }}]
+}}"#,
+            destination
+        )
+    };
+    let sectors = Sectors::builtin();
+    assert!(Sector::from_json("lone", &gates(START_SECTOR), &sectors).is_ok());
+    assert!(Sector::from_json("lone", &gates("lone"), &sectors).is_ok());
+    assert_eq!(
+        Sector::from_json("lone", &gates("nowhere"), &sectors).unwrap_err(),
+        SectorError::Invalid {
+            path: "lone.gates[0].destination".to_string(),
+            message: "no sector named \"nowhere\"".to_string(),
+        }
+    );
+}
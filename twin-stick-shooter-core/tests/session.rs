@@ -0,0 +1,53 @@
+use legion::World;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use twin_stick_shooter_core::net::{SessionTable, SESSION_GRACE_SECONDS};
+use twin_stick_shooter_core::resource::Time;
+
+#[test]
+fn resume_within_grace_period() {
+    let mut world = World::default();
+    let player = world.push((0u32,));
+    let mut sessions = SessionTable::new();
+    let token = sessions.create(&mut Pcg32::seed_from_u64(0), player);
+
+    sessions.disconnect(&token);
+    assert!(!sessions.is_connected(&token));
+    let expired = sessions.step(&Time {
+        elapsed_seconds: SESSION_GRACE_SECONDS - 1.0,
+    });
+    assert!(expired.is_empty());
+
+    assert_eq!(sessions.resume(&token), Some(player));
+    assert!(sessions.is_connected(&token));
+
+    // A connected session never expires.
+    let expired = sessions.step(&Time {
+        elapsed_seconds: 2.0 * SESSION_GRACE_SECONDS,
+    });
+    assert!(expired.is_empty());
+}
+
+#[test]
+fn expire_after_grace_period() {
+    let mut world = World::default();
+    let player = world.push((0u32,));
+    let mut sessions = SessionTable::new();
+    let token = sessions.create(&mut Pcg32::seed_from_u64(0), player);
+
+    sessions.disconnect(&token);
+    let expired = sessions.step(&Time {
+        elapsed_seconds: SESSION_GRACE_SECONDS,
+    });
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].token, token);
+    assert_eq!(expired[0].name, "Pilot 1");
+    assert_eq!(expired[0].player, player);
+    assert_eq!(sessions.resume(&token), None);
+    assert_eq!(sessions.player(&token), None);
+}
+
+#[test]
+fn unknown_token() {
+    assert_eq!(SessionTable::new().resume("not a token"), None);
+}
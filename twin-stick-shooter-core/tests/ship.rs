@@ -0,0 +1,136 @@
+use cgmath::num_traits::zero;
+use cgmath::vec2;
+use legion::{Entity, EntityStore, IntoQuery};
+use twin_stick_shooter_core::bullet::{LifespanComponent, RemoveOnHitComponent};
+use twin_stick_shooter_core::collision::Circle;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{
+    HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent,
+};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::ship::ShipSystemsComponent;
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDef, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+fn spawn_ship(game: &mut Game, ship: ShipSystemsComponent) -> Entity {
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        VelocityComponent(zero()),
+        HurtboxComponent {
+            shape: Circle { radius: 20.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            hit_by_entities: vec![],
+        },
+        HealthComponent::new(10.0),
+        ship,
+    ))
+}
+
+fn hit(game: &mut Game, damage: f32) {
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        HitboxComponent {
+            shape: Circle { radius: 1.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            effect: HitboxEffect::Damage(damage),
+            hit_entities: vec![],
+        },
+        RemoveOnHitComponent,
+    ));
+    game.step(0.01, Input::default());
+}
+
+fn state(game: &Game, entity: Entity) -> (ShipSystemsComponent, f32) {
+    let entry = game.world().entry_ref(entity).unwrap();
+    (
+        entry
+            .get_component::<ShipSystemsComponent>()
+            .unwrap()
+            .clone(),
+        entry.get_component::<HealthComponent>().unwrap().health,
+    )
+}
+
+#[test]
+fn shields_absorb_damage_before_hull() {
+    let mut game = Game::new();
+    let ship = spawn_ship(&mut game, ShipSystemsComponent::new(0.0, 10.0, 3.0));
+
+    hit(&mut game, 2.0);
+    let (systems, hull) = state(&game, ship);
+    assert_eq!(systems.shield, 1.0);
+    assert_eq!(hull, 10.0);
+
+    hit(&mut game, 2.0);
+    let (systems, hull) = state(&game, ship);
+    assert_eq!(systems.shield, 0.0);
+    assert_eq!(hull, 9.0);
+}
+
+#[test]
+fn shields_recharge_from_battery_after_delay() {
+    let mut game = Game::new();
+    let ship = spawn_ship(&mut game, ShipSystemsComponent::new(0.0, 4.0, 3.0));
+    hit(&mut game, 3.0);
+
+    // Nothing happens during the delay.
+    let delay = state(&game, ship).0.shield_recharge_delay;
+    let steps = (delay / 0.1) as usize - 1;
+    for _ in 0..steps {
+        game.step(0.1, Input::default());
+    }
+    let (systems, _) = state(&game, ship);
+    assert_eq!(systems.shield, 0.0);
+    assert_eq!(systems.battery, 4.0);
+
+    // With no reactor output, the battery runs dry partway through recharging.
+    for _ in 0..100 {
+        game.step(0.1, Input::default());
+    }
+    let (systems, _) = state(&game, ship);
+    assert!(systems.battery < 1e-3);
+    assert!((systems.shield - 4.0 / systems.shield_energy_cost).abs() < 1e-3);
+    assert!(systems.shield < systems.shield_capacity);
+}
+
+#[test]
+fn weapons_need_energy_to_fire() {
+    let mut game = Game::new();
+    let mut systems = ShipSystemsComponent::new(0.0, 10.0, 0.0);
+    let blaster = WeaponDef {
+        lifetime: 100.0,
+        ..WeaponDefs::builtin().get("blaster").unwrap().clone()
+    };
+    let shots = (systems.battery / blaster.energy) as usize;
+    systems.battery = shots as f32 * blaster.energy;
+    let shooter = spawn_ship(&mut game, systems);
+    game.world_mut()
+        .entry(shooter)
+        .unwrap()
+        .add_component(WeaponComponent::new(blaster, HitboxMask::PLAYER));
+
+    for _ in 0..shots + 5 {
+        game.world_mut()
+            .entry_mut(shooter)
+            .unwrap()
+            .get_component_mut::<WeaponComponent>()
+            .unwrap()
+            .trigger = Some(vec2(1.0, 0.0));
+        game.step(1.0, Input::default());
+    }
+
+    let fired = <&LifespanComponent>::query().iter(game.world()).count();
+    assert_eq!(fired, shots);
+}
+
+#[test]
+fn empty_systems_report_zero_fractions() {
+    let ship = ShipSystemsComponent::new(1.0, 0.0, 0.0);
+    assert_eq!(ship.battery_fraction(), 0.0);
+    assert_eq!(ship.shield_fraction(), 0.0);
+}
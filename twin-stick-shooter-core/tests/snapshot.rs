@@ -0,0 +1,125 @@
+use cgmath::vec2;
+use legion::{EntityStore, IntoQuery};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::inventory::Resource;
+use twin_stick_shooter_core::model::ModelComponent;
+use twin_stick_shooter_core::net::{EntitySnapshot, Snapshot};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::Pt2;
+
+#[test]
+fn restore_replaces_the_world() {
+    let mut game = Game::new();
+    spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let snapshot = Snapshot {
+        tick: 10,
+        entities: vec![
+            EntitySnapshot {
+                pos: Pt2::new(50.0, 0.0),
+                vel: Some(vec2(10.0, 0.0)),
+                model: "enemy".to_string(),
+                health: Some(2.0),
+            },
+            EntitySnapshot {
+                pos: Pt2::new(-20.0, 30.0),
+                vel: Some(vec2(0.0, 5.0)),
+                model: "player".to_string(),
+                health: Some(4.0),
+            },
+        ],
+        you: Some(1),
+    };
+
+    let you = snapshot.restore(&mut game).unwrap();
+    assert_eq!(<&ModelComponent>::query().iter(game.world()).count(), 2);
+    assert_eq!(<&PlayerComponent>::query().iter(game.world()).count(), 1);
+    let entry = game.world().entry_ref(you).unwrap();
+    assert_eq!(
+        entry.get_component::<PositionComponent>().unwrap().0,
+        Pt2::new(-20.0, 30.0)
+    );
+    assert_eq!(
+        entry.get_component::<VelocityComponent>().unwrap().0,
+        vec2(0.0, 5.0)
+    );
+    assert_eq!(
+        entry.get_component::<HealthComponent>().unwrap().health,
+        4.0
+    );
+}
+
+#[test]
+fn capture_then_restore_round_trips() {
+    let mut server = Game::new();
+    let player = spawn_player(
+        server.world_mut(),
+        &Prefabs::builtin(),
+        Pt2::new(30.0, 40.0),
+    );
+    server.world_mut().push((
+        PositionComponent(Pt2::new(-5.0, 5.0)),
+        ModelComponent {
+            name: "asteroid".to_string(),
+            transform: cgmath::SquareMatrix::identity(),
+        },
+    ));
+    let snapshot = Snapshot::capture(&server, Some(player));
+
+    let mut client = Game::new();
+    let you = snapshot.restore(&mut client).unwrap();
+    let restored = Snapshot::capture(&client, Some(you));
+    assert_eq!(restored.entities.len(), 2);
+    let you = restored.you().unwrap();
+    assert_eq!(you.pos, Pt2::new(30.0, 40.0));
+    assert_eq!(you.model, snapshot.you().unwrap().model);
+    assert!(restored
+        .entities
+        .iter()
+        .any(|entity| entity.model == "asteroid" && entity.vel.is_none()));
+}
+
+#[test]
+fn restore_keeps_the_local_players_ship() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    {
+        let mut entry = game.world_mut().entry(player).unwrap();
+        let component = entry.get_component_mut::<PlayerComponent>().unwrap();
+        component.inventory.add(Resource::Scrap, 25).unwrap();
+        component.lives = 2;
+    }
+    let snapshot = Snapshot {
+        tick: 10,
+        entities: vec![EntitySnapshot {
+            pos: Pt2::new(-20.0, 30.0),
+            vel: None,
+            model: "player".to_string(),
+            health: None,
+        }],
+        you: Some(0),
+    };
+
+    assert_eq!(snapshot.restore(&mut game), Some(player));
+    let entry = game.world().entry_ref(player).unwrap();
+    let component = entry.get_component::<PlayerComponent>().unwrap();
+    assert_eq!(component.inventory.count(Resource::Scrap), 25);
+    assert_eq!(component.lives, 2);
+    assert_eq!(
+        entry.get_component::<PositionComponent>().unwrap().0,
+        Pt2::new(-20.0, 30.0)
+    );
+}
+
+#[test]
+fn you_out_of_range_is_none() {
+    let snapshot = Snapshot {
+        tick: 0,
+        entities: vec![],
+        you: Some(3),
+    };
+    assert!(snapshot.you().is_none());
+}
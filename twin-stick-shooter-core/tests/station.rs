@@ -0,0 +1,174 @@
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::inventory::{InventoryError, Resource};
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent, PLAYER_MAX_HEALTH};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::ship::ShipSystemsComponent;
+use twin_stick_shooter_core::station::{
+    buy, equip, repair, repair_price, sell, Catalog, Price, StationError, SCRAP_PER_HULL_POINT,
+    SCRAP_PER_SHIELD_POINT,
+};
+use twin_stick_shooter_core::weapon::{WeaponComponent, WeaponDefs};
+use twin_stick_shooter_core::Pt2;
+
+fn docked_player(game: &mut Game) -> Entity {
+    let station = game
+        .world_mut()
+        .push((PositionComponent(Pt2::new(0.0, 0.0)),));
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    player_mut(game, player).docked_to = Some(station);
+    player
+}
+
+fn player_mut(game: &mut Game, player: Entity) -> &mut PlayerComponent {
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .into_component_mut::<PlayerComponent>()
+        .unwrap()
+}
+
+fn catalog() -> Catalog {
+    Catalog::from_json(
+        r#"[
+            { "item": "scatter_blaster", "name": "Scatter", "price": { "cores": 2, "scrap": 10 } },
+            { "item": "blaster", "name": "Blaster", "price": { "scrap": 4 } }
+        ]"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn repair_costs_scrap_per_missing_point() {
+    let mut game = Game::new();
+    let player = docked_player(&mut game);
+    {
+        let mut entry = game.world_mut().entry_mut(player).unwrap();
+        entry.get_component_mut::<HealthComponent>().unwrap().health = PLAYER_MAX_HEALTH - 2.0;
+        let ship = entry.get_component_mut::<ShipSystemsComponent>().unwrap();
+        ship.shield = ship.shield_capacity - 3.0;
+    }
+    let price = repair_price(game.world(), player);
+    assert_eq!(
+        price,
+        Price {
+            cores: 0,
+            scrap: 2 * SCRAP_PER_HULL_POINT + 3 * SCRAP_PER_SHIELD_POINT,
+        }
+    );
+
+    player_mut(&mut game, player)
+        .inventory
+        .add(Resource::Scrap, price.scrap - 1)
+        .unwrap();
+    assert_eq!(
+        repair(game.world_mut(), player),
+        Err(StationError::Inventory(InventoryError::NotEnough))
+    );
+
+    player_mut(&mut game, player)
+        .inventory
+        .add(Resource::Scrap, 1)
+        .unwrap();
+    assert_eq!(repair(game.world_mut(), player), Ok(price));
+    assert_eq!(
+        player_mut(&mut game, player)
+            .inventory
+            .count(Resource::Scrap),
+        0
+    );
+    let entry = game.world().entry_ref(player).unwrap();
+    assert_eq!(
+        entry.get_component::<HealthComponent>().unwrap().health,
+        PLAYER_MAX_HEALTH
+    );
+    assert_eq!(
+        repair(game.world_mut(), player),
+        Err(StationError::NothingToRepair)
+    );
+}
+
+#[test]
+fn buying_and_selling_validate_costs() {
+    let mut game = Game::new();
+    let player = docked_player(&mut game);
+    let catalog = catalog();
+    player_mut(&mut game, player)
+        .inventory
+        .add(Resource::Scrap, 12)
+        .unwrap();
+
+    assert_eq!(
+        buy(game.world_mut(), player, &catalog, "scatter_blaster"),
+        Err(StationError::Inventory(InventoryError::NotEnough))
+    );
+    assert_eq!(
+        buy(game.world_mut(), player, &catalog, "laser_sword"),
+        Err(StationError::NotForSale)
+    );
+    player_mut(&mut game, player)
+        .inventory
+        .add(Resource::Cores, 2)
+        .unwrap();
+    assert_eq!(
+        buy(game.world_mut(), player, &catalog, "scatter_blaster"),
+        Ok(())
+    );
+
+    let inventory = &player_mut(&mut game, player).inventory;
+    assert_eq!(inventory.item_count("scatter_blaster"), 1);
+    assert_eq!(inventory.count(Resource::Cores), 0);
+    assert_eq!(inventory.count(Resource::Scrap), 2);
+
+    assert_eq!(
+        sell(game.world_mut(), player, &catalog, "scatter_blaster"),
+        Ok(Price { cores: 1, scrap: 5 })
+    );
+    assert_eq!(
+        sell(game.world_mut(), player, &catalog, "scatter_blaster"),
+        Err(StationError::Inventory(InventoryError::NotEnough))
+    );
+    let inventory = &player_mut(&mut game, player).inventory;
+    assert_eq!(inventory.count(Resource::Cores), 1);
+    assert_eq!(inventory.count(Resource::Scrap), 7);
+
+    player_mut(&mut game, player).docked_to = None;
+    assert_eq!(
+        buy(game.world_mut(), player, &catalog, "blaster"),
+        Err(StationError::NotDocked)
+    );
+}
+
+#[test]
+fn equipping_swaps_the_main_weapon() {
+    let mut game = Game::new();
+    let player = docked_player(&mut game);
+    let weapon_defs = WeaponDefs::builtin();
+    player_mut(&mut game, player)
+        .inventory
+        .add_item("scatter_blaster", 1)
+        .unwrap();
+    // As if modules had boosted the old weapon.
+    {
+        let mut entry = game.world_mut().entry_mut(player).unwrap();
+        let weapon = entry.get_component_mut::<WeaponComponent>().unwrap();
+        weapon.cooldown_scale = 0.5;
+        weapon.damage_scale = 2.0;
+    }
+
+    assert_eq!(
+        equip(game.world_mut(), player, &weapon_defs, "scatter_blaster"),
+        Ok(())
+    );
+
+    let entry = game.world().entry_ref(player).unwrap();
+    let weapon = entry.get_component::<WeaponComponent>().unwrap();
+    assert_eq!(weapon.def.name, "scatter_blaster");
+    assert_eq!(weapon.cooldown_scale, 0.5);
+    assert_eq!(weapon.damage_scale, 2.0);
+    let inventory = &entry.get_component::<PlayerComponent>().unwrap().inventory;
+    assert_eq!(inventory.item_count("scatter_blaster"), 0);
+    assert_eq!(inventory.item_count("blaster"), 1);
+}
@@ -0,0 +1,140 @@
+use cgmath::vec2;
+use collision::dbvt::DynamicBoundingVolumeTree;
+use legion::{Entity, EntityStore, Resources, Schedule, World};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use twin_stick_shooter_core::bullet::RemoveOnHitComponent;
+use twin_stick_shooter_core::collision::{Circle, Rectangle};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{
+    hitbox_system, HitboxComponent, HitboxEffect, HitboxMask, HurtboxComponent, SweptComponent,
+};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::resource::{CollideCounters, Input};
+use twin_stick_shooter_core::Pt2;
+
+fn shot(effect: HitboxEffect) -> HitboxComponent {
+    HitboxComponent {
+        shape: Circle::new(5.0).into(),
+        dbvt_index: None,
+        mask: HitboxMask::TARGET,
+        effect,
+        hit_entities: vec![],
+    }
+}
+
+/// A wall one unit thick, standing across the x axis.
+fn wall(world: &mut World, x: f32) -> Entity {
+    world.push((
+        PositionComponent(Pt2::new(x, 0.0)),
+        HurtboxComponent {
+            shape: Rectangle::new(1.0, 100.0).into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            hit_by_entities: vec![],
+        },
+    ))
+}
+
+/// Runs collision detection before and after moving `shots` to `to`.
+fn sweep(world: &mut World, shots: &[Entity], to: Pt2) {
+    let mut resources = Resources::default();
+    resources.insert(Pcg32::from_rng(rand::thread_rng()).unwrap());
+    resources.insert(CollideCounters::default());
+    let mut schedule = Schedule::builder()
+        .add_system(hitbox_system(DynamicBoundingVolumeTree::new()))
+        .build();
+    schedule.execute(world, &mut resources);
+    for &shot in shots {
+        world
+            .entry(shot)
+            .unwrap()
+            .add_component(PositionComponent(to));
+    }
+    schedule.execute(world, &mut resources);
+}
+
+fn hit_entities(world: &World, entity: Entity) -> Vec<Entity> {
+    world
+        .entry_ref(entity)
+        .unwrap()
+        .get_component::<HitboxComponent>()
+        .unwrap()
+        .hit_entities
+        .clone()
+}
+
+#[test]
+fn thin_targets_are_hit_at_high_speed() {
+    let mut world = World::default();
+    let start = Pt2::new(0.0, 0.0);
+    let wall = wall(&mut world, 100.0);
+    let swept = world.push((
+        PositionComponent(start),
+        shot(HitboxEffect::None),
+        SweptComponent { prev_pos: start },
+    ));
+    let unswept = world.push((PositionComponent(start), shot(HitboxEffect::None)));
+
+    // Far enough in one step to land clear on the other side.
+    sweep(&mut world, &[swept, unswept], Pt2::new(300.0, 0.0));
+    assert_eq!(hit_entities(&world, swept), [wall]);
+    assert_eq!(hit_entities(&world, unswept), []);
+    let entry = world.entry_ref(wall).unwrap();
+    let hurtbox = entry.get_component::<HurtboxComponent>().unwrap();
+    assert_eq!(hurtbox.hit_by_entities, [swept]);
+}
+
+#[test]
+fn hits_are_listed_earliest_first() {
+    let mut world = World::default();
+    let start = Pt2::new(0.0, 0.0);
+    let far = wall(&mut world, 200.0);
+    let near = wall(&mut world, 100.0);
+    let behind = wall(&mut world, -100.0);
+    let swept = world.push((
+        PositionComponent(start),
+        shot(HitboxEffect::None),
+        SweptComponent { prev_pos: start },
+    ));
+
+    sweep(&mut world, &[swept], Pt2::new(300.0, 0.0));
+    assert_eq!(hit_entities(&world, swept), [near, far]);
+    assert!(!hit_entities(&world, swept).contains(&behind));
+}
+
+#[test]
+fn fast_shots_stop_at_the_first_target() {
+    let mut game = Game::new();
+    let far = wall(game.world_mut(), 200.0);
+    let near = wall(game.world_mut(), 100.0);
+    for &target in &[far, near] {
+        game.world_mut()
+            .entry(target)
+            .unwrap()
+            .add_component(HealthComponent::new(10.0));
+    }
+    let start = Pt2::new(0.0, 0.0);
+    let shot = game.world_mut().push((
+        PositionComponent(start),
+        VelocityComponent(vec2(6000.0, 0.0)),
+        shot(HitboxEffect::Damage(1.0)),
+        SweptComponent { prev_pos: start },
+        RemoveOnHitComponent,
+    ));
+
+    game.step(0.05, Input::default());
+    let health = |game: &Game, entity: Entity| {
+        game.world()
+            .entry_ref(entity)
+            .unwrap()
+            .get_component::<HealthComponent>()
+            .unwrap()
+            .health
+    };
+    assert_eq!(health(&game, near), 9.0);
+    assert_eq!(health(&game, far), 10.0);
+    assert!(game.world().entry_ref(shot).is_err());
+}
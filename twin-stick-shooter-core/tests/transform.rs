@@ -0,0 +1,102 @@
+use cgmath::num_traits::zero;
+use cgmath::{vec2, InnerSpace};
+use legion::{Entity, EntityStore};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::transform::{OrbitComponent, Orphan, ParentComponent};
+use twin_stick_shooter_core::{Pt2, Vec2};
+
+fn spawn(game: &mut Game, pos: Pt2, vel: Vec2) -> Entity {
+    game.world_mut()
+        .push((PositionComponent(pos), VelocityComponent(vel)))
+}
+
+/// Spawns a child in place.
+fn attach(game: &mut Game, parent: Entity, offset: Vec2, orphan: Orphan) -> Entity {
+    let (parent_pos, _) = pos_and_vel(game, parent);
+    let child = spawn(game, parent_pos + offset, zero());
+    game.world_mut()
+        .entry(child)
+        .unwrap()
+        .add_component(ParentComponent::new(parent, offset, orphan));
+    child
+}
+
+fn pos_and_vel(game: &Game, entity: Entity) -> (Pt2, Vec2) {
+    let entry = game.world().entry_ref(entity).unwrap();
+    (
+        entry.get_component::<PositionComponent>().unwrap().0,
+        entry.get_component::<VelocityComponent>().unwrap().0,
+    )
+}
+
+#[test]
+fn children_follow_their_parents_down_the_chain() {
+    let mut game = Game::new();
+    let parent = spawn(&mut game, Pt2::new(0.0, 0.0), vec2(100.0, 0.0));
+    // Created before its parent, which still has to be placed first.
+    let grandchild = spawn(&mut game, Pt2::new(10.0, 5.0), zero());
+    let child = attach(&mut game, parent, vec2(10.0, 0.0), Orphan::Destroy);
+    game.world_mut()
+        .entry(grandchild)
+        .unwrap()
+        .add_component(ParentComponent::new(child, vec2(0.0, 5.0), Orphan::Destroy));
+
+    for step in 1..=3 {
+        game.step(0.1, Input::default());
+        let x = 10.0 * step as f32;
+        assert_eq!(pos_and_vel(&game, parent).0, Pt2::new(x, 0.0));
+        let (pos, vel) = pos_and_vel(&game, child);
+        assert!((pos - Pt2::new(x + 10.0, 0.0)).magnitude() < 1e-3);
+        assert!((vel - vec2(100.0, 0.0)).magnitude() < 1e-2);
+        let (pos, _) = pos_and_vel(&game, grandchild);
+        assert!((pos - Pt2::new(x + 10.0, 5.0)).magnitude() < 1e-3);
+    }
+}
+
+#[test]
+fn orbiting_children_circle_their_parent() {
+    let mut game = Game::new();
+    let parent = spawn(&mut game, Pt2::new(0.0, 0.0), zero());
+    let drone = attach(&mut game, parent, vec2(10.0, 0.0), Orphan::Destroy);
+    game.world_mut()
+        .entry(drone)
+        .unwrap()
+        .add_component(OrbitComponent {
+            angular_speed: std::f32::consts::FRAC_PI_2,
+        });
+
+    for _ in 0..10 {
+        game.step(0.1, Input::default());
+    }
+    let (pos, vel) = pos_and_vel(&game, drone);
+    assert!((pos - Pt2::new(0.0, 10.0)).magnitude() < 1e-3, "{:?}", pos);
+    // Moving around the circle, not away from the parent.
+    assert!(vel.dot(vec2(-1.0, 0.0)) > 0.0);
+}
+
+#[test]
+fn orphans_are_removed_or_detached() {
+    let mut game = Game::new();
+    let parent = spawn(&mut game, Pt2::new(0.0, 0.0), vec2(100.0, 0.0));
+    let doomed = attach(&mut game, parent, vec2(10.0, 0.0), Orphan::Destroy);
+    let doomed_child = attach(&mut game, doomed, vec2(10.0, 0.0), Orphan::Detach);
+    let survivor = attach(&mut game, parent, vec2(-10.0, 0.0), Orphan::Detach);
+    game.step(0.1, Input::default());
+
+    game.world_mut().remove(parent);
+    game.step(0.1, Input::default());
+    assert!(game.world().entry_ref(doomed).is_err());
+    assert!(
+        game.world().entry_ref(doomed_child).is_ok(),
+        "detaches from a parent being removed"
+    );
+
+    let entry = game.world().entry_ref(survivor).unwrap();
+    assert!(entry.get_component::<ParentComponent>().is_err());
+    let (pos, vel) = pos_and_vel(&game, survivor);
+    assert!((pos - Pt2::new(10.0, 0.0)).magnitude() < 1e-3, "{:?}", pos);
+    assert!((vel - vec2(100.0, 0.0)).magnitude() < 1e-2);
+}
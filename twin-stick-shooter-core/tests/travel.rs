@@ -0,0 +1,221 @@
+use cgmath::vec2;
+use legion::{EntityStore, IntoQuery};
+use twin_stick_shooter_core::enemy::EnemyComponent;
+use twin_stick_shooter_core::game::{Game, IDLE_SECTOR_STEP_INTERVAL};
+use twin_stick_shooter_core::net::{SessionTable, Snapshot, SESSION_GRACE_SECONDS};
+use twin_stick_shooter_core::player::{spawn_player, InputComponent, PlayerComponent};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::{Instance, Prefabs};
+use twin_stick_shooter_core::resource::{GameEvent, Input, Time};
+use twin_stick_shooter_core::sector::{SectorError, Sectors};
+use twin_stick_shooter_core::transform::{Orphan, ParentComponent};
+use twin_stick_shooter_core::Pt2;
+
+/// The player starts on a gate to "b", which is empty but for a gate back.
+const SECTORS_JSON: &str = r#"{
+    "a": {
+        "name": "A",
+        "boundary": 1000.0,
+        "gates": [{ "position": [0.0, 0.0], "destination": "b", "arrival": [100.0, 0.0] }],
+        "enemies": [{ "kind": "Sniper", "center": [900.0, 0.0], "radius": 10.0 }]
+    },
+    "b": {
+        "name": "B",
+        "boundary": 1000.0,
+        "gates": [{ "position": [300.0, 0.0], "destination": "a" }]
+    }
+}"#;
+
+/// Steps until a player enters another sector, returning every event seen along the way.
+fn step_until_jumped(game: &mut Game) -> Vec<GameEvent> {
+    let mut events = vec![];
+    for _ in 0..50 {
+        game.step(0.1, Input::default());
+        events.extend(game.game_event_queue().drain());
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::SectorEntered { .. }))
+        {
+            return events;
+        }
+    }
+    panic!("never jumped; saw {:?}", events);
+}
+
+fn enemy_positions(game: &Game, sector: &str) -> Vec<Pt2> {
+    <(&PositionComponent, &EnemyComponent)>::query()
+        .iter(game.sector_world(sector).unwrap())
+        .map(|(pos, _)| pos.0)
+        .collect()
+}
+
+#[test]
+fn gates_carry_players_between_sectors() {
+    let sectors = Sectors::from_json(SECTORS_JSON).unwrap();
+    let mut game = Game::new();
+    let player = sectors.get("a").unwrap().populate(&mut game);
+    game.set_sector_defs(sectors);
+
+    let events = step_until_jumped(&mut game);
+    let started = events.iter().position(|event| {
+        matches!(event, GameEvent::JumpStarted { player: p, sector } if *p == player && sector == "b")
+    });
+    let entered = events.iter().position(|event| {
+        matches!(event, GameEvent::SectorEntered { player: p, sector } if *p == player && sector == "b")
+    });
+    assert!(started.unwrap() < entered.unwrap());
+
+    assert_eq!(game.current_sector(), "b");
+    let entry = game.world().entry_ref(player).unwrap();
+    assert_eq!(
+        entry.get_component::<PositionComponent>().unwrap().0,
+        Pt2::new(100.0, 0.0)
+    );
+    let player_component = entry.get_component::<PlayerComponent>().unwrap();
+    assert!(player_component.jumping.is_none());
+    assert_eq!(player_component.spawn_pos, Pt2::new(100.0, 0.0));
+
+    // The sector left behind keeps everything but the player.
+    let left = game.sector_world("a").unwrap();
+    assert!(left.entry_ref(player).is_err());
+    assert_eq!(enemy_positions(&game, "a").len(), 1);
+}
+
+#[test]
+fn players_are_found_in_whichever_sector_they_jumped_to() {
+    let sectors = Sectors::from_json(SECTORS_JSON).unwrap();
+    let mut game = Game::new();
+    let jumper = sectors.get("a").unwrap().populate(&mut game);
+    game.set_sector_defs(sectors);
+    // Someone staying behind keeps the view in "a".
+    let stayer = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(-500.0, 0.0));
+    let mut sessions = SessionTable::new();
+    let token = sessions.create(game.rng_mut(), jumper);
+
+    step_until_jumped(&mut game);
+    assert_eq!(game.current_sector(), "a");
+    assert_eq!(game.sector_of(jumper), Some("b"));
+    assert_eq!(game.sector_of(stayer), Some("a"));
+
+    // Steer the session's ship where it landed.
+    let player = sessions.player(&token).unwrap();
+    game.world_of_mut(player)
+        .unwrap()
+        .entry(player)
+        .unwrap()
+        .add_component(InputComponent(Input {
+            move_: vec2(1.0, 0.0),
+            ..Input::default()
+        }));
+    game.step(0.1, Input::default());
+    let pos = game
+        .world_of(player)
+        .unwrap()
+        .entry_ref(player)
+        .unwrap()
+        .get_component::<PositionComponent>()
+        .unwrap()
+        .0;
+    assert!(pos.x > 100.0);
+    assert!(Snapshot::capture(&game, Some(player)).you().is_some());
+
+    // Let the session expire and remove its ship from there too.
+    sessions.disconnect(&token);
+    let expired = sessions.step(&Time {
+        elapsed_seconds: SESSION_GRACE_SECONDS + 1.0,
+    });
+    assert_eq!(expired.len(), 1);
+    game.world_of_mut(expired[0].player)
+        .unwrap()
+        .remove(expired[0].player);
+    assert_eq!(game.sector_of(jumper), None);
+    assert!(game.sector_world("b").unwrap().entry_ref(jumper).is_err());
+}
+
+#[test]
+fn children_travel_with_their_parent() {
+    let sectors = Sectors::from_json(SECTORS_JSON).unwrap();
+    let mut game = Game::new();
+    let player = sectors.get("a").unwrap().populate(&mut game);
+    game.set_sector_defs(sectors);
+    let prefabs = Prefabs::builtin();
+    let child = prefabs
+        .spawn(
+            "engine",
+            game.world_mut(),
+            &Instance::at(Pt2::new(0.0, 30.0)),
+        )
+        .unwrap();
+    let grandchild = prefabs
+        .spawn(
+            "engine",
+            game.world_mut(),
+            &Instance::at(Pt2::new(0.0, 60.0)),
+        )
+        .unwrap();
+    for (entity, parent) in [(child, player), (grandchild, child)] {
+        game.world_mut()
+            .entry(entity)
+            .unwrap()
+            .add_component(ParentComponent::new(
+                parent,
+                vec2(0.0, 30.0),
+                Orphan::Destroy,
+            ));
+    }
+
+    step_until_jumped(&mut game);
+    game.step(0.1, Input::default());
+    assert_eq!(game.sector_of(child), Some("b"));
+    assert_eq!(game.sector_of(grandchild), Some("b"));
+    let pos = game
+        .world()
+        .entry_ref(grandchild)
+        .unwrap()
+        .get_component::<PositionComponent>()
+        .unwrap()
+        .0;
+    assert_eq!(pos, Pt2::new(100.0, 60.0));
+}
+
+#[test]
+fn empty_sectors_run_at_a_reduced_rate() {
+    let sectors = Sectors::from_json(SECTORS_JSON).unwrap();
+    let mut game = Game::new();
+    sectors.get("a").unwrap().populate(&mut game);
+    game.set_sector_defs(sectors);
+    step_until_jumped(&mut game);
+
+    // Find a step that moves the enemies left behind, then count the steps to the next one.
+    let mut before = enemy_positions(&game, "a");
+    let mut moved_at = vec![];
+    for step in 0..3 * IDLE_SECTOR_STEP_INTERVAL {
+        game.step(0.01, Input::default());
+        let after = enemy_positions(&game, "a");
+        if after != before {
+            moved_at.push(step);
+        }
+        before = after;
+    }
+    assert_eq!(moved_at.len(), 3);
+    assert_eq!(moved_at[1] - moved_at[0], IDLE_SECTOR_STEP_INTERVAL);
+    assert_eq!(moved_at[2] - moved_at[1], IDLE_SECTOR_STEP_INTERVAL);
+}
+
+#[test]
+fn gates_must_lead_somewhere() {
+    let json = r#"{
+        "a": {
+            "name": "A",
+            "boundary": 1000.0,
+            "gates": [{ "position": [0.0, 0.0], "destination": "nowhere" }]
+        }
+    }"#;
+    assert_eq!(
+        Sectors::from_json(json).unwrap_err(),
+        SectorError::Invalid {
+            path: "a.gates[0].destination".to_string(),
+            message: "no sector named \"nowhere\"".to_string(),
+        }
+    );
+}
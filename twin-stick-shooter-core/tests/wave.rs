@@ -0,0 +1,128 @@
+use cgmath::{EuclideanSpace, InnerSpace};
+use legion::{Entity, EntityStore, IntoQuery};
+use twin_stick_shooter_core::enemy::{EnemyComponent, EnemyKind};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::player::{spawn_player, PlayerComponent};
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::prefab::Prefabs;
+use twin_stick_shooter_core::resource::{GameEvent, GuiOverride, Input};
+use twin_stick_shooter_core::wave::{SpawnerComponent, WavePhase, WavePlan};
+use twin_stick_shooter_core::Pt2;
+
+fn quick_plan() -> WavePlan {
+    WavePlan {
+        waves: 2,
+        first_wave_size: 3,
+        breather_seconds: 0.1,
+        spawn_interval: 0.1,
+        ..WavePlan::default()
+    }
+}
+
+/// Steps until an event matching `pred` comes out, returning every event seen along the way.
+fn step_until(game: &mut Game, pred: impl Fn(&GameEvent) -> bool) -> Vec<GameEvent> {
+    let mut events = vec![];
+    for _ in 0..1000 {
+        game.step(0.01, Input::default());
+        events.extend(game.game_event_queue().drain());
+        if events.iter().any(&pred) {
+            return events;
+        }
+    }
+    panic!("no matching event in {:?}", events);
+}
+
+fn enemies(game: &Game) -> Vec<(Entity, Pt2)> {
+    <(Entity, &PositionComponent, &EnemyComponent)>::query()
+        .iter(game.world())
+        .map(|(&entity, &PositionComponent(pos), _)| (entity, pos))
+        .collect()
+}
+
+#[test]
+fn waves_escalate() {
+    let plan = WavePlan::default();
+    let chargers = |enemies: &[EnemyKind]| {
+        enemies
+            .iter()
+            .filter(|&&kind| kind == EnemyKind::Charger)
+            .count()
+    };
+
+    let first = plan.composition(1);
+    let fifth = plan.composition(5);
+    let last = plan.composition(plan.waves);
+    assert_eq!(first.len(), plan.first_wave_size as usize);
+    assert!(fifth.len() > first.len());
+    assert!(last.len() > fifth.len());
+    assert!(
+        chargers(&fifth) as f32 / fifth.len() as f32 > chargers(&first) as f32 / first.len() as f32
+    );
+    assert!(chargers(&last) as f32 / last.len() as f32 <= plan.max_charger_share + 0.05);
+    assert!(plan.spawners(plan.waves) > plan.spawners(1));
+}
+
+#[test]
+fn clearing_every_wave_wins() {
+    let mut game = Game::new();
+    spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    let plan = quick_plan();
+    let spawn_radius = plan.spawn_radius;
+    game.wave_director_mut().start(plan);
+
+    for wave in 1..=2 {
+        let events = step_until(&mut game, |event| *event == GameEvent::WaveStarted { wave });
+        assert!(!events.contains(&GameEvent::WavesWon));
+        game.step(0.01, Input::default());
+        for (_, &PositionComponent(pos)) in
+            <(&SpawnerComponent, &PositionComponent)>::query().iter(game.world())
+        {
+            assert!((pos.to_vec().magnitude() - spawn_radius).abs() < 1.0);
+        }
+
+        // Wait for the spawners to empty, then destroy everything they released.
+        while <&SpawnerComponent>::query().iter(game.world()).count() > 0 {
+            game.step(0.01, Input::default());
+        }
+        let spawned = enemies(&game);
+        assert_eq!(
+            spawned.len(),
+            game.wave_director().plan.composition(wave).len()
+        );
+        for (entity, _) in spawned {
+            game.world_mut().remove(entity);
+        }
+
+        let events = step_until(&mut game, |event| *event == GameEvent::WaveCleared { wave });
+        assert_eq!(events.contains(&GameEvent::WavesWon), wave == 2);
+    }
+
+    assert!(matches!(game.wave_director().phase(), WavePhase::Won));
+    assert!(game
+        .gui_override_queue()
+        .drain()
+        .iter()
+        .any(|gui_override| matches!(gui_override, GuiOverride::Victory)));
+}
+
+#[test]
+fn running_out_of_ships_loses() {
+    let mut game = Game::new();
+    let player = spawn_player(game.world_mut(), &Prefabs::builtin(), Pt2::new(0.0, 0.0));
+    game.wave_director_mut().start(quick_plan());
+    step_until(&mut game, |event| {
+        *event == GameEvent::WaveStarted { wave: 1 }
+    });
+
+    game.world_mut()
+        .entry_mut(player)
+        .unwrap()
+        .get_component_mut::<PlayerComponent>()
+        .unwrap()
+        .lives = 0;
+    let events = step_until(&mut game, |event| {
+        matches!(event, GameEvent::WavesLost { .. })
+    });
+    assert!(events.contains(&GameEvent::WavesLost { wave: 1 }));
+    assert!(matches!(game.wave_director().phase(), WavePhase::Lost));
+}
@@ -0,0 +1,263 @@
+use cgmath::num_traits::zero;
+use cgmath::{vec2, InnerSpace};
+use legion::{Entity, EntityStore, IntoQuery};
+use twin_stick_shooter_core::bullet::LifespanComponent;
+use twin_stick_shooter_core::collision::{Circle, ShapeDef, ShapeError};
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::health::HealthComponent;
+use twin_stick_shooter_core::hitbox::{HitboxMask, HurtboxComponent};
+use twin_stick_shooter_core::physics::VelocityComponent;
+use twin_stick_shooter_core::position::PositionComponent;
+use twin_stick_shooter_core::resource::Input;
+use twin_stick_shooter_core::weapon::{
+    ChargeDef, WeaponComponent, WeaponDef, WeaponDefError, WeaponDefs,
+};
+use twin_stick_shooter_core::Pt2;
+
+fn def(projectile_count: u32, spread: f32) -> WeaponDef {
+    WeaponDef {
+        name: "test".to_string(),
+        cooldown: 0.5,
+        projectile_count,
+        spread,
+        muzzle_offset: 10.0,
+        speed: 100.0,
+        lifetime: 1.0,
+        damage: 1.0,
+        shape: ShapeDef::Circle { radius: 1.0 },
+        model: "test".to_string(),
+        pierce: false,
+        swept: false,
+        charge: None,
+        energy: 0.0,
+    }
+}
+
+fn charged_def() -> WeaponDef {
+    WeaponDef {
+        pierce: true,
+        charge: Some(ChargeDef {
+            seconds: 1.0,
+            min_seconds: 0.25,
+            full_size: 2.0,
+            full_damage: 3.0,
+            full_speed: 0.5,
+        }),
+        ..def(1, 0.0)
+    }
+}
+
+fn spawn_target(game: &mut Game, pos: Pt2) -> Entity {
+    game.world_mut().push((
+        PositionComponent(pos),
+        HurtboxComponent {
+            shape: Circle { radius: 20.0 }.into(),
+            dbvt_index: None,
+            mask: HitboxMask::TARGET,
+            hit_by_entities: vec![],
+        },
+        HealthComponent::new(100.0),
+    ))
+}
+
+fn health(game: &Game, entity: Entity) -> f32 {
+    game.world()
+        .entry_ref(entity)
+        .unwrap()
+        .into_component::<HealthComponent>()
+        .unwrap()
+        .health
+}
+
+fn pull_trigger(game: &mut Game, entity: Entity) {
+    let mut entry = game.world_mut().entry(entity).unwrap();
+    entry
+        .get_component_mut::<WeaponComponent>()
+        .unwrap()
+        .trigger = Some(vec2(1.0, 0.0));
+}
+
+fn projectile_count(game: &Game) -> usize {
+    <&LifespanComponent>::query().iter(game.world()).count()
+}
+
+#[test]
+fn builtin_weapons_parse() {
+    let defs = WeaponDefs::builtin();
+    let blaster = defs.get("blaster").unwrap();
+    assert_eq!(blaster.cooldown, 0.1);
+    assert_eq!(blaster.projectile_count, 1);
+    assert!(defs.get("sniper").is_some());
+    assert!(defs.get("shotgun").is_some());
+    assert!(defs.get("no such weapon").is_none());
+}
+
+#[test]
+fn weapons_with_bad_shapes_dont_load() {
+    let json = r#"{
+        "dart": {
+            "cooldown": 1.0,
+            "projectile_count": 1,
+            "spread": 0.0,
+            "muzzle_offset": 10.0,
+            "speed": 100.0,
+            "lifetime": 1.0,
+            "damage": 1.0,
+            "shape": { "type": "Polygon", "vertices": [[0.0, 0.0], [1.0, 0.0]] },
+            "model": "shots/lemon"
+        }
+    }"#;
+    assert_eq!(
+        WeaponDefs::from_json(json).unwrap_err(),
+        WeaponDefError::BadShape {
+            weapon: "dart".to_string(),
+            error: ShapeError::TooFewVertices(2),
+        }
+    );
+}
+
+#[test]
+fn spread_fans_around_aim() {
+    let dirs = def(3, 1.0).directions(vec2(1.0, 0.0));
+    assert_eq!(dirs.len(), 3);
+    assert!((dirs[1] - vec2(1.0, 0.0)).magnitude() < 1e-6);
+    assert!((dirs[0].y + dirs[2].y).abs() < 1e-6);
+    assert!((dirs[0].angle(dirs[2]).0.abs() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn full_turn_spread_makes_a_ring() {
+    let dirs = def(4, std::f32::consts::TAU).directions(vec2(1.0, 0.0));
+    assert_eq!(dirs.len(), 4);
+    // A quarter turn apart, with none doubled up.
+    for i in 0..4 {
+        let angle = dirs[i].angle(dirs[(i + 1) % 4]).0;
+        assert!(
+            (angle - std::f32::consts::FRAC_PI_2).abs() < 1e-3,
+            "{}",
+            angle
+        );
+    }
+}
+
+#[test]
+fn new_weapon_waits_a_cooldown_before_firing() {
+    let mut game = Game::new();
+    let shooter = game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        VelocityComponent(zero()),
+        WeaponComponent::new(def(1, 0.0), HitboxMask::TARGET),
+    ));
+
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 0);
+
+    for _ in 0..4 {
+        game.step(0.1, Input::default());
+    }
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 1);
+}
+
+#[test]
+fn weapon_fires_volleys_at_its_cadence() {
+    let mut game = Game::new();
+    let shooter = game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        VelocityComponent(zero()),
+        WeaponComponent::new(def(3, 0.5), HitboxMask::TARGET).with_cooldown(0.0),
+    ));
+
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 3);
+
+    // Still cooling down.
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 3);
+
+    // The trigger only holds for one step.
+    for _ in 0..5 {
+        game.step(0.1, Input::default());
+    }
+    assert_eq!(projectile_count(&game), 3);
+
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 6);
+}
+
+#[test]
+fn charged_weapon_fires_on_release() {
+    let mut game = Game::new();
+    let shooter = game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        VelocityComponent(zero()),
+        WeaponComponent::new(charged_def(), HitboxMask::TARGET).with_cooldown(0.0),
+    ));
+    let weapon = |game: &Game| {
+        game.world()
+            .entry_ref(shooter)
+            .unwrap()
+            .into_component::<WeaponComponent>()
+            .unwrap()
+            .clone()
+    };
+
+    // A tap too short to reach the minimum charge fizzles.
+    pull_trigger(&mut game, shooter);
+    game.step(0.1, Input::default());
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 0);
+    assert_eq!(weapon(&game).charge_fraction(), 0.0);
+
+    // Holding builds charge, capped at full.
+    for _ in 0..20 {
+        pull_trigger(&mut game, shooter);
+        game.step(0.1, Input::default());
+        assert_eq!(projectile_count(&game), 0);
+    }
+    assert_eq!(weapon(&game).charge_fraction(), 1.0);
+
+    // Releasing fires one full-strength shot.
+    game.step(0.1, Input::default());
+    assert_eq!(projectile_count(&game), 1);
+    assert_eq!(weapon(&game).charge_fraction(), 0.0);
+    let (&VelocityComponent(vel), _) = <(&VelocityComponent, &LifespanComponent)>::query()
+        .iter(game.world())
+        .next()
+        .unwrap();
+    assert!((vel.magnitude() - 50.0).abs() < 1e-3);
+}
+
+#[test]
+fn piercing_shot_hits_each_target_once() {
+    let mut game = Game::new();
+    let mut weapon = WeaponComponent::new(
+        WeaponDef {
+            charge: None,
+            speed: 500.0,
+            ..charged_def()
+        },
+        HitboxMask::TARGET,
+    )
+    .with_cooldown(0.0);
+    weapon.trigger = Some(vec2(1.0, 0.0));
+    game.world_mut().push((
+        PositionComponent(Pt2::new(0.0, 0.0)),
+        VelocityComponent(zero()),
+        weapon,
+    ));
+    let near = spawn_target(&mut game, Pt2::new(60.0, 0.0));
+    let far = spawn_target(&mut game, Pt2::new(120.0, 0.0));
+
+    for _ in 0..50 {
+        game.step(0.01, Input::default());
+    }
+
+    assert_eq!(health(&game, near), 99.0);
+    assert_eq!(health(&game, far), 99.0);
+}
@@ -12,7 +12,8 @@ use sha1::{Digest, Sha1};
 use std::ffi::OsStr;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 use structopt::StructOpt;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
@@ -20,8 +21,14 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 use tokio_compat_02::{FutureExt, IoCompat};
 use tokio_tungstenite::tungstenite::protocol::Role;
+use tungstenite::Message;
+use twin_stick_shooter_core::net::{ClientMessage, ServerMessage};
 use webrtc_unreliable::{MessageResult, MessageType, SessionEndpoint};
 
+mod room;
+
+use room::{Room, RoomClock, RoomCommand};
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "twin-stick-shooter-server")]
 struct Opt {
@@ -49,10 +56,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let session_endpoint = webrtc_server.session_endpoint();
     tokio::spawn(handle_webrtc_sessions(webrtc_server));
 
+    let (room_sender, room_receiver) = mpsc::channel();
+    let room_clock = Arc::new(RoomClock::new());
+    {
+        let room_clock = Arc::clone(&room_clock);
+        std::thread::spawn(move || Room::run(room_receiver, &room_clock));
+    }
+
     let listener = TcpListener::bind(&opt.http_listen_addr).await?;
     let shared_state = Arc::new(SharedState {
         opt,
         webrtc_session_endpoint: Mutex::new(session_endpoint),
+        room_clock,
+        room: std::sync::Mutex::new(room_sender),
+        next_connection_id: AtomicU64::new(0),
     });
     loop {
         let (stream, _) = listener.accept().await?;
@@ -92,6 +109,9 @@ async fn handle_webrtc_sessions(mut webrtc_server: webrtc_unreliable::Server) {
 struct SharedState {
     opt: Opt,
     webrtc_session_endpoint: Mutex<SessionEndpoint>,
+    room_clock: Arc<RoomClock>,
+    room: std::sync::Mutex<mpsc::Sender<RoomCommand>>,
+    next_connection_id: AtomicU64,
 }
 
 async fn handle_http_connection(shared_state: Arc<SharedState>, stream: TcpStream) {
@@ -116,7 +136,7 @@ async fn handle_http_request(
     if req.uri().path() == "/special" {
         Ok(Response::new("you have reached the special URL".into()))
     } else if req.uri().path() == "/websocket" {
-        upgrade_http_request_to_websocket(req).await
+        upgrade_http_request_to_websocket(shared_state, req).await
     } else if req.uri().path() == "/webrtc-offer" {
         handle_webrtc_offer(&shared_state, req).await
     } else {
@@ -141,6 +161,7 @@ async fn handle_webrtc_offer(
 }
 
 async fn upgrade_http_request_to_websocket(
+    shared_state: Arc<SharedState>,
     req: Request<Body>,
 ) -> Result<Response<Body>, hyper::http::Error> {
     const WEBSOCKET_HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
@@ -164,7 +185,7 @@ async fn upgrade_http_request_to_websocket(
     tokio::spawn(async move {
         match req.into_body().on_upgrade().await {
             Ok(upgraded) => {
-                if let Err(e) = handle_websocket(upgraded).await {
+                if let Err(e) = handle_websocket(shared_state, upgraded).await {
                     eprintln!("websocket handling error: {:?}", e);
                 }
             }
@@ -184,19 +205,75 @@ async fn upgrade_http_request_to_websocket(
     Ok(resp)
 }
 
-async fn handle_websocket(upgraded: Upgraded) -> anyhow::Result<()> {
+async fn handle_websocket(
+    shared_state: Arc<SharedState>,
+    upgraded: Upgraded,
+) -> anyhow::Result<()> {
     let ws = tokio_tungstenite::WebSocketStream::from_raw_socket(
         IoCompat::new(upgraded),
         Role::Server,
         None,
     )
     .await;
+    let connection_id = shared_state
+        .next_connection_id
+        .fetch_add(1, Ordering::Relaxed);
+    let room = shared_state.room.lock().unwrap().clone();
+
+    let result = serve_websocket(&shared_state, &room, connection_id, ws).await;
+
+    // Whatever ended the connection, the room should start this session's grace period. A send
+    // error only means the room is shutting down.
+    let _ = room.send(RoomCommand::Leave { connection_id });
+    result
+}
+
+async fn serve_websocket(
+    shared_state: &SharedState,
+    room: &mpsc::Sender<RoomCommand>,
+    connection_id: room::ConnectionId,
+    ws: tokio_tungstenite::WebSocketStream<IoCompat<Upgraded>>,
+) -> anyhow::Result<()> {
     let (mut sink, mut stream) = ws.split();
-    while let Some(msg) = stream.next().await {
-        if let Err(tungstenite::error::Error::ConnectionClosed) = msg {
-            break;
-        }
-        match sink.send(msg?).await {
+    let (outbox_sender, mut outbox) = tokio::sync::mpsc::unbounded_channel();
+    loop {
+        let reply = tokio::select! {
+            msg = stream.next() => match msg {
+                None | Some(Err(tungstenite::error::Error::ConnectionClosed)) => break,
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(Message::Close(_))) => break,
+                Some(Ok(Message::Text(text))) => match ClientMessage::from_json(&text) {
+                    Ok(ClientMessage::Hello { resume_token }) => {
+                        room.send(RoomCommand::Join {
+                            connection_id,
+                            resume_token,
+                            outbox: outbox_sender.clone(),
+                        })?;
+                        continue;
+                    }
+                    Ok(ClientMessage::Ping { client_time }) => ServerMessage::Pong {
+                        client_time,
+                        server_time: shared_state.room_clock.server_time(),
+                    },
+                    Ok(ClientMessage::Chat { text }) => {
+                        room.send(RoomCommand::Chat { connection_id, text })?;
+                        continue;
+                    }
+                    Ok(ClientMessage::Input(input)) => {
+                        room.send(RoomCommand::Input { connection_id, input })?;
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("malformed client message: {}", e);
+                        continue;
+                    }
+                },
+                Some(Ok(_)) => continue,
+            },
+            // The outbox never closes because this task holds a sender.
+            Some(message) = outbox.recv() => message,
+        };
+        match sink.send(Message::Text(reply.to_json())).await {
             Ok(()) => (),
             Err(tungstenite::error::Error::ConnectionClosed) => break,
             Err(e) => return Err(e.into()),
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use twin_stick_shooter_core::game::Game;
+use twin_stick_shooter_core::net::{
+    validate_chat_text, ChatMessage, ChatRateLimiter, ChatSender, ServerMessage, SessionTable,
+    Snapshot, TICKS_PER_SECOND,
+};
+use twin_stick_shooter_core::player::{spawn_player, InputComponent};
+use twin_stick_shooter_core::resource::{GameEvent, Input, Time};
+use twin_stick_shooter_core::Pt2;
+
+pub type ConnectionId = u64;
+
+/// Requests from websocket connection tasks to the room thread.
+#[derive(Debug)]
+pub enum RoomCommand {
+    Join {
+        connection_id: ConnectionId,
+        resume_token: Option<String>,
+        outbox: UnboundedSender<ServerMessage>,
+    },
+    Leave {
+        connection_id: ConnectionId,
+    },
+    Chat {
+        connection_id: ConnectionId,
+        text: String,
+    },
+    Input {
+        connection_id: ConnectionId,
+        input: Input,
+    },
+}
+
+/// How far the room has got through its ticks, for connection tasks to answer pings with.
+#[derive(Debug)]
+pub struct RoomClock {
+    /// The number of ticks simulated, and when the last one finished.
+    last_tick: Mutex<(u64, Instant)>,
+}
+
+impl RoomClock {
+    pub fn new() -> RoomClock {
+        RoomClock {
+            last_tick: Mutex::new((0, Instant::now())),
+        }
+    }
+
+    /// The server's time in seconds, counted in ticks so that it falls behind the wall clock
+    /// along with the room when ticks are dropped.
+    pub fn server_time(&self) -> f64 {
+        let (tick, at) = *self.last_tick.lock().unwrap();
+        let tick_seconds = 1.0 / TICKS_PER_SECOND;
+        // Partway to the next tick, but never past it.
+        tick as f64 * tick_seconds + at.elapsed().as_secs_f64().min(tick_seconds)
+    }
+
+    fn record(&self, tick: u64) {
+        *self.last_tick.lock().unwrap() = (tick, Instant::now());
+    }
+}
+
+struct Connection {
+    session_token: String,
+    outbox: UnboundedSender<ServerMessage>,
+}
+
+/// The authoritative game shared by every connected client.
+///
+/// `Game` isn't `Send`, so the room lives on its own thread and talks to connection tasks through
+/// channels.
+pub struct Room {
+    game: Game,
+    sessions: SessionTable,
+    connections: HashMap<ConnectionId, Connection>,
+
+    /// Chat allowance per session token. Keyed by session so reconnecting doesn't reset it.
+    chat_limiters: HashMap<String, ChatRateLimiter>,
+}
+
+impl Room {
+    fn new() -> Room {
+        Room {
+            game: Game::new(),
+            sessions: SessionTable::new(),
+            connections: HashMap::new(),
+            chat_limiters: HashMap::new(),
+        }
+    }
+
+    /// Runs the room at the fixed tick rate until every command sender is dropped.
+    pub fn run(commands: Receiver<RoomCommand>, clock: &RoomClock) {
+        let mut room = Room::new();
+        let tick_duration = Duration::from_secs_f64(1.0 / TICKS_PER_SECOND);
+        let mut next_tick = Instant::now();
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(command) => room.handle_command(command),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+
+            room.step(tick_duration.as_secs_f32());
+            clock.record(room.game.tick());
+
+            next_tick += tick_duration;
+            let now = Instant::now();
+            if next_tick > now {
+                std::thread::sleep(next_tick - now);
+            } else {
+                // Running behind. Drop the missed ticks rather than trying to catch up.
+                next_tick = now;
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: RoomCommand) {
+        match command {
+            RoomCommand::Join {
+                connection_id,
+                resume_token,
+                outbox,
+            } => {
+                // Each connection gets one player. Another hello would strand the first ship.
+                if self.connections.contains_key(&connection_id) {
+                    eprintln!("ignoring repeated hello from connection {}", connection_id);
+                    return;
+                }
+                let resumed = resume_token.and_then(|token| {
+                    let player = self.sessions.resume(&token)?;
+                    Some((token, player))
+                });
+                let (session_token, player, resumed) = match resumed {
+                    Some((token, player)) => {
+                        // Detach any stale connection the server hasn't noticed dropping yet.
+                        self.connections
+                            .retain(|_, connection| connection.session_token != token);
+                        (token, player, true)
+                    }
+                    None => {
+                        let player = {
+                            let (prefabs, world) = self.game.prefabs_and_world_mut();
+                            spawn_player(world, &prefabs, Pt2::new(0.0, 0.0))
+                        };
+                        let token = self.sessions.create(self.game.rng_mut(), player);
+                        (token, player, false)
+                    }
+                };
+
+                // A failed send means the connection already closed. Its `Leave` will follow.
+                let _ = outbox.send(ServerMessage::Welcome {
+                    session_token: session_token.clone(),
+                    resumed,
+                });
+                let _ = outbox.send(ServerMessage::Snapshot(Snapshot::capture(
+                    &self.game,
+                    Some(player),
+                )));
+
+                let name = self.sessions.name(&session_token).unwrap().to_string();
+                self.connections.insert(
+                    connection_id,
+                    Connection {
+                        session_token,
+                        outbox,
+                    },
+                );
+                self.broadcast(ServerMessage::Chat(ChatMessage::system(if resumed {
+                    format!("{} reconnected", name)
+                } else {
+                    format!("{} joined", name)
+                })));
+            }
+            RoomCommand::Leave { connection_id } => {
+                if let Some(connection) = self.connections.remove(&connection_id) {
+                    // Let go of the controls so the ship doesn't fly on unattended.
+                    self.set_input(&connection.session_token, Input::default());
+                    self.sessions.disconnect(&connection.session_token);
+                    if let Some(name) = self.sessions.name(&connection.session_token) {
+                        let text = format!("{} lost connection", name);
+                        self.broadcast(ServerMessage::Chat(ChatMessage::system(text)));
+                    }
+                }
+            }
+            RoomCommand::Chat {
+                connection_id,
+                text,
+            } => self.handle_chat(connection_id, &text),
+            RoomCommand::Input {
+                connection_id,
+                input,
+            } => {
+                // Ignore input from connections that haven't said hello yet.
+                if let Some(connection) = self.connections.get(&connection_id) {
+                    let token = connection.session_token.clone();
+                    self.set_input(&token, input);
+                }
+            }
+        }
+    }
+
+    /// Drives the session's player with `input` until it changes.
+    fn set_input(&mut self, session_token: &str, input: Input) {
+        let player = match self.sessions.player(session_token) {
+            Some(player) => player,
+            None => return,
+        };
+        // The player may have jumped to a sector other than the current one.
+        if let Some(world) = self.game.world_of_mut(player) {
+            world
+                .entry(player)
+                .unwrap()
+                .add_component(InputComponent(input));
+        }
+    }
+
+    fn handle_chat(&mut self, connection_id: ConnectionId, text: &str) {
+        // Ignore chat from connections that haven't said hello yet.
+        let connection = match self.connections.get(&connection_id) {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let limiter = self
+            .chat_limiters
+            .entry(connection.session_token.clone())
+            .or_default();
+        let result = validate_chat_text(text).and_then(|text| {
+            limiter.try_send()?;
+            Ok(text)
+        });
+        match result {
+            Ok(text) => {
+                let name = self.sessions.name(&connection.session_token).unwrap();
+                let message = ChatMessage {
+                    sender: ChatSender::Player {
+                        name: name.to_string(),
+                    },
+                    text,
+                };
+                self.broadcast(ServerMessage::Chat(message));
+            }
+            // Only the sender hears about rejected messages.
+            Err(e) => {
+                let _ = connection
+                    .outbox
+                    .send(ServerMessage::Chat(ChatMessage::system(format!(
+                        "Message not sent: {}",
+                        e
+                    ))));
+            }
+        }
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        for connection in self.connections.values() {
+            let _ = connection.outbox.send(message.clone());
+        }
+    }
+
+    fn step(&mut self, elapsed_seconds: f32) {
+        // Every player is driven by their own `InputComponent`.
+        self.game.step(elapsed_seconds, Input::default());
+
+        // Menus are the clients' business.
+        self.game.gui_override_queue().drain();
+        for event in self.game.game_event_queue().drain() {
+            match event {
+                GameEvent::PlayerDied { player, lives } => {
+                    if let Some(name) = self.sessions.name_of_player(player) {
+                        let text = if lives > 0 {
+                            format!("{} was destroyed", name)
+                        } else {
+                            format!("{} is out of ships", name)
+                        };
+                        self.broadcast(ServerMessage::Chat(ChatMessage::system(text)));
+                    }
+                }
+                GameEvent::InventoryChanged { .. } => (),
+                GameEvent::WaveStarted { wave } => {
+                    self.broadcast(ServerMessage::Chat(ChatMessage::system(format!(
+                        "Wave {}",
+                        wave
+                    ))));
+                }
+                GameEvent::WaveCleared { .. } | GameEvent::WavesLost { .. } => (),
+                GameEvent::JumpStarted { .. } => (),
+                GameEvent::SectorEntered { player, sector } => {
+                    if let Some(name) = self.sessions.name_of_player(player) {
+                        let sector = match self.game.sector_defs().get(&sector) {
+                            Ok(sector) => sector.name.clone(),
+                            Err(_) => sector,
+                        };
+                        self.broadcast(ServerMessage::Chat(ChatMessage::system(format!(
+                            "{} jumped to {}",
+                            name, sector
+                        ))));
+                    }
+                }
+                GameEvent::WavesWon => {
+                    self.broadcast(ServerMessage::Chat(ChatMessage::system(
+                        "All waves cleared",
+                    )));
+                }
+            }
+        }
+
+        let time = Time { elapsed_seconds };
+        for limiter in self.chat_limiters.values_mut() {
+            limiter.step(&time);
+        }
+        for expired in self.sessions.step(&time) {
+            if let Some(world) = self.game.world_of_mut(expired.player) {
+                world.remove(expired.player);
+            }
+            self.chat_limiters.remove(&expired.token);
+            let text = format!("{} left", expired.name);
+            self.broadcast(ServerMessage::Chat(ChatMessage::system(text)));
+        }
+    }
+}